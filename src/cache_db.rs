@@ -2,7 +2,7 @@ use heed::{
     Database, Env, Error,
     types::{Bytes, Str},
 };
-use serde::{Serialize, de::DeserializeOwned};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -14,6 +14,26 @@ pub const DB_RELAYS: &str = "nip65_relays";
 pub const DB_TIMELINE: &str = "timeline_posts";
 pub const DB_IMAGES: &str = "images";
 pub const DB_ARTICLES: &str = "articles";
+pub const DB_DRAFTS: &str = "article_drafts";
+pub const DB_PUBLISH_QUEUE: &str = "publish_queue";
+pub const DB_ARTICLE_HISTORY: &str = "article_history";
+pub const DB_READING_POSITIONS: &str = "reading_positions";
+pub const DB_REACTIONS: &str = "reactions";
+pub const DB_BOOKMARKS: &str = "bookmarks";
+pub const DB_SEARCH_INDEX: &str = "search_index";
+pub const DB_SEARCH_DOCS: &str = "search_docs";
+pub const DB_SETTINGS: &str = "user_settings";
+pub const DB_REPOSTS: &str = "reposted_articles";
+
+// キーとエントリの組
+type CacheEntries<T> = Vec<(String, Cache<T>)>;
+
+// 上書きのときに引き継ぐエントリの属性
+#[derive(Deserialize)]
+struct CacheFlags {
+    #[serde(default)]
+    pinned: bool,
+}
 
 #[derive(Clone)]
 pub struct LmdbCache {
@@ -25,7 +45,7 @@ impl LmdbCache {
         std::fs::create_dir_all(path)?;
         let mut options = heed::EnvOpenOptions::new();
         options.map_size(1024 * 1024 * 1024); // 1 GB
-        options.max_dbs(10);
+        options.max_dbs(20);
         let env = unsafe { options.open(path)? };
 
         let mut txn = env.write_txn()?;
@@ -35,6 +55,16 @@ impl LmdbCache {
         let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_TIMELINE))?;
         let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_IMAGES))?;
         let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_ARTICLES))?;
+        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_DRAFTS))?;
+        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_PUBLISH_QUEUE))?;
+        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_ARTICLE_HISTORY))?;
+        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_READING_POSITIONS))?;
+        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_REACTIONS))?;
+        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_BOOKMARKS))?;
+        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_SEARCH_INDEX))?;
+        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_SEARCH_DOCS))?;
+        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_SETTINGS))?;
+        let _: Database<Str, Bytes> = env.create_database(&mut txn, Some(DB_REPOSTS))?;
         txn.commit()?;
 
         Ok(Self { env: Arc::new(env) })
@@ -54,13 +84,113 @@ impl LmdbCache {
 
         let cache: Cache<T> = serde_json::from_slice(data)?;
 
-        if cache.is_expired() {
+        // 固定されたエントリは期限切れでも返す。新しさは呼び出し側が is_expired で判断する
+        if cache.is_expired() && !cache.pinned {
             Err("Cache expired".into())
         } else {
             Ok(cache)
         }
     }
 
+    // 下書きなど、期限切れで消えてはいけないデータを読み込む
+    pub fn read_persistent_cache<T: DeserializeOwned>(
+        &self,
+        db_name: &str,
+        key: &str,
+    ) -> Result<Cache<T>, Box<dyn std::error::Error + Send + Sync>> {
+        let rtxn = self.env.read_txn()?;
+        let db: Database<Str, Bytes> = self
+            .env
+            .open_database(&rtxn, Some(db_name))?
+            .ok_or("database not found")?;
+        let data = db.get(&rtxn, key)?.ok_or("key not found")?;
+
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    // データベース内のすべてのエントリを有効期限に関係なく読み込む
+    pub fn read_all_cache<T: DeserializeOwned>(
+        &self,
+        db_name: &str,
+    ) -> Result<CacheEntries<T>, Box<dyn std::error::Error + Send + Sync>> {
+        let rtxn = self.env.read_txn()?;
+        let db: Database<Str, Bytes> = self
+            .env
+            .open_database(&rtxn, Some(db_name))?
+            .ok_or("database not found")?;
+
+        let mut entries = Vec::new();
+        for result in db.iter(&rtxn)? {
+            let (key, data) = result?;
+            match serde_json::from_slice::<Cache<T>>(data) {
+                Ok(cache) => entries.push((key.to_string(), cache)),
+                Err(e) => eprintln!("Skipping malformed entry {key} in {db_name}: {e}"),
+            }
+        }
+        Ok(entries)
+    }
+
+    // キーが prefix で始まるエントリを有効期限に関係なく読み込む
+    pub fn read_prefix_cache<T: DeserializeOwned>(
+        &self,
+        db_name: &str,
+        prefix: &str,
+    ) -> Result<CacheEntries<T>, Box<dyn std::error::Error + Send + Sync>> {
+        let rtxn = self.env.read_txn()?;
+        let db: Database<Str, Bytes> = self
+            .env
+            .open_database(&rtxn, Some(db_name))?
+            .ok_or("database not found")?;
+
+        let mut entries = Vec::new();
+        for result in db.prefix_iter(&rtxn, prefix)? {
+            let (key, data) = result?;
+            match serde_json::from_slice::<Cache<T>>(data) {
+                Ok(cache) => entries.push((key.to_string(), cache)),
+                Err(e) => eprintln!("Skipping malformed entry {key} in {db_name}: {e}"),
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn entry_count(&self, db_name: &str) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let rtxn = self.env.read_txn()?;
+        let db: Database<Str, Bytes> = self
+            .env
+            .open_database(&rtxn, Some(db_name))?
+            .ok_or("database not found")?;
+        Ok(db.len(&rtxn)?)
+    }
+
+    // データベースのエントリをすべて削除する
+    pub fn clear_cache(&self, db_name: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut wtxn = self.env.write_txn()?;
+        let db: Database<Str, Bytes> = self
+            .env
+            .open_database(&wtxn, Some(db_name))?
+            .ok_or("database not found")?;
+        db.clear(&mut wtxn)?;
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    pub fn delete_cache(
+        &self,
+        db_name: &str,
+        key: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut wtxn = self.env.write_txn()?;
+        let db: Database<Str, Bytes> = self
+            .env
+            .open_database(&wtxn, Some(db_name))?
+            .ok_or("database not found")?;
+        db.delete(&mut wtxn, key)?;
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
     pub fn write_cache<T: Serialize>(
         &self,
         db_name: &str,
@@ -72,7 +202,70 @@ impl LmdbCache {
             .env
             .open_database(&wtxn, Some(db_name))?
             .ok_or("database not found")?;
-        let cache = Cache::new(data);
+        let mut cache = Cache::new(data);
+        // 固定されたエントリは上書きしても固定のままにする
+        if let Some(existing) = db.get(&wtxn, key)? {
+            cache.pinned = serde_json::from_slice::<CacheFlags>(existing).is_ok_and(|flags| flags.pinned);
+        }
+        let serialized_data = serde_json::to_vec(&cache)?;
+
+        db.put(&mut wtxn, key, &serialized_data)?;
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    // 複数のエントリを一つのトランザクションで書き込む (None のエントリは削除する)
+    pub fn write_cache_batch<T: Serialize>(
+        &self,
+        db_name: &str,
+        entries: &[(String, Option<T>)],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut wtxn = self.env.write_txn()?;
+        let db: Database<Str, Bytes> = self
+            .env
+            .open_database(&wtxn, Some(db_name))?
+            .ok_or("database not found")?;
+        for (key, data) in entries {
+            match data {
+                Some(data) => {
+                    let serialized_data = serde_json::to_vec(&Cache::new(data))?;
+                    db.put(&mut wtxn, key, &serialized_data)?;
+                }
+                None => {
+                    db.delete(&mut wtxn, key)?;
+                }
+            }
+        }
+        wtxn.commit()?;
+
+        Ok(())
+    }
+
+    // エントリを期限切れで消えないよう固定する (または固定を外す)。エントリがなければ何もしない
+    pub fn set_pinned(
+        &self,
+        db_name: &str,
+        key: &str,
+        pinned: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut wtxn = self.env.write_txn()?;
+        let db: Database<Str, Bytes> = self
+            .env
+            .open_database(&wtxn, Some(db_name))?
+            .ok_or("database not found")?;
+        let Some(existing) = db.get(&wtxn, key)? else {
+            return Ok(());
+        };
+        let mut cache: Cache<serde_json::Value> = serde_json::from_slice(existing)?;
+        if cache.pinned == pinned {
+            return Ok(());
+        }
+        cache.pinned = pinned;
+        // 固定を外したときは、今から 24 時間で期限切れになる
+        if !pinned {
+            cache.timestamp = chrono::Utc::now();
+        }
         let serialized_data = serde_json::to_vec(&cache)?;
 
         db.put(&mut wtxn, key, &serialized_data)?;
@@ -1,6 +1,10 @@
 mod cache_db;
+mod markdown_io;
+mod media_upload;
+mod nip23;
 mod nip49;
 mod nostr_client;
+mod search_index;
 mod ui;
 mod types;
 
@@ -16,8 +20,8 @@ use regex::Regex;
 mod theme;
 
 use crate::cache_db::{LmdbCache, DB_FOLLOWED, DB_PROFILES, DB_RELAYS};
+use crate::nostr_client::DEFAULT_SEARCH_RELAYS;
 use crate::types::*;
-use egui_commonmark::CommonMarkCache;
 
 
 const CONFIG_FILE: &str = "config.json"; // 設定ファイル名
@@ -84,6 +88,62 @@ async fn migrate_data_from_files(
     Ok(())
 }
 
+// 予約投稿キューを確認する間隔
+const PUBLISH_QUEUE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15);
+
+// 公開時刻を過ぎた予約投稿をリレーに送信する
+async fn process_publish_queue(data: &Arc<Mutex<NostrStatusAppInternal>>) -> bool {
+    let (cache_db, client, my_metadata, due) = {
+        let app_data = data.lock().unwrap();
+        let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.as_ref()) else {
+            return false;
+        };
+        let now = nostr::Timestamp::now();
+        let due: Vec<ScheduledArticle> = app_data
+            .publish_queue
+            .iter()
+            .filter(|item| item.is_due(now) && item.event.pubkey == keys.public_key())
+            .cloned()
+            .collect();
+        (app_data.cache_db.clone(), client, app_data.editable_profile.clone(), due)
+    };
+    if due.is_empty() {
+        return false;
+    }
+
+    for mut item in due {
+        let key = item.coordinate_key();
+        match nostr_client::send_scheduled_article(&client, &item).await {
+            Ok(()) => {
+                println!("Scheduled article published: {key}");
+                let article = nostr_client::article_from_event(&item.event, my_metadata.clone());
+                nostr_client::store_article_with_history(&cache_db, &article);
+                let mut app_data = data.lock().unwrap();
+                // 送信中に取り消し・変更された項目は、新しい方をキューに残す
+                if app_data.publish_queue.iter().any(|queued| queued.event.id == item.event.id) {
+                    nostr_client::remove_scheduled_article(&cache_db, &key);
+                    app_data.publish_queue.retain(|queued| queued.event.id != item.event.id);
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to publish scheduled article {key}: {e}");
+                nostr_client::schedule_retry(&mut item, e.to_string());
+                let mut app_data = data.lock().unwrap();
+                // 送信中に取り消し・変更された項目は上書きしない
+                if let Some(queued) = app_data
+                    .publish_queue
+                    .iter_mut()
+                    .find(|queued| queued.event.id == item.event.id)
+                {
+                    nostr_client::save_scheduled_article(&cache_db, &item);
+                    *queued = item;
+                }
+            }
+        }
+    }
+    true
+}
+
 // eframe::Appトレイトを実装する構造体
 pub struct NostrStatusApp {
     data: Arc<Mutex<NostrStatusAppInternal>>,
@@ -160,6 +220,7 @@ impl NostrStatusApp {
 
         let lmdb_cache =
             LmdbCache::new(Path::new(DB_PATH)).expect("Failed to initialize LMDB cache");
+        search_index::start_indexer(lmdb_cache.clone());
 
         let app_data_internal = NostrStatusAppInternal {
             nwc_uri_input: String::new(),
@@ -167,6 +228,15 @@ impl NostrStatusApp {
             is_logged_in: false,
             article_title_input: String::new(),
             article_content_input: String::new(),
+            article_summary_input: String::new(),
+            article_image_input: String::new(),
+            article_hashtags_input: String::new(),
+            editing_article_identifier: None,
+            is_editing_published_article: false,
+            editing_article_published_at: None,
+            article_media_tags: Vec::new(),
+            schedule_article: false,
+            schedule_time_input: String::new(),
             show_post_dialog: false,
             secret_key_input: String::new(),
             passphrase_input: String::new(),
@@ -176,11 +246,28 @@ impl NostrStatusApp {
             followed_pubkeys: HashSet::new(),
             followed_pubkeys_display: String::new(),
             timeline_posts: Vec::new(),
+            is_loading_older_posts: false,
+            timeline_exhausted: false,
+            timeline_gap: false,
+            timeline_status: String::new(),
+            pending_posts: Vec::new(),
+            timeline_subscription: None,
+            timeline_stream_authors: HashSet::new(),
             should_repaint: false,
             is_loading: false,
             current_tab: AppTab::Home,
             current_profile_sub_view: ProfileSubView::Profile,
             selected_label: None,
+            interests: Vec::new(),
+            interests_loaded: false,
+            is_loading_interests: false,
+            interests_load_failed: false,
+            interests_list_missing: false,
+            interest_input: String::new(),
+            is_saving_interests: false,
+            label_posts: Vec::new(),
+            is_loading_label_posts: false,
+            label_status: String::new(),
             connected_relays_display: String::new(),
             nip01_profile_display: String::new(), // ここを初期化
             editable_profile: ProfileMetadata::default(), // 編集可能なプロファイルデータ
@@ -202,10 +289,96 @@ impl NostrStatusApp {
             show_zap_dialog: false,
             zap_amount_input: String::new(),
             zap_target_post: None,
-            viewing_article_id: None,
+            viewing_article_coordinate: None,
             viewing_article: None,
+            confirm_delete_article: false,
+            show_article_history: false,
+            article_history: Vec::new(),
+            history_compare: [0, 0],
+            is_fetching_history: false,
+            show_article_outline: true,
+            current_heading: None,
+            scroll_to_heading: None,
+            reading_state: None,
+            article_highlights: Vec::new(),
+            highlights_article_key: None,
+            is_selecting_highlight: false,
+            highlight_comment_input: String::new(),
+            is_publishing_highlight: false,
+            highlight_status: String::new(),
+            reaction_summaries: HashMap::new(),
+            requested_reactions: HashSet::new(),
+            reposted_articles: HashSet::new(),
+            bookmarks: Vec::new(),
+            is_syncing_bookmarks: false,
+            bookmarks_synced: false,
+            bookmark_list_missing: false,
+            bookmark_status: String::new(),
+            reading_list_unread_only: false,
+            reading_list_posts: HashMap::new(),
+            requested_reading_list_posts: HashSet::new(),
+            search_query: String::new(),
+            search_submitted_query: String::new(),
+            search_results: Vec::new(),
+            is_searching: false,
+            search_mode: SearchMode::Local,
+            search_relays_editor: DEFAULT_SEARCH_RELAYS.to_string(),
+            search_relays_loaded: false,
+            search_relays_status: String::new(),
+            relay_search_articles: Vec::new(),
+            relay_search_profiles: Vec::new(),
+            search_status: String::new(),
+            article_comments: Vec::new(),
+            comments_article_key: None,
+            is_loading_comments: false,
+            comment_input: String::new(),
+            reply_target: None,
+            reply_input: String::new(),
+            is_posting_comment: false,
+            comment_status: String::new(),
+            nostr_ref_profiles: HashMap::new(),
+            nostr_ref_events: HashMap::new(),
+            requested_nostr_refs: HashSet::new(),
+            missing_nostr_refs: HashSet::new(),
+            rendered_contents: HashMap::new(),
+            viewing_profile: None,
+            viewing_profile_articles: None,
+            is_loading_user_profile: false,
+            article_editor_mode: ArticleEditorMode::Edit,
+            editor_scroll_leader: EditorPane::Editor,
+            editor_scroll_fraction: 0.0,
+            editor_scroll_max: [0.0, 0.0],
+            editor_cursor: None,
+            mention_candidates: None,
+            mention_selected: 0,
+            mention_dismissed_at: None,
+            show_article_link_picker: false,
+            article_link_query: String::new(),
+            article_link_candidates: Vec::new(),
+            drafts: Vec::new(),
+            sync_drafts_to_relays: false,
+            draft_status: String::new(),
+            draft_last_edit: None,
+            draft_last_relay_sync: None,
+            editor_draft_at_open: None,
+            show_import_dialog: false,
+            import_items: Vec::new(),
+            import_preview_index: None,
+            import_status: String::new(),
+            show_export_dialog: false,
+            export_static_site: false,
+            is_exporting: false,
+            export_status: String::new(),
+            publish_queue: Vec::new(),
+            reschedule_inputs: HashMap::new(),
+            publish_queue_status: String::new(),
+            media_server_kind: MediaServerKind::Nip96,
+            media_server_url: "https://nostr.build".to_string(),
+            is_uploading_media: false,
+            media_upload_status: String::new(),
+            uploaded_media: Vec::new(),
+            editor_session: 0,
             show_profile_menu: false,
-            commonmark_cache: CommonMarkCache::default(),
         };
         let data = Arc::new(Mutex::new(app_data_internal));
 
@@ -237,6 +410,18 @@ impl NostrStatusApp {
             app_data.should_repaint = true;
         });
 
+        // 予約投稿の送信ループ
+        let queue_data = data.clone();
+        let egui_ctx = _cc.egui_ctx.clone();
+        runtime_handle.spawn(async move {
+            loop {
+                tokio::time::sleep(PUBLISH_QUEUE_INTERVAL).await;
+                if process_publish_queue(&queue_data).await {
+                    egui_ctx.request_repaint();
+                }
+            }
+        });
+
         Self { data, runtime }
     }
 }
@@ -0,0 +1,470 @@
+use nostr::{nips::nip19::{Nip19Coordinate, ToBech32}, EventId, Timestamp};
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::nip23::{self, ArticleMetadata};
+use crate::types::ArticleFull;
+use crate::ui::publish_queue_view::{local_timestamp, parse_local_time};
+
+// TOML の日時は serde 経由だとこのキーを持つオブジェクトになる
+const TOML_DATETIME_KEY: &str = "$__toml_private_datetime";
+
+/// Splits a Markdown document into its front matter and body.
+/// YAML front matter is fenced with `---`, TOML front matter with `+++`.
+fn split_front_matter(text: &str) -> Result<(Value, &str), Box<dyn std::error::Error + Send + Sync>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let first_line_end = text.find('\n').map_or(text.len(), |i| i + 1);
+    let fence = text[..first_line_end].trim_end();
+    if fence != "---" && fence != "+++" {
+        return Ok((Value::Null, text));
+    }
+
+    let rest = &text[first_line_end..];
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == fence {
+            let raw = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            let value = if fence == "---" {
+                serde_json::to_value(serde_yaml::from_str::<serde_yaml::Value>(raw)?)?
+            } else {
+                serde_json::to_value(toml::from_str::<toml::Value>(raw)?)?
+            };
+            return Ok((value, body));
+        }
+        offset += line.len();
+    }
+    Err("フロントマターが閉じられていません".into())
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Object(map) => map.get(TOML_DATETIME_KEY).and_then(value_to_string),
+        _ => None,
+    }
+    .filter(|s| !s.is_empty())
+}
+
+// 別名のキーも含めて最初に見つかった値を文字列で返す
+fn string_field(front_matter: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| front_matter.get(*key).and_then(value_to_string))
+}
+
+// tags は配列でもカンマ区切りの文字列でもよい
+fn tags_field(front_matter: &Value) -> Vec<String> {
+    let input = ["tags", "hashtags", "categories"]
+        .iter()
+        .filter_map(|key| front_matter.get(*key))
+        .map(|value| match value {
+            Value::Array(items) => items.iter().filter_map(value_to_string).collect::<Vec<_>>().join(","),
+            other => value_to_string(other).unwrap_or_default(),
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    nip23::parse_hashtags(&input)
+}
+
+/// Parses `published_at` / `date` values: a unix timestamp, RFC 3339, or a local
+/// `YYYY-MM-DD[ HH:MM[:SS]]` date.
+pub fn parse_date(value: &str) -> Option<Timestamp> {
+    let value = value.trim();
+    if let Some(timestamp) = parse_local_time(value) {
+        return Some(timestamp);
+    }
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Timestamp::from(secs));
+    }
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
+        return u64::try_from(dt.timestamp()).ok().map(Timestamp::from);
+    }
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|format| chrono::NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    local_timestamp(naive)
+}
+
+// 本文の先頭が `# 見出し` ならタイトルとして取り出す
+fn take_heading_title(body: &str) -> Option<(String, &str)> {
+    let trimmed = body.trim_start_matches(['\r', '\n']);
+    let first_line_end = trimmed.find('\n').map_or(trimmed.len(), |i| i + 1);
+    let title = trimmed[..first_line_end].trim().strip_prefix("# ")?.trim().to_string();
+    Some((title, &trimmed[first_line_end..]))
+}
+
+/// Maps a Markdown document onto NIP-23 metadata and content.
+/// `slug` becomes the `d` tag, falling back to `fallback_identifier` (the file
+/// name), so importing the same file again replaces the same article.
+pub fn parse_markdown_article(
+    text: &str,
+    fallback_identifier: &str,
+) -> Result<(ArticleMetadata, String), Box<dyn std::error::Error + Send + Sync>> {
+    let (front_matter, mut body) = split_front_matter(text)?;
+
+    let mut title = string_field(&front_matter, &["title"]);
+    if title.is_none() {
+        if let Some((heading, rest)) = take_heading_title(body) {
+            title = Some(heading);
+            body = rest;
+        }
+    }
+
+    let identifier = string_field(&front_matter, &["slug", "d", "identifier"])
+        .unwrap_or_else(|| fallback_identifier.trim().to_string());
+    if identifier.is_empty() {
+        return Err("記事の識別子 (slug) を決められません".into());
+    }
+
+    let metadata = ArticleMetadata {
+        title: title.unwrap_or_else(|| identifier.clone()),
+        identifier,
+        summary: string_field(&front_matter, &["summary", "description"]).unwrap_or_default(),
+        image: string_field(&front_matter, &["image", "cover", "banner"]).unwrap_or_default(),
+        published_at: string_field(&front_matter, &["published_at", "date"]).and_then(|date| parse_date(&date)),
+        hashtags: tags_field(&front_matter),
+        media: Vec::new(),
+    };
+    Ok((metadata, body.trim().to_string()))
+}
+
+/// Returns the `.md` files at `path`: the file itself, or every Markdown file
+/// under a folder (recursively, sorted by path).
+pub fn collect_markdown_files(path: &Path) -> Vec<PathBuf> {
+    if path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+    let mut files = Vec::new();
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let entry_path = entry.path();
+            if entry_path.is_dir() {
+                dirs.push(entry_path);
+            } else if entry_path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"))
+            {
+                files.push(entry_path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+// --- 書き出し ---
+
+#[derive(Debug, Serialize)]
+struct ExportFrontMatter {
+    title: String,
+    slug: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    summary: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    image: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    published_at: Option<String>,
+    updated_at: String,
+    nostr: ExportNostrMetadata,
+}
+
+#[derive(Debug, Serialize)]
+struct ExportNostrMetadata {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    naddr: String,
+    event_id: String,
+    author: String,
+    kind: u16,
+}
+
+fn format_rfc3339(timestamp: Timestamp) -> String {
+    chrono::DateTime::<chrono::Utc>::from_timestamp(timestamp.as_u64() as i64, 0)
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_default()
+}
+
+fn format_date(timestamp: Timestamp) -> String {
+    chrono::DateTime::<chrono::Utc>::from_timestamp(timestamp.as_u64() as i64, 0)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Renders an article as Markdown with YAML front matter. The front matter uses
+/// the same keys the importer reads, so an exported file can be imported again.
+pub fn article_to_markdown(article: &ArticleFull) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let metadata = article.metadata();
+    let front_matter = ExportFrontMatter {
+        title: article.title.clone(),
+        slug: article.identifier.clone(),
+        summary: metadata.summary,
+        image: metadata.image,
+        tags: metadata.hashtags,
+        published_at: metadata.published_at.map(format_rfc3339),
+        updated_at: format_rfc3339(article.created_at),
+        nostr: ExportNostrMetadata {
+            naddr: Nip19Coordinate::new(article.coordinate(), Vec::new()).to_bech32().unwrap_or_default(),
+            event_id: article.id.to_hex(),
+            author: article.author_pubkey.to_bech32().unwrap_or_default(),
+            kind: article.kind.as_u16(),
+        },
+    };
+    Ok(format!("---\n{}---\n\n{}\n", serde_yaml::to_string(&front_matter)?, article.content.trim_end()))
+}
+
+// 識別子からファイル名として安全な名前を作る
+fn file_stem_for(article: &ArticleFull) -> String {
+    let stem: String = article
+        .identifier
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    let stem = stem.trim_matches('-');
+    if stem.is_empty() {
+        article.id.to_hex()
+    } else {
+        stem.to_string()
+    }
+}
+
+// 記事ごとのファイル名。変換後の名前が (大文字小文字を除いて) 重なる記事には
+// 識別子のハッシュを付けて、どの順番で書き出しても同じ名前になるようにする
+fn file_stems(articles: &[ArticleFull]) -> HashMap<EventId, String> {
+    let stems: Vec<String> = articles.iter().map(file_stem_for).collect();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for stem in &stems {
+        *counts.entry(stem.to_lowercase()).or_default() += 1;
+    }
+    articles
+        .iter()
+        .zip(stems)
+        .map(|(article, stem)| {
+            let stem = if counts[&stem.to_lowercase()] > 1 {
+                let hash = hex::encode(Sha256::digest(article.identifier.as_bytes()));
+                format!("{}-{}", stem, &hash[..8])
+            } else {
+                stem
+            };
+            (article.id, stem)
+        })
+        .collect()
+}
+
+/// Writes one `<slug>.md` file per article into `dir`. Slugs that map to the same
+/// file name get a short hash of the `d` tag appended.
+pub fn export_markdown(dir: &Path, articles: &[ArticleFull]) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    std::fs::create_dir_all(dir)?;
+    let stems = file_stems(articles);
+    for article in articles {
+        let path = dir.join(format!("{}.md", stems[&article.id]));
+        std::fs::write(path, article_to_markdown(article)?)?;
+    }
+    Ok(articles.len())
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const SITE_STYLE: &str = "body{max-width:720px;margin:40px auto;padding:0 16px;font-family:sans-serif;line-height:1.7;color:#222}\
+a{color:#4a5fc1}img{max-width:100%}pre{background:#f4f4f4;padding:12px;overflow-x:auto}\
+.meta{color:#777;font-size:.9em}.summary{color:#555;font-style:italic}ul.articles{list-style:none;padding:0}\
+ul.articles li{margin-bottom:24px}\n";
+
+fn html_page(title: &str, stylesheet: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>{}</title>\n<link rel=\"stylesheet\" href=\"{}\">\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        stylesheet,
+        body
+    )
+}
+
+/// Writes a static site into `dir`: `index.html` listing every article (newest
+/// first) and `articles/<slug>.html` for each one.
+pub fn export_static_site(
+    dir: &Path,
+    site_title: &str,
+    articles: &[ArticleFull],
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let articles_dir = dir.join("articles");
+    std::fs::create_dir_all(&articles_dir)?;
+    std::fs::write(dir.join("style.css"), SITE_STYLE)?;
+
+    let stems = file_stems(articles);
+    let mut sorted: Vec<&ArticleFull> = articles.iter().collect();
+    sorted.sort_by_key(|a| std::cmp::Reverse(a.metadata().published_at.unwrap_or(a.created_at)));
+
+    let mut index_items = String::new();
+    for article in &sorted {
+        let metadata = article.metadata();
+        let stem = &stems[&article.id];
+        let date = format_date(metadata.published_at.unwrap_or(article.created_at));
+
+        let mut content_html = String::new();
+        pulldown_cmark::html::push_html(
+            &mut content_html,
+            pulldown_cmark::Parser::new_ext(&article.content, pulldown_cmark::Options::all()),
+        );
+        let mut body = String::from("<p><a href=\"../index.html\">← 一覧へ</a></p>\n");
+        body.push_str(&format!("<h1>{}</h1>\n", escape_html(&article.title)));
+        body.push_str(&format!("<p class=\"meta\">{date}</p>\n"));
+        if !metadata.image.is_empty() {
+            body.push_str(&format!("<img src=\"{}\" alt=\"\">\n", escape_html(&metadata.image)));
+        }
+        body.push_str(&content_html);
+        std::fs::write(
+            articles_dir.join(format!("{stem}.html")),
+            html_page(&article.title, "../style.css", &body),
+        )?;
+
+        index_items.push_str(&format!(
+            "<li><a href=\"articles/{}.html\">{}</a><div class=\"meta\">{}</div>",
+            escape_html(stem),
+            escape_html(&article.title),
+            date
+        ));
+        if !metadata.summary.is_empty() {
+            index_items.push_str(&format!("<div class=\"summary\">{}</div>", escape_html(&metadata.summary)));
+        }
+        index_items.push_str("</li>\n");
+    }
+
+    let index_body = format!(
+        "<h1>{}</h1>\n<ul class=\"articles\">\n{}</ul>\n",
+        escape_html(site_title),
+        index_items
+    );
+    std::fs::write(dir.join("index.html"), html_page(site_title, "style.css", &index_body))?;
+    Ok(sorted.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{Keys, Kind};
+
+    fn test_article(identifier: &str, id_byte: u8) -> ArticleFull {
+        ArticleFull {
+            id: EventId::from_byte_array([id_byte; 32]),
+            kind: Kind::from(30023),
+            author_pubkey: Keys::generate().public_key(),
+            author_metadata: Default::default(),
+            title: identifier.to_string(),
+            content: String::new(),
+            created_at: Timestamp::from(1_700_000_000),
+            tags: Vec::new(),
+            identifier: identifier.to_string(),
+        }
+    }
+
+    #[test]
+    fn reads_yaml_and_toml_front_matter() {
+        let yaml = "---\ntitle: YAML の記事\nslug: yaml-post\ntags: [Rust, nostr]\n---\n本文\n";
+        let (metadata, content) = parse_markdown_article(yaml, "file").unwrap();
+        assert_eq!(metadata.title, "YAML の記事");
+        assert_eq!(metadata.identifier, "yaml-post");
+        assert_eq!(metadata.hashtags, vec!["rust", "nostr"]);
+        assert_eq!(content, "本文");
+
+        let toml = "+++\ntitle = \"TOML の記事\"\nslug = \"toml-post\"\ndate = 2024-01-02T03:04:05Z\n+++\n本文\n";
+        let (metadata, content) = parse_markdown_article(toml, "file").unwrap();
+        assert_eq!(metadata.title, "TOML の記事");
+        assert_eq!(metadata.identifier, "toml-post");
+        assert_eq!(metadata.published_at, Some(Timestamp::from(1_704_164_645)));
+        assert_eq!(content, "本文");
+    }
+
+    #[test]
+    fn skips_a_byte_order_mark() {
+        let text = "\u{feff}---\ntitle: BOM\n---\n本文";
+        let (metadata, content) = parse_markdown_article(text, "file").unwrap();
+        assert_eq!(metadata.title, "BOM");
+        assert_eq!(content, "本文");
+    }
+
+    #[test]
+    fn rejects_unclosed_front_matter() {
+        assert!(split_front_matter("---\ntitle: x\n本文").is_err());
+    }
+
+    #[test]
+    fn takes_the_title_from_a_leading_heading() {
+        let (metadata, content) = parse_markdown_article("\n# 見出しのタイトル\n\n本文", "file").unwrap();
+        assert_eq!(metadata.title, "見出しのタイトル");
+        assert_eq!(content, "本文");
+
+        // フロントマターに title があれば見出しは本文に残す
+        let (metadata, content) = parse_markdown_article("---\ntitle: 題名\n---\n# 見出し\n本文", "file").unwrap();
+        assert_eq!(metadata.title, "題名");
+        assert_eq!(content, "# 見出し\n本文");
+    }
+
+    #[test]
+    fn falls_back_to_the_file_stem_for_the_identifier() {
+        // slug がなければ同じファイルは同じ d タグになり、取り込み直すと更新になる
+        let (first, _) = parse_markdown_article("本文", "my-post").unwrap();
+        let (second, _) = parse_markdown_article("書き直した本文", "my-post").unwrap();
+        assert_eq!(first.identifier, "my-post");
+        assert_eq!(first.identifier, second.identifier);
+        assert_eq!(first.title, "my-post");
+
+        assert!(parse_markdown_article("本文", "  ").is_err());
+    }
+
+    #[test]
+    fn reads_tags_from_arrays_and_strings() {
+        let front_matter = serde_json::json!({ "tags": "Rust, #Nostr 日記", "categories": ["Rust", "blog"] });
+        assert_eq!(tags_field(&front_matter), vec!["rust", "nostr", "日記", "blog"]);
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(parse_date("1700000000"), Some(Timestamp::from(1_700_000_000)));
+        assert_eq!(parse_date("2024-01-02T03:04:05+09:00"), Some(Timestamp::from(1_704_132_245)));
+        assert_eq!(parse_date("2024-01-02 03:04"), parse_local_time("2024-01-02 03:04"));
+        let midnight = chrono::NaiveDate::from_ymd_opt(2024, 1, 2).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        assert_eq!(parse_date("2024-01-02"), local_timestamp(midnight));
+        assert_eq!(parse_date("not a date"), None);
+    }
+
+    #[test]
+    fn hashes_file_names_that_collide_ignoring_case() {
+        let articles = vec![test_article("Hello", 1), test_article("hello", 2), test_article("other", 3)];
+        let stems = file_stems(&articles);
+        let first = &stems[&articles[0].id];
+        let second = &stems[&articles[1].id];
+        assert!(first.starts_with("Hello-") && second.starts_with("hello-"));
+        assert_ne!(first.to_lowercase(), second.to_lowercase());
+        assert_eq!(stems[&articles[2].id], "other");
+
+        // 書き出す順番が変わっても同じ名前になる
+        let reversed: Vec<ArticleFull> = articles.iter().rev().cloned().collect();
+        assert_eq!(file_stems(&reversed), stems);
+    }
+}
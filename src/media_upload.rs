@@ -0,0 +1,483 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use nostr::{EventBuilder, JsonUtil, Keys, Kind, Tag, Timestamp};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::types::MediaServerKind;
+
+/// The result of an upload, used to build the Markdown link and the `imeta` tag.
+#[derive(Debug, Clone)]
+pub struct UploadedMedia {
+    pub url: String,
+    pub mime_type: String,
+    pub sha256: String,
+    pub dimensions: Option<(u32, u32)>,
+}
+
+impl UploadedMedia {
+    pub fn markdown(&self, alt: &str) -> String {
+        format!("![{}]({})", alt, self.url)
+    }
+
+    /// NIP-92 `imeta` tag values
+    pub fn imeta_tag(&self) -> Vec<String> {
+        let mut values = vec![
+            "imeta".to_string(),
+            format!("url {}", self.url),
+            format!("m {}", self.mime_type),
+            format!("x {}", self.sha256),
+        ];
+        if let Some((width, height)) = self.dimensions {
+            values.push(format!("dim {width}x{height}"));
+        }
+        values
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Nip96ServerInfo {
+    api_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Nip96Response {
+    status: String,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    processing_url: Option<String>,
+    #[serde(default)]
+    nip94_event: Option<Nip94Event>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Nip94Event {
+    tags: Vec<Vec<String>>,
+}
+
+impl Nip94Event {
+    fn tag_value(&self, name: &str) -> Option<String> {
+        self.tags
+            .iter()
+            .find(|tag| tag.first().map(|s| s.as_str()) == Some(name))
+            .and_then(|tag| tag.get(1).cloned())
+    }
+}
+
+// Blossom (BUD-02) のアップロードレスポンス
+#[derive(Debug, Serialize, Deserialize)]
+struct BlobDescriptor {
+    url: String,
+    sha256: String,
+    #[serde(default, rename = "type")]
+    mime_type: Option<String>,
+}
+
+pub fn mime_type_from_path(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    image::ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+// イベントを `Authorization: Nostr <base64>` ヘッダーの値に変換する
+fn authorization_header(event: &nostr::Event) -> String {
+    format!("Nostr {}", general_purpose::STANDARD.encode(event.as_json()))
+}
+
+/// Uploads a file to the configured media server and returns its public URL.
+/// NIP-96 servers are authorized with a NIP-98 HTTP auth event, Blossom servers
+/// with a BUD-02 kind 24242 authorization event; both are signed with `keys`.
+pub async fn upload_file(
+    server_kind: MediaServerKind,
+    server_url: &str,
+    keys: &Keys,
+    path: &Path,
+) -> Result<UploadedMedia> {
+    let data = tokio::fs::read(path).await?;
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("upload")
+        .to_string();
+    let mime_type = mime_type_from_path(path).to_string();
+    let server_url = server_url.trim().trim_end_matches('/').to_string();
+    if server_url.is_empty() {
+        return Err(anyhow!("アップロード先のサーバーが設定されていません"));
+    }
+
+    match server_kind {
+        MediaServerKind::Nip96 => upload_nip96(&server_url, keys, file_name, mime_type, data).await,
+        MediaServerKind::Blossom => upload_blossom(&server_url, keys, file_name, mime_type, data).await,
+    }
+}
+
+async fn upload_nip96(
+    server_url: &str,
+    keys: &Keys,
+    file_name: String,
+    mime_type: String,
+    data: Vec<u8>,
+) -> Result<UploadedMedia> {
+    // 1. Discover the upload endpoint
+    let info_url = format!("{server_url}/.well-known/nostr/nip96.json");
+    let server_info: Nip96ServerInfo = tokio::task::spawn_blocking(move || -> Result<Nip96ServerInfo> {
+        let res = ureq::get(&info_url).call().map_err(|e| anyhow!(e))?;
+        res.into_json().map_err(|e| anyhow!(e))
+    })
+    .await??;
+    if server_info.api_url.is_empty() {
+        return Err(anyhow!("サーバーがNIP-96のapi_urlを公開していません"));
+    }
+
+    // 2. Build the multipart body
+    let boundary = format!("----nostr-upload-{}", hex::encode(rand::random::<[u8; 8]>()));
+    let mut body = Vec::new();
+    body.extend_from_slice(
+        format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\nContent-Type: {mime_type}\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(&data);
+    body.extend_from_slice(
+        format!(
+            "\r\n--{boundary}\r\nContent-Disposition: form-data; name=\"content_type\"\r\n\r\n{mime_type}\r\n--{boundary}--\r\n"
+        )
+        .as_bytes(),
+    );
+
+    // 3. NIP-98 HTTP auth
+    let auth_event = EventBuilder::new(Kind::HttpAuth, "")
+        .tags([
+            Tag::parse(["u", server_info.api_url.as_str()])?,
+            Tag::parse(["method", "POST"])?,
+            Tag::parse(["payload", sha256_hex(&body).as_str()])?,
+        ])
+        .sign(keys)
+        .await?;
+    let authorization = authorization_header(&auth_event);
+
+    // 4. Upload
+    let api_url = server_info.api_url.clone();
+    let content_type = format!("multipart/form-data; boundary={boundary}");
+    let response: Nip96Response = tokio::task::spawn_blocking(move || -> Result<Nip96Response> {
+        let res = ureq::post(&api_url)
+            .set("Authorization", &authorization)
+            .set("Content-Type", &content_type)
+            .send_bytes(&body)
+            .map_err(|e| anyhow!(e))?;
+        res.into_json().map_err(|e| anyhow!(e))
+    })
+    .await??;
+
+    let response = match (response.nip94_event.is_none(), response.processing_url.clone()) {
+        (true, Some(processing_url)) => wait_for_nip96_processing(processing_url).await?,
+        _ => response,
+    };
+    if response.status == "error" {
+        return Err(anyhow!(response.message.unwrap_or_else(|| "アップロードに失敗しました".to_string())));
+    }
+
+    let nip94_event = response
+        .nip94_event
+        .ok_or_else(|| anyhow!("サーバーの応答にnip94_eventが含まれていません"))?;
+    let url = nip94_event
+        .tag_value("url")
+        .ok_or_else(|| anyhow!("サーバーの応答にURLが含まれていません"))?;
+    let dimensions = nip94_event
+        .tag_value("dim")
+        .and_then(|dim| {
+            let (width, height) = dim.split_once('x')?;
+            Some((width.parse().ok()?, height.parse().ok()?))
+        })
+        .or_else(|| image_dimensions(&data));
+
+    Ok(UploadedMedia {
+        url,
+        mime_type: nip94_event.tag_value("m").unwrap_or(mime_type),
+        sha256: nip94_event.tag_value("x").unwrap_or_else(|| sha256_hex(&data)),
+        dimensions,
+    })
+}
+
+// サーバー側で変換処理中の場合は完了するまで待つ
+async fn wait_for_nip96_processing(processing_url: String) -> Result<Nip96Response> {
+    for _ in 0..10 {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        let url = processing_url.clone();
+        let response: Nip96Response = tokio::task::spawn_blocking(move || -> Result<Nip96Response> {
+            let res = ureq::get(&url).call().map_err(|e| anyhow!(e))?;
+            res.into_json().map_err(|e| anyhow!(e))
+        })
+        .await??;
+        if response.nip94_event.is_some() || response.status == "error" {
+            return Ok(response);
+        }
+    }
+    Err(anyhow!("アップロードした画像の処理がタイムアウトしました"))
+}
+
+async fn upload_blossom(
+    server_url: &str,
+    keys: &Keys,
+    file_name: String,
+    mime_type: String,
+    data: Vec<u8>,
+) -> Result<UploadedMedia> {
+    let sha256 = sha256_hex(&data);
+    let expiration = Timestamp::now().as_u64() + 300;
+
+    // BUD-02 authorization event
+    let auth_event = EventBuilder::new(Kind::from(24242), format!("Upload {file_name}"))
+        .tags([
+            Tag::parse(["t", "upload"])?,
+            Tag::parse(["x", sha256.as_str()])?,
+            Tag::parse(["expiration", expiration.to_string().as_str()])?,
+        ])
+        .sign(keys)
+        .await?;
+    let authorization = authorization_header(&auth_event);
+
+    let upload_url = format!("{server_url}/upload");
+    let content_type = mime_type.clone();
+    let dimensions = image_dimensions(&data);
+    let descriptor: BlobDescriptor = tokio::task::spawn_blocking(move || -> Result<BlobDescriptor> {
+        let res = ureq::put(&upload_url)
+            .set("Authorization", &authorization)
+            .set("Content-Type", &content_type)
+            .send_bytes(&data)
+            .map_err(|e| anyhow!(e))?;
+        res.into_json().map_err(|e| anyhow!(e))
+    })
+    .await??;
+
+    if descriptor.sha256 != sha256 {
+        return Err(anyhow!("サーバーが返したハッシュがファイルと一致しません"));
+    }
+
+    Ok(UploadedMedia {
+        url: descriptor.url,
+        mime_type: descriptor.mime_type.unwrap_or(mime_type),
+        sha256,
+        dimensions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::Event;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    // モックサーバーが受け取ったリクエスト
+    struct MockRequest {
+        method: String,
+        path: String,
+        authorization: Option<String>,
+        body: Vec<u8>,
+    }
+
+    // リクエストごとに `respond` の返した JSON を返す HTTP サーバーを起動し、そのベース URL を返す
+    async fn start_mock_server<F>(respond: F) -> String
+    where
+        F: Fn(&MockRequest, &str) -> String + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let respond = std::sync::Arc::new(respond);
+        let server_url = base_url.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    return;
+                };
+                let respond = respond.clone();
+                let server_url = server_url.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut reader = BufReader::new(reader);
+                    // keep-alive で同じ接続に続けて送られるリクエストも処理する
+                    while let Some(request) = read_request(&mut reader).await {
+                        let body = respond(&request, &server_url);
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                            body.len(),
+                            body
+                        );
+                        if writer.write_all(response.as_bytes()).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        });
+        base_url
+    }
+
+    async fn read_request<R: tokio::io::AsyncBufRead + Unpin>(reader: &mut R) -> Option<MockRequest> {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).await.ok()? == 0 {
+            return None;
+        }
+        let mut parts = request_line.split_whitespace();
+        let method = parts.next()?.to_string();
+        let path = parts.next()?.to_string();
+
+        let mut authorization = None;
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await.ok()?;
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line.split_once(':')?;
+            match name.to_ascii_lowercase().as_str() {
+                "authorization" => authorization = Some(value.trim().to_string()),
+                "content-length" => content_length = value.trim().parse().ok()?,
+                _ => {}
+            }
+        }
+        let mut body = vec![0; content_length];
+        reader.read_exact(&mut body).await.ok()?;
+        Some(MockRequest { method, path, authorization, body })
+    }
+
+    // `Authorization: Nostr <base64>` から署名済みイベントを取り出して検証する
+    fn auth_event(request: &MockRequest) -> Event {
+        let value = request.authorization.as_deref().expect("missing Authorization header");
+        let encoded = value.strip_prefix("Nostr ").expect("not a Nostr authorization");
+        let json = general_purpose::STANDARD.decode(encoded).unwrap();
+        let event = Event::from_json(json).unwrap();
+        event.verify().unwrap();
+        event
+    }
+
+    fn tag_value(event: &Event, name: &str) -> Option<String> {
+        event
+            .tags
+            .iter()
+            .map(|tag| tag.as_slice())
+            .find(|tag| tag.first().map(|s| s.as_str()) == Some(name))
+            .and_then(|tag| tag.get(1).cloned())
+    }
+
+    // 1x1 の PNG を一時ファイルに書き出す
+    fn write_test_image(name: &str) -> std::path::PathBuf {
+        let mut png = Vec::new();
+        image::RgbImage::new(1, 1)
+            .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("{}-{}.png", name, std::process::id()));
+        std::fs::write(&path, png).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn uploads_to_nip96_server_with_nip98_auth() {
+        let keys = Keys::generate();
+        let pubkey = keys.public_key();
+        let server_url = start_mock_server(move |request, server_url| match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/.well-known/nostr/nip96.json") => format!(r#"{{"api_url":"{server_url}/api/upload"}}"#),
+            ("POST", "/api/upload") => {
+                let event = auth_event(request);
+                assert_eq!(event.kind, Kind::HttpAuth);
+                assert_eq!(event.pubkey, pubkey);
+                assert_eq!(tag_value(&event, "u"), Some(format!("{server_url}/api/upload")));
+                assert_eq!(tag_value(&event, "method").as_deref(), Some("POST"));
+                assert_eq!(tag_value(&event, "payload"), Some(sha256_hex(&request.body)));
+                format!(
+                    r#"{{"status":"success","nip94_event":{{"tags":[["url","{server_url}/media/image.png"],["m","image/png"],["x","abc"],["dim","1x1"]]}}}}"#
+                )
+            }
+            other => panic!("unexpected request {other:?}"),
+        })
+        .await;
+        let path = write_test_image("nip96-upload");
+
+        let media = upload_file(MediaServerKind::Nip96, &server_url, &keys, &path).await.unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(media.url, format!("{server_url}/media/image.png"));
+        assert_eq!(media.dimensions, Some((1, 1)));
+        assert_eq!(media.markdown("image"), format!("![image]({server_url}/media/image.png)"));
+        assert_eq!(
+            media.imeta_tag(),
+            vec![
+                "imeta".to_string(),
+                format!("url {server_url}/media/image.png"),
+                "m image/png".to_string(),
+                "x abc".to_string(),
+                "dim 1x1".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn uploads_to_blossom_server_with_bud02_auth() {
+        let keys = Keys::generate();
+        let server_url = start_mock_server(|request, server_url| {
+            assert_eq!((request.method.as_str(), request.path.as_str()), ("PUT", "/upload"));
+            let event = auth_event(request);
+            let sha256 = sha256_hex(&request.body);
+            assert_eq!(event.kind, Kind::from(24242));
+            assert_eq!(tag_value(&event, "t").as_deref(), Some("upload"));
+            assert_eq!(tag_value(&event, "x"), Some(sha256.clone()));
+            format!(r#"{{"url":"{server_url}/{sha256}.png","sha256":"{sha256}","type":"image/png"}}"#)
+        })
+        .await;
+        let path = write_test_image("blossom-upload");
+        let sha256 = sha256_hex(&std::fs::read(&path).unwrap());
+
+        let media = upload_file(MediaServerKind::Blossom, &server_url, &keys, &path).await.unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(media.url, format!("{server_url}/{sha256}.png"));
+        assert_eq!(media.mime_type, "image/png");
+        assert_eq!(media.sha256, sha256);
+        assert_eq!(media.dimensions, Some((1, 1)));
+    }
+
+    #[tokio::test]
+    async fn rejects_blossom_response_with_wrong_hash() {
+        let keys = Keys::generate();
+        let server_url = start_mock_server(|_, server_url| {
+            format!(r#"{{"url":"{server_url}/blob.png","sha256":"0000","type":"image/png"}}"#)
+        })
+        .await;
+        let path = write_test_image("blossom-wrong-hash");
+
+        let result = upload_file(MediaServerKind::Blossom, &server_url, &keys, &path).await;
+        std::fs::remove_file(&path).ok();
+
+        assert!(result.is_err());
+    }
+}
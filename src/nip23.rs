@@ -0,0 +1,202 @@
+use nostr::{
+    nips::nip19::{FromBech32, Nip19},
+    EventBuilder, Kind, PublicKey, Tag, TagStandard, Timestamp,
+};
+use regex::Regex;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+// 記事の `d` タグとして使うスラッグの最大長
+const MAX_SLUG_LENGTH: usize = 48;
+
+// NIP-21 の `nostr:` URI (秘密鍵の nsec は対象にしない)
+static NOSTR_URI_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"nostr:((?:npub|nprofile|note|nevent|naddr)1[02-9ac-hj-np-z]+)").unwrap()
+});
+
+/// Generates a stable `d` identifier for a new article.
+/// The slug is derived from the ASCII part of the title and suffixed with the
+/// creation time, so titles written entirely in Japanese still get a unique value.
+pub fn generate_identifier(title: &str) -> String {
+    let lowered: String = title
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    let mut slug = lowered
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    slug.truncate(MAX_SLUG_LENGTH);
+    let slug = slug.trim_end_matches('-');
+
+    let suffix = format!("{:x}", Timestamp::now().as_u64());
+    if slug.is_empty() {
+        suffix
+    } else {
+        format!("{slug}-{suffix}")
+    }
+}
+
+/// Returns the `<kind>:<pubkey>:<d>` string used both as the `a` tag value and as
+/// the cache key of an addressable event.
+pub fn coordinate_key(kind: Kind, author: &PublicKey, identifier: &str) -> String {
+    format!("{}:{}:{}", kind.as_u16(), author.to_hex(), identifier)
+}
+
+pub fn title_from_tags<'a>(tags: impl IntoIterator<Item = &'a Tag>) -> String {
+    tags.into_iter()
+        .find_map(|tag| match tag.as_standardized() {
+            Some(TagStandard::Title(title)) => Some(title.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+pub fn identifier_from_tags<'a>(tags: impl IntoIterator<Item = &'a Tag>) -> String {
+    tags.into_iter()
+        .find_map(|tag| match tag.as_standardized() {
+            Some(TagStandard::Identifier(identifier)) => Some(identifier.clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+/// The NIP-23 metadata tags of an article.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArticleMetadata {
+    pub identifier: String,
+    pub title: String,
+    pub summary: String,
+    pub image: String,
+    pub published_at: Option<Timestamp>,
+    pub hashtags: Vec<String>,
+    // NIP-92 `imeta` タグ (タグ名を含むそのままの値)
+    pub media: Vec<Vec<String>>,
+}
+
+impl ArticleMetadata {
+    pub fn from_tags<'a>(tags: impl IntoIterator<Item = &'a Tag>) -> Self {
+        let mut metadata = Self::default();
+        for tag in tags {
+            let [name, value, ..] = tag.as_slice() else {
+                continue;
+            };
+            match name.as_str() {
+                "d" => metadata.identifier = value.clone(),
+                "title" => metadata.title = value.clone(),
+                "summary" => metadata.summary = value.clone(),
+                "image" => metadata.image = value.clone(),
+                "published_at" => metadata.published_at = value.parse::<u64>().ok().map(Timestamp::from),
+                "t" => metadata.hashtags.push(value.clone()),
+                "imeta" => metadata.media.push(tag.as_slice().to_vec()),
+                _ => {}
+            }
+        }
+        metadata
+    }
+
+    pub fn to_tags(&self) -> Vec<Tag> {
+        let mut tags = vec![
+            Tag::identifier(&self.identifier),
+            Tag::from_standardized(TagStandard::Title(self.title.clone())),
+        ];
+        if !self.summary.is_empty() {
+            tags.push(Tag::from_standardized(TagStandard::Summary(self.summary.clone())));
+        }
+        if !self.image.is_empty() {
+            if let Ok(tag) = Tag::parse(["image", self.image.as_str()]) {
+                tags.push(tag);
+            }
+        }
+        if let Some(published_at) = self.published_at {
+            tags.push(Tag::from_standardized(TagStandard::PublishedAt(published_at)));
+        }
+        for hashtag in &self.hashtags {
+            tags.push(Tag::hashtag(hashtag));
+        }
+        for media in &self.media {
+            if let Ok(tag) = Tag::parse(media) {
+                tags.push(tag);
+            }
+        }
+        tags
+    }
+}
+
+/// Splits the hashtag input of the editor (comma or space separated, `#` optional)
+/// into lowercase `t` tag values.
+pub fn parse_hashtags(input: &str) -> Vec<String> {
+    let mut hashtags: Vec<String> = Vec::new();
+    for hashtag in input.split(|c: char| c == ',' || c == '、' || c.is_whitespace()) {
+        let hashtag = hashtag.trim().trim_start_matches('#').to_lowercase();
+        if !hashtag.is_empty() && !hashtags.contains(&hashtag) {
+            hashtags.push(hashtag);
+        }
+    }
+    hashtags
+}
+
+/// Returns the `url` field of an `imeta` tag.
+pub fn imeta_url(imeta: &[String]) -> Option<&str> {
+    imeta.iter().skip(1).find_map(|field| field.strip_prefix("url "))
+}
+
+/// Finds the `nostr:` URIs in `content` and returns their byte ranges and decoded entities.
+pub fn nostr_uris(content: &str) -> Vec<(Range<usize>, Nip19)> {
+    NOSTR_URI_REGEX
+        .captures_iter(content)
+        .filter_map(|captures| {
+            let uri = captures.get(0)?;
+            let entity = Nip19::from_bech32(captures.get(1)?.as_str()).ok()?;
+            Some((uri.range(), entity))
+        })
+        .collect()
+}
+
+/// Key used to look up the resolved target of a `nostr:` URI: the public key or
+/// event id in hex, or the coordinate key of an addressable event.
+pub fn reference_key(entity: &Nip19) -> Option<String> {
+    match entity {
+        Nip19::Pubkey(public_key) => Some(public_key.to_hex()),
+        Nip19::Profile(profile) => Some(profile.public_key.to_hex()),
+        Nip19::EventId(event_id) => Some(event_id.to_hex()),
+        Nip19::Event(event) => Some(event.event_id.to_hex()),
+        Nip19::Coordinate(naddr) => Some(coordinate_key(
+            naddr.coordinate.kind,
+            &naddr.coordinate.public_key,
+            &naddr.coordinate.identifier,
+        )),
+        _ => None,
+    }
+}
+
+/// NIP-27: returns the `p`, `e` and `a` tags for the profiles, notes and articles
+/// referenced from the content.
+pub fn reference_tags(content: &str) -> Vec<Tag> {
+    let mut values: Vec<[String; 2]> = Vec::new();
+    for (_, entity) in nostr_uris(content) {
+        let value = match entity {
+            Nip19::Pubkey(public_key) => ["p".to_string(), public_key.to_hex()],
+            Nip19::Profile(profile) => ["p".to_string(), profile.public_key.to_hex()],
+            Nip19::EventId(event_id) => ["e".to_string(), event_id.to_hex()],
+            Nip19::Event(event) => ["e".to_string(), event.event_id.to_hex()],
+            Nip19::Coordinate(naddr) => [
+                "a".to_string(),
+                coordinate_key(naddr.coordinate.kind, &naddr.coordinate.public_key, &naddr.coordinate.identifier),
+            ],
+            _ => continue,
+        };
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+    values.into_iter().filter_map(|value| Tag::parse(value).ok()).collect()
+}
+
+/// Builds an unsigned kind 30023 event for the given metadata and content.
+pub fn article_builder(metadata: &ArticleMetadata, content: &str) -> EventBuilder {
+    let mut tags = metadata.to_tags();
+    tags.extend(reference_tags(content));
+    EventBuilder::new(Kind::from(30023), content).tags(tags)
+}
@@ -1,12 +1,25 @@
 use futures::future::join_all;
-use nostr::{Filter, Keys, Kind, PublicKey, Tag as NostrTag, nips::nip19::ToBech32};
-use nostr_sdk::{Client, ClientOptions as Options, SubscribeAutoCloseOptions};
+use nostr::{
+    Alphabet, Event, EventBuilder, EventId, Filter, JsonUtil, Keys, Kind, PublicKey, RelayMessage, SingleLetterTag,
+    Tag as NostrTag, Timestamp,
+    nips::{nip01::Coordinate, nip19::{Nip19, ToBech32}, nip44},
+};
+use nostr_sdk::{
+    Client, ClientOptions as Options, RelayPoolNotification, RelayUrl, SubscribeAutoCloseOptions, SubscriptionId,
+};
 use std::collections::{HashMap, HashSet};
 use std::time::Duration;
 
 use crate::{
-    cache_db::{LmdbCache, DB_ARTICLES},
-    types::{ArticleFull, ProfileMetadata, TimelinePost},
+    cache_db::{
+        LmdbCache, DB_ARTICLES, DB_ARTICLE_HISTORY, DB_BOOKMARKS, DB_DRAFTS, DB_PROFILES, DB_PUBLISH_QUEUE, DB_REACTIONS,
+        DB_READING_POSITIONS, DB_REPOSTS, DB_SETTINGS, DB_TIMELINE,
+    },
+    nip23, search_index,
+    types::{
+        ArticleComment, ArticleDraft, ArticleFull, Bookmark, Highlight, ProfileMetadata, ReactionSummary, RepostInfo,
+        ScheduledArticle, TimelinePost, UserSettings,
+    },
 };
 
 // NIP-65とフォールバックを考慮したリレー接続関数
@@ -346,15 +359,565 @@ pub async fn get_profile_metadata(
     }
 }
 
+// NIP-23 イベントから記事オブジェクトを作成する
+pub fn article_from_event(event: &Event, author_metadata: ProfileMetadata) -> ArticleFull {
+    ArticleFull {
+        id: event.id,
+        kind: event.kind,
+        author_pubkey: event.pubkey,
+        author_metadata,
+        title: nip23::title_from_tags(event.tags.iter()),
+        content: event.content.clone(),
+        created_at: event.created_at,
+        tags: event.tags.clone().to_vec(),
+        identifier: nip23::identifier_from_tags(event.tags.iter()),
+    }
+}
+
+// タイムライン表示用の投稿を作成する
+// 著者が書いた summary タグがあればそれを使い、なければ本文の先頭を切り出す
+pub fn timeline_post_from_article(article: &ArticleFull) -> TimelinePost {
+    let metadata = article.metadata();
+    let summary = if !metadata.summary.is_empty() {
+        metadata.summary
+    } else {
+        truncate_summary(&article.content)
+    };
+
+    TimelinePost {
+        id: article.id,
+        kind: article.kind,
+        author_pubkey: article.author_pubkey,
+        author_metadata: article.author_metadata.clone(),
+        title: article.title.clone(),
+        summary,
+        created_at: article.created_at,
+        tags: article.tags.clone(),
+        identifier: article.identifier.clone(),
+        image: metadata.image,
+        reposted_by: None,
+    }
+}
+
+fn truncate_summary(content: &str) -> String {
+    if content.chars().count() > 200 {
+        let mut truncated: String = content.chars().take(200).collect();
+        truncated.push_str("...");
+        truncated
+    } else {
+        content.to_string()
+    }
+}
+
+// 記事以外のイベント (kind 1 など) を引用カード用の投稿にする
+pub fn timeline_post_from_note(event: &Event, author_metadata: ProfileMetadata) -> TimelinePost {
+    TimelinePost {
+        id: event.id,
+        kind: event.kind,
+        author_pubkey: event.pubkey,
+        author_metadata,
+        title: String::new(),
+        summary: truncate_summary(&event.content),
+        created_at: event.created_at,
+        tags: event.tags.clone().to_vec(),
+        identifier: String::new(),
+        image: String::new(),
+        reposted_by: None,
+    }
+}
+
+// 記事をアドレス (kind:pubkey:d) をキーにしてキャッシュする
+pub fn store_article(cache_db: &LmdbCache, article: &ArticleFull) {
+    let key = article.coordinate_key();
+    if let Err(e) = cache_db.write_cache(DB_ARTICLES, &key, article) {
+        eprintln!("Failed to write article cache for {}: {}", key, e);
+    }
+    search_index::index_article(article);
+}
+
+// 自分が公開・編集した記事を、版の履歴にも残してキャッシュする
+// (タイムラインなどで流れてくる記事は履歴に入れない)
+pub fn store_article_with_history(cache_db: &LmdbCache, article: &ArticleFull) {
+    store_article(cache_db, article);
+    record_revisions(cache_db, &article.coordinate_key(), vec![article.clone()]);
+}
+
+// キャッシュにある記事を有効期限に関係なく読み込む
+pub fn load_cached_article(cache_db: &LmdbCache, coordinate_key: &str) -> Option<ArticleFull> {
+    cache_db
+        .read_persistent_cache::<ArticleFull>(DB_ARTICLES, coordinate_key)
+        .ok()
+        .map(|cache| cache.data)
+}
+
+// 記事を署名して公開し、キャッシュに保存する
+pub async fn publish_article(
+    cache_db: &LmdbCache,
+    client: &Client,
+    keys: &Keys,
+    metadata: &nip23::ArticleMetadata,
+    content: &str,
+    author_metadata: ProfileMetadata,
+) -> Result<ArticleFull, Box<dyn std::error::Error + Send + Sync>> {
+    let event = nip23::article_builder(metadata, content).sign(keys).await?;
+    client.send_event(&event).await?;
+    let article = article_from_event(&event, author_metadata);
+    store_article_with_history(cache_db, &article);
+    Ok(article)
+}
+
+// 公開済みの記事の最初の公開日時を調べる (published_at がなければ最新版の作成日時、未公開なら None)
+pub async fn fetch_published_at(
+    client: &Client,
+    author: &PublicKey,
+    identifier: &str,
+) -> Result<Option<Timestamp>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .kind(Kind::from(30023))
+        .author(*author)
+        .identifier(identifier);
+    let events = client.fetch_events(filter, Duration::from_secs(10)).await?;
+    Ok(events.into_iter().max_by_key(|e| e.created_at).map(|event| {
+        let article = article_from_event(&event, ProfileMetadata::default());
+        article.metadata().published_at.unwrap_or(article.created_at)
+    }))
+}
+
+// 自分の記事をリレーとローカルのキャッシュの両方から集める (同じアドレスは新しい方を使う)
+pub async fn fetch_my_articles(
+    cache_db: &LmdbCache,
+    client: &Client,
+    keys: &Keys,
+    my_metadata: ProfileMetadata,
+) -> Result<Vec<ArticleFull>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .authors(vec![keys.public_key()])
+        .kind(Kind::from(30023));
+    let events = latest_by_coordinate(client.fetch_events(filter, Duration::from_secs(10)).await?);
+    let deletions = fetch_deletions(client, &events).await.unwrap_or_default();
+
+    let mut articles: HashMap<String, ArticleFull> = HashMap::new();
+    for (key, cache) in cache_db.read_all_cache::<ArticleFull>(DB_ARTICLES)? {
+        if cache.data.author_pubkey == keys.public_key() {
+            articles.insert(key, cache.data);
+        }
+    }
+    for event in events {
+        let key = nip23::coordinate_key(event.kind, &event.pubkey, &nip23::identifier_from_tags(event.tags.iter()));
+        if is_deleted(&event, &deletions) {
+            forget_article(cache_db, &keys.public_key(), &key);
+            articles.remove(&key);
+            continue;
+        }
+        if articles.get(&key).is_some_and(|a| a.created_at >= event.created_at) {
+            continue;
+        }
+        let article = article_from_event(&event, my_metadata.clone());
+        store_article_with_history(cache_db, &article);
+        articles.insert(key, article);
+    }
+
+    let mut articles: Vec<ArticleFull> = articles.into_values().collect();
+    articles.sort_by_key(|a| std::cmp::Reverse(a.created_at));
+    Ok(articles)
+}
+
+// --- 読んでいた位置 ---
+
+// 記事ごとの読んでいた位置 (本文のスクロール量)
+pub fn load_reading_position(cache_db: &LmdbCache, coordinate_key: &str) -> Option<f32> {
+    cache_db
+        .read_persistent_cache::<f32>(DB_READING_POSITIONS, coordinate_key)
+        .ok()
+        .map(|cache| cache.data)
+}
+
+pub fn save_reading_position(cache_db: &LmdbCache, coordinate_key: &str, offset: f32) {
+    if let Err(e) = cache_db.write_cache(DB_READING_POSITIONS, coordinate_key, &offset) {
+        eprintln!("Failed to save reading position for {}: {}", coordinate_key, e);
+    }
+}
+
+// --- 記事の変更履歴 ---
+
+// 記事ごとに保存しておく版の数
+const MAX_ARTICLE_REVISIONS: usize = 20;
+
+// ローカルに保存された記事の版を新しい順に読み込む
+pub fn load_article_history(cache_db: &LmdbCache, coordinate_key: &str) -> Vec<ArticleFull> {
+    cache_db
+        .read_persistent_cache::<Vec<ArticleFull>>(DB_ARTICLE_HISTORY, coordinate_key)
+        .map(|cache| cache.data)
+        .unwrap_or_default()
+}
+
+// 記事の版を履歴に追加する (同じイベントは一度だけ保存し、古い版から捨てる)
+pub fn record_revisions(cache_db: &LmdbCache, coordinate_key: &str, revisions: Vec<ArticleFull>) -> Vec<ArticleFull> {
+    let mut history = load_article_history(cache_db, coordinate_key);
+    let mut changed = false;
+    for revision in revisions {
+        if !history.iter().any(|r| r.id == revision.id) {
+            history.push(revision);
+            changed = true;
+        }
+    }
+    history.sort_by_key(|r| std::cmp::Reverse(r.created_at));
+    history.truncate(MAX_ARTICLE_REVISIONS);
+    if changed {
+        if let Err(e) = cache_db.write_cache(DB_ARTICLE_HISTORY, coordinate_key, &history) {
+            eprintln!("Failed to write article history for {coordinate_key}: {e}");
+        }
+    }
+    history
+}
+
+// リレーに残っている記事のすべての版を取得し、ローカルの履歴とマージする
+pub async fn fetch_article_history(
+    cache_db: &LmdbCache,
+    client: &Client,
+    coordinate: &Coordinate,
+) -> Result<Vec<ArticleFull>, Box<dyn std::error::Error + Send + Sync>> {
+    let key = nip23::coordinate_key(coordinate.kind, &coordinate.public_key, &coordinate.identifier);
+    let filter = Filter::new()
+        .kind(coordinate.kind)
+        .authors(vec![coordinate.public_key])
+        .identifier(coordinate.identifier.clone());
+    let events = client.fetch_events(filter, Duration::from_secs(10)).await?;
+
+    let author_metadata = match load_article_history(cache_db, &key).first() {
+        Some(revision) => revision.author_metadata.clone(),
+        None => get_profile_metadata(coordinate.public_key, client).await.unwrap_or_default(),
+    };
+    let revisions = events
+        .into_iter()
+        .filter(|event| event.pubkey == coordinate.public_key)
+        .map(|event| article_from_event(&event, author_metadata.clone()))
+        .collect();
+    Ok(record_revisions(cache_db, &key, revisions))
+}
+
+// 古い版の内容とタグをそのまま使って、新しい版として公開し直す
+pub async fn restore_revision(
+    client: &Client,
+    keys: &Keys,
+    revision: &ArticleFull,
+) -> Result<Event, Box<dyn std::error::Error + Send + Sync>> {
+    if revision.author_pubkey != keys.public_key() {
+        return Err("自分の記事以外は復元できません".into());
+    }
+    let event = EventBuilder::new(revision.kind, revision.content.clone())
+        .tags(revision.tags.clone())
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(event)
+}
+
+// 同じアドレスの記事は新しいバージョンだけを残してタイムラインにマージする
+pub fn merge_timeline_posts(timeline_posts: &mut Vec<TimelinePost>, new_posts: Vec<TimelinePost>) -> usize {
+    let mut added_posts = 0;
+    for post in new_posts {
+        let key = post.coordinate_key();
+        match timeline_posts.iter_mut().find(|p| p.coordinate_key() == key) {
+            Some(existing) => {
+                if post.created_at > existing.created_at {
+                    *existing = post;
+                    added_posts += 1;
+                } else if post.reposted_by.is_none() && existing.reposted_by.is_some() {
+                    // 元の記事が届いたらリポストのカードは通常のカードに置き換える
+                    existing.reposted_by = None;
+                    added_posts += 1;
+                }
+            }
+            None => {
+                timeline_posts.push(post);
+                added_posts += 1;
+            }
+        }
+    }
+    if added_posts > 0 {
+        timeline_posts.sort_by_key(|p| std::cmp::Reverse(p.timeline_at()));
+    }
+    added_posts
+}
+
+// リレーは同じアドレスの古いバージョンを返すことがあるため、最新のものだけを残す
+fn latest_by_coordinate(events: impl IntoIterator<Item = Event>) -> Vec<Event> {
+    let mut latest: HashMap<String, Event> = HashMap::new();
+    for event in events {
+        let key = nip23::coordinate_key(
+            event.kind,
+            &event.pubkey,
+            &nip23::identifier_from_tags(event.tags.iter()),
+        );
+        match latest.get(&key) {
+            Some(existing) if existing.created_at >= event.created_at => {}
+            _ => {
+                latest.insert(key, event);
+            }
+        }
+    }
+    latest.into_values().collect()
+}
+
+// until をさかのぼりながら、フィルターに合うイベントを新しいものが返らなくなるまで取得する
+// 上限のページ数で打ち切ったときは false を返す
+async fn fetch_pages(
+    client: &Client,
+    filter: Filter,
+    page_size: usize,
+    max_pages: usize,
+) -> Result<(Vec<Event>, bool), Box<dyn std::error::Error + Send + Sync>> {
+    let mut events: HashMap<EventId, Event> = HashMap::new();
+    let mut until = None;
+    for _ in 0..max_pages {
+        let mut page_filter = filter.clone().limit(page_size);
+        if let Some(until) = until {
+            page_filter = page_filter.until(until);
+        }
+        let page = client.fetch_events(page_filter, Duration::from_secs(10)).await?;
+        let before = events.len();
+        until = page.iter().map(|e| e.created_at).min();
+        events.extend(page.into_iter().map(|e| (e.id, e)));
+        if events.len() == before {
+            return Ok((events.into_values().collect(), true));
+        }
+    }
+    Ok((events.into_values().collect(), false))
+}
+
+// フィルターに合うイベントを取得し、購読できたすべてのリレーが EOSE を返したかも返す
+// (fetch_events はタイムアウトしたときも同じように終わるので、それと区別するため)
+async fn fetch_events_until_eose(
+    client: &Client,
+    filter: Filter,
+    timeout: Duration,
+) -> Result<(Vec<Event>, bool), Box<dyn std::error::Error + Send + Sync>> {
+    let mut notifications = client.notifications();
+    let subscription_id = SubscriptionId::generate();
+    let output = client.subscribe_with_id(subscription_id.clone(), filter, None).await?;
+    let mut events: HashMap<EventId, Event> = HashMap::new();
+    let mut eose_relays = HashSet::new();
+    let all_eose = tokio::time::timeout(timeout, async {
+        loop {
+            let notification = match notifications.recv().await {
+                Ok(notification) => notification,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(_) => return false,
+            };
+            let RelayPoolNotification::Message { relay_url, message } = notification else {
+                continue;
+            };
+            match message {
+                RelayMessage::Event { subscription_id: id, event } if *id == subscription_id => {
+                    events.insert(event.id, event.into_owned());
+                }
+                RelayMessage::EndOfStoredEvents(id) if *id == subscription_id => {
+                    eose_relays.insert(relay_url);
+                    if output.success.iter().all(|url| eose_relays.contains(url)) {
+                        return true;
+                    }
+                }
+                _ => {}
+            }
+        }
+    })
+    .await
+    .unwrap_or(false);
+    client.unsubscribe(&subscription_id).await;
+    Ok((events.into_values().collect(), all_eose && !output.success.is_empty()))
+}
+
+// NIP-09: 削除リクエストが対象イベントの著者本人によるものであれば削除済みとみなす
+// `a` タグによる削除は、削除リクエストより前に作られたバージョンだけに適用する
+fn is_deleted(event: &Event, deletions: &[Event]) -> bool {
+    let event_id = event.id.to_hex();
+    let coordinate = nip23::coordinate_key(
+        event.kind,
+        &event.pubkey,
+        &nip23::identifier_from_tags(event.tags.iter()),
+    );
+    deletions
+        .iter()
+        .filter(|deletion| deletion.kind == Kind::EventDeletion && deletion.pubkey == event.pubkey)
+        .any(|deletion| {
+            deletion.tags.iter().any(|tag| match tag.as_slice() {
+                [name, value, ..] if name == "e" => *value == event_id,
+                [name, value, ..] if name == "a" => *value == coordinate && event.created_at <= deletion.created_at,
+                _ => false,
+            })
+        })
+}
+
+// 記事を参照している削除リクエストを取得する
+async fn fetch_deletions(
+    client: &Client,
+    events: &[Event],
+) -> Result<Vec<Event>, Box<dyn std::error::Error + Send + Sync>> {
+    if events.is_empty() {
+        return Ok(Vec::new());
+    }
+    let authors: HashSet<PublicKey> = events.iter().map(|e| e.pubkey).collect();
+    let coordinates: Vec<Coordinate> = events
+        .iter()
+        .map(|e| {
+            Coordinate::new(e.kind, e.pubkey).identifier(nip23::identifier_from_tags(e.tags.iter()))
+        })
+        .collect();
+
+    let by_id = Filter::new()
+        .kind(Kind::EventDeletion)
+        .authors(authors.clone())
+        .events(events.iter().map(|e| e.id));
+    let by_coordinate = Filter::new()
+        .kind(Kind::EventDeletion)
+        .authors(authors)
+        .coordinates(coordinates.iter());
+
+    let mut deletions: Vec<Event> = client
+        .fetch_events(by_id, Duration::from_secs(5))
+        .await?
+        .into_iter()
+        .collect();
+    deletions.extend(client.fetch_events(by_coordinate, Duration::from_secs(5)).await?);
+    Ok(deletions)
+}
+
+// 自分の記事に対して NIP-09 の削除リクエストを送る (`e` と `a` の両方で参照する)
+pub async fn delete_article(
+    client: &Client,
+    keys: &Keys,
+    article: &ArticleFull,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if article.author_pubkey != keys.public_key() {
+        return Err("自分の記事以外は削除できません".into());
+    }
+    let event = EventBuilder::new(Kind::EventDeletion, "")
+        .tags([
+            NostrTag::event(article.id),
+            NostrTag::parse(["a", article.coordinate_key().as_str()])?,
+            NostrTag::parse(["k", article.kind.as_u16().to_string().as_str()])?,
+        ])
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(())
+}
+
+// 削除された記事をキャッシュ (記事本体と、ログイン中のユーザーのタイムライン) から取り除く
+pub fn forget_article(cache_db: &LmdbCache, timeline_owner: &PublicKey, coordinate_key: &str) {
+    if let Err(e) = cache_db.delete_cache(DB_ARTICLES, coordinate_key) {
+        eprintln!("Failed to delete article cache for {coordinate_key}: {e}");
+    }
+    search_index::remove_article(coordinate_key);
+    let owner_hex = timeline_owner.to_hex();
+    if let Ok(cache) = cache_db.read_persistent_cache::<Vec<TimelinePost>>(DB_TIMELINE, &owner_hex) {
+        let mut posts = cache.data;
+        let count = posts.len();
+        posts.retain(|p| p.coordinate_key() != coordinate_key);
+        if posts.len() != count {
+            if let Err(e) = cache_db.write_cache(DB_TIMELINE, &owner_hex, &posts) {
+                eprintln!("Failed to update timeline cache: {e}");
+            }
+        }
+    }
+}
+
+/// Articles fetched for the timeline, plus the addresses of articles that their
+/// authors have deleted and which should be dropped from any local copy.
+/// `reached_end` is set when an older page confirmed there is nothing older, and
+/// `gap` when a newer page stopped before reaching the newest loaded post.
+#[derive(Debug, Default)]
+pub struct TimelineFetch {
+    pub posts: Vec<TimelinePost>,
+    pub deleted: HashSet<String>,
+    pub reached_end: bool,
+    pub gap: bool,
+}
+
+// 1 ページで取得する記事の数
+const TIMELINE_PAGE_SIZE: usize = 20;
+// 新しい記事をさかのぼって取得するときの 1 回の数と、さかのぼる回数の上限
+const TIMELINE_NEWER_LIMIT: usize = 100;
+const MAX_TIMELINE_NEWER_PAGES: usize = 10;
+// DB_TIMELINE に保存しておく記事の数
+const MAX_SAVED_TIMELINE_POSTS: usize = 1000;
+
+/// Which part of the timeline to fetch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimelinePage {
+    /// The newest page.
+    Latest,
+    /// The page before the given time (the oldest loaded post).
+    Older(Timestamp),
+    /// Everything after the given time (the newest loaded post).
+    Newer(Timestamp),
+}
+
+impl TimelinePage {
+    /// Picks the page to fetch for newer posts: everything since the newest
+    /// post, or the newest page when nothing is loaded yet.
+    pub fn newer_than(posts: &[TimelinePost]) -> Self {
+        posts.iter().map(TimelinePost::timeline_at).max().map_or(TimelinePage::Latest, TimelinePage::Newer)
+    }
+
+    pub fn older_than(posts: &[TimelinePost]) -> Option<Self> {
+        posts.iter().map(TimelinePost::timeline_at).min().map(TimelinePage::Older)
+    }
+
+    // ページのイベントを取得する。bool は、Older ならリレーがすべて EOSE を返して until より古いイベントが
+    // なかったこと (これ以上さかのぼれない)、Newer なら since までさかのぼれたこと
+    async fn fetch(
+        self,
+        client: &Client,
+        filter: Filter,
+    ) -> Result<(Vec<Event>, bool), Box<dyn std::error::Error + Send + Sync>> {
+        match self {
+            TimelinePage::Latest => {
+                let events = client.fetch_events(filter.limit(TIMELINE_PAGE_SIZE), Duration::from_secs(10)).await?;
+                Ok((events.into_iter().collect(), false))
+            }
+            TimelinePage::Older(until) => {
+                let filter = filter.until(until).limit(TIMELINE_PAGE_SIZE);
+                let (events, all_eose) = fetch_events_until_eose(client, filter, Duration::from_secs(10)).await?;
+                let has_older = events.iter().any(|e| e.created_at < until);
+                Ok((events, all_eose && !has_older))
+            }
+            TimelinePage::Newer(since) => {
+                fetch_pages(client, filter.since(since), TIMELINE_NEWER_LIMIT, MAX_TIMELINE_NEWER_PAGES).await
+            }
+        }
+    }
+}
+
+// これまでに読み込んだタイムライン (期限切れでも履歴として使う)
+pub fn load_timeline(cache_db: &LmdbCache, timeline_owner: &PublicKey) -> Vec<TimelinePost> {
+    cache_db
+        .read_persistent_cache::<Vec<TimelinePost>>(DB_TIMELINE, &timeline_owner.to_hex())
+        .map(|cache| cache.data)
+        .unwrap_or_default()
+}
+
+pub fn save_timeline(cache_db: &LmdbCache, timeline_owner: &PublicKey, posts: &[TimelinePost]) {
+    let posts = &posts[..posts.len().min(MAX_SAVED_TIMELINE_POSTS)];
+    if let Err(e) = cache_db.write_cache(DB_TIMELINE, &timeline_owner.to_hex(), &posts) {
+        eprintln!("Failed to write timeline cache: {e}");
+    }
+}
+
 pub async fn fetch_timeline_events(
     keys: &Keys,
     discover_relays: &str,
     followed_pubkeys: &HashSet<PublicKey>,
     cache_db: &LmdbCache,
-) -> Result<Vec<TimelinePost>, Box<dyn std::error::Error + Send + Sync>> {
+    page: TimelinePage,
+) -> Result<TimelineFetch, Box<dyn std::error::Error + Send + Sync>> {
     let mut timeline_posts = Vec::new();
+    let mut deleted = HashSet::new();
+    let mut reached_end = false;
+    let mut gap = false;
     if followed_pubkeys.is_empty() {
-        return Ok(timeline_posts);
+        return Ok(TimelineFetch::default());
     }
 
     let temp_discover_client = Client::new(keys.clone());
@@ -376,15 +939,56 @@ pub async fn fetch_timeline_events(
 
         let timeline_filter = Filter::new()
             .authors(followed_pubkeys.clone())
-            .kind(Kind::from(30023)) // Changed to NIP-23 Kind
-            .limit(20);
-        let status_events = temp_fetch_client
-            .fetch_events(timeline_filter, Duration::from_secs(10))
-            .await?;
+            .kind(Kind::from(30023)); // Changed to NIP-23 Kind
+        let (status_events, articles_complete) = page.fetch(&temp_fetch_client, timeline_filter).await?;
+        let mut status_events = latest_by_coordinate(status_events);
+
+        // フォロー中のユーザーがリポストした記事も並べる (取得済みの記事とは重複させない)
+        let known: HashSet<String> = status_events
+            .iter()
+            .map(|e| nip23::coordinate_key(e.kind, &e.pubkey, &nip23::identifier_from_tags(e.tags.iter())))
+            .collect();
+        let (reposted_events, reposts, reposts_complete) =
+            match fetch_reposted_articles(&temp_fetch_client, followed_pubkeys, &known, page).await {
+                Ok(result) => result,
+                Err(e) => {
+                    eprintln!("Failed to fetch reposts: {e}");
+                    (Vec::new(), HashMap::new(), false)
+                }
+            };
+        status_events.extend(reposted_events);
+        match page {
+            TimelinePage::Latest => {}
+            TimelinePage::Older(_) => reached_end = articles_complete && reposts_complete,
+            TimelinePage::Newer(_) => gap = !(articles_complete && reposts_complete),
+        }
+
+        let deletions = match fetch_deletions(&temp_fetch_client, &status_events).await {
+            Ok(deletions) => deletions,
+            Err(e) => {
+                eprintln!("Failed to fetch deletion requests: {e}");
+                Vec::new()
+            }
+        };
+        let (deleted_events, status_events): (Vec<Event>, Vec<Event>) = status_events
+            .into_iter()
+            .partition(|event| is_deleted(event, &deletions));
+        for event in deleted_events {
+            let key = nip23::coordinate_key(
+                event.kind,
+                &event.pubkey,
+                &nip23::identifier_from_tags(event.tags.iter()),
+            );
+            forget_article(cache_db, &keys.public_key(), &key);
+            deleted.insert(key);
+        }
 
         if !status_events.is_empty() {
-            let author_pubkeys: HashSet<PublicKey> =
-                status_events.iter().map(|e| e.pubkey).collect();
+            let author_pubkeys: HashSet<PublicKey> = status_events
+                .iter()
+                .map(|e| e.pubkey)
+                .chain(reposts.values().map(|(reposter, _)| *reposter))
+                .collect();
             let metadata_filter = Filter::new()
                 .authors(author_pubkeys.into_iter())
                 .kind(Kind::Metadata);
@@ -399,110 +1003,1457 @@ pub async fn fetch_timeline_events(
             }
 
             for event in status_events {
-                // Extract title from 't' tag for NIP-23
-                let title = event.tags.iter().find_map(|tag| {
-                    if let Some(nostr::TagStandard::Title(title)) = tag.as_standardized() {
-                        Some(title.clone())
-                    } else {
-                        None
-                    }
-                }).unwrap_or_default();
-
                 // Create the full article object for caching
-                let full_article = ArticleFull {
-                    id: event.id,
-                    kind: event.kind,
-                    author_pubkey: event.pubkey,
-                    author_metadata: profiles.get(&event.pubkey).cloned().unwrap_or_default(),
-                    title: title.clone(),
-                    content: event.content.clone(),
-                    created_at: event.created_at,
-                    tags: event.tags.clone().to_vec(),
-                };
+                let full_article = article_from_event(
+                    &event,
+                    profiles.get(&event.pubkey).cloned().unwrap_or_default(),
+                );
 
                 // Cache the full article
-                if let Err(e) = cache_db.write_cache(DB_ARTICLES, &event.id.to_hex(), &full_article) {
-                    eprintln!("Failed to write article cache for {}: {}", event.id.to_hex(), e);
-                }
+                store_article(cache_db, &full_article);
 
-                // Create a summary for the timeline view
-                let summary = if event.content.chars().count() > 200 {
-                    let mut truncated: String = event.content.chars().take(200).collect();
-                    truncated.push_str("...");
-                    truncated
-                } else {
-                    event.content.clone()
-                };
-
-                timeline_posts.push(TimelinePost {
-                    id: event.id,
-                    kind: event.kind,
-                    author_pubkey: event.pubkey,
-                    author_metadata: profiles.get(&event.pubkey).cloned().unwrap_or_default(),
-                    title,
-                    summary, // Use summary instead of full content
-                    created_at: event.created_at,
-                    tags: event.tags.to_vec(),
-                });
+                let mut post = timeline_post_from_article(&full_article);
+                if let Some((reposter, reposted_at)) = reposts.get(&full_article.coordinate_key()) {
+                    post.reposted_by = Some(RepostInfo {
+                        reposter_pubkey: *reposter,
+                        reposter_metadata: profiles.get(reposter).cloned().unwrap_or_default(),
+                        reposted_at: *reposted_at,
+                    });
+                }
+                timeline_posts.push(post);
             }
-            timeline_posts.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+            timeline_posts.sort_by_key(|p| std::cmp::Reverse(p.timeline_at()));
         }
         temp_fetch_client.shutdown().await;
     }
-    Ok(timeline_posts)
+    Ok(TimelineFetch {
+        posts: timeline_posts,
+        deleted,
+        reached_end,
+        gap,
+    })
+}
+
+// --- タイムラインのライブ購読 ---
+
+// フォロー中のユーザーがこれから公開する記事を購読する (前の購読があれば閉じる)
+pub async fn subscribe_timeline(
+    client: &Client,
+    followed_pubkeys: &HashSet<PublicKey>,
+    previous: Option<SubscriptionId>,
+) -> Result<SubscriptionId, Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(previous) = previous {
+        client.unsubscribe(&previous).await;
+    }
+    let filter = Filter::new()
+        .authors(followed_pubkeys.clone())
+        .kind(Kind::from(30023))
+        .since(Timestamp::now());
+    Ok(client.subscribe(filter, None).await?.val)
+}
+
+// 購読で届いた記事をキャッシュに保存し、タイムラインのカードにする
+pub async fn timeline_post_from_live_event(
+    cache_db: &LmdbCache,
+    client: &Client,
+    event: &Event,
+) -> Result<TimelinePost, Box<dyn std::error::Error + Send + Sync>> {
+    let profiles = fetch_profiles(cache_db, client, HashSet::from([event.pubkey])).await?;
+    let article = article_from_event(event, profiles.get(&event.pubkey).cloned().unwrap_or_default());
+    store_article(cache_db, &article);
+    Ok(timeline_post_from_article(&article))
+}
+
+// --- NIP-18 リポスト ---
+
+// リポスト先の記事のイベント、coordinate_key -> (最後にリポストしたユーザー, その時刻)、ページを取り切れたか
+type RepostedArticles = (Vec<Event>, HashMap<String, (PublicKey, Timestamp)>, bool);
+
+// 記事をアドレスで参照する kind 16 のリポストを取得し、リポストされた記事を集める
+async fn fetch_reposted_articles(
+    client: &Client,
+    followed_pubkeys: &HashSet<PublicKey>,
+    known: &HashSet<String>,
+    page: TimelinePage,
+) -> Result<RepostedArticles, Box<dyn std::error::Error + Send + Sync>> {
+    let repost_filter = Filter::new()
+        .authors(followed_pubkeys.clone())
+        .kind(Kind::GenericRepost)
+        .custom_tag(SingleLetterTag::lowercase(Alphabet::K), "30023");
+    let (repost_events, complete) = page.fetch(client, repost_filter).await?;
+
+    let mut reposts: HashMap<String, (PublicKey, Timestamp)> = HashMap::new();
+    let mut embedded: Vec<Event> = Vec::new();
+    for repost in repost_events.iter() {
+        let Some(key) = repost.tags.iter().find_map(|tag| match tag.as_slice() {
+            [name, value, ..] if name == "a" && value.starts_with("30023:") => Some(value.clone()),
+            _ => None,
+        }) else {
+            continue;
+        };
+        if known.contains(&key) {
+            continue;
+        }
+        // content に元のイベントが入っていれば、署名を確かめてそのまま使う
+        if let Ok(event) = Event::from_json(&repost.content) {
+            let embedded_key =
+                nip23::coordinate_key(event.kind, &event.pubkey, &nip23::identifier_from_tags(event.tags.iter()));
+            if embedded_key == key && event.verify().is_ok() {
+                embedded.push(event);
+            }
+        }
+        let is_newer = reposts.get(&key).is_none_or(|(_, reposted_at)| repost.created_at > *reposted_at);
+        if is_newer {
+            reposts.insert(key, (repost.pubkey, repost.created_at));
+        }
+    }
+    if reposts.is_empty() {
+        return Ok((Vec::new(), reposts, complete));
+    }
+
+    let event_key =
+        |e: &Event| nip23::coordinate_key(e.kind, &e.pubkey, &nip23::identifier_from_tags(e.tags.iter()));
+    let mut events = embedded;
+    let embedded_keys: HashSet<String> = events.iter().map(event_key).collect();
+    let missing: Vec<Coordinate> = reposts
+        .keys()
+        .filter(|key| !embedded_keys.contains(*key))
+        .filter_map(|key| Coordinate::parse(key).ok())
+        .collect();
+    if !missing.is_empty() {
+        let article_filter = Filter::new()
+            .kind(Kind::from(30023))
+            .authors(missing.iter().map(|c| c.public_key))
+            .identifiers(missing.iter().map(|c| c.identifier.clone()));
+        let fetched = client.fetch_events(article_filter, Duration::from_secs(10)).await?;
+        events.extend(fetched.into_iter().filter(|e| reposts.contains_key(&event_key(e))));
+    }
+    Ok((latest_by_coordinate(events), reposts, complete))
+}
+
+// リポスト済みの記事 (アドレスのキー) を読み込む
+pub fn load_reposted_articles(cache_db: &LmdbCache, owner: &PublicKey) -> HashSet<String> {
+    cache_db
+        .read_persistent_cache::<HashSet<String>>(DB_REPOSTS, &owner.to_hex())
+        .map(|cache| cache.data)
+        .unwrap_or_default()
+}
+
+pub fn save_reposted_articles(cache_db: &LmdbCache, owner: &PublicKey, reposted: &HashSet<String>) {
+    if let Err(e) = cache_db.write_cache(DB_REPOSTS, &owner.to_hex(), reposted) {
+        eprintln!("Failed to write reposted articles: {e}");
+    }
+}
+
+// 記事を kind 16 (generic repost) でリポストする
+pub async fn publish_repost(
+    client: &Client,
+    keys: &Keys,
+    event_id: EventId,
+    coordinate: &Coordinate,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let coordinate_key = nip23::coordinate_key(coordinate.kind, &coordinate.public_key, &coordinate.identifier);
+    let event = EventBuilder::new(Kind::GenericRepost, "")
+        .tags([
+            NostrTag::parse(["k", coordinate.kind.as_u16().to_string().as_str()])?,
+            NostrTag::parse(["a", coordinate_key.as_str()])?,
+            NostrTag::parse(["e", event_id.to_hex().as_str()])?,
+            NostrTag::parse(["p", coordinate.public_key.to_hex().as_str()])?,
+        ])
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(())
 }
 
 pub async fn fetch_article(
     cache_db: &LmdbCache,
     client: &Client,
-    event_id: nostr::EventId,
+    coordinate: &Coordinate,
 ) -> Result<ArticleFull, Box<dyn std::error::Error + Send + Sync>> {
     // 1. Try to get from cache first
-    let key = event_id.to_hex();
+    let key = nip23::coordinate_key(coordinate.kind, &coordinate.public_key, &coordinate.identifier);
+    // 固定された (ブックマークした) 記事は古くなっても残るが、編集を反映するため 24 時間ごとに取得し直す
+    let mut stale = None;
     if let Ok(cached_data) = cache_db.read_cache::<ArticleFull>(DB_ARTICLES, &key) {
         if !cached_data.is_expired() {
             println!("Article {} loaded from cache.", key);
             return Ok(cached_data.data);
         }
+        stale = Some(cached_data.data);
     }
 
     // 2. If not in cache or expired, fetch from network
     println!("Article {} not in cache or expired, fetching from network.", key);
-    let filter = Filter::new().id(event_id).limit(1);
+    match fetch_article_from_relays(cache_db, client, coordinate).await {
+        Ok(article) => Ok(article),
+        // 取得できなければ、固定してあった古い版を使う
+        Err(e) => match stale {
+            Some(article) => {
+                eprintln!("Failed to refresh article {}, using the cached copy: {}", key, e);
+                Ok(article)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+async fn fetch_article_from_relays(
+    cache_db: &LmdbCache,
+    client: &Client,
+    coordinate: &Coordinate,
+) -> Result<ArticleFull, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .kind(coordinate.kind)
+        .authors(vec![coordinate.public_key])
+        .identifier(coordinate.identifier.clone());
     let events = client
         .fetch_events(filter, Duration::from_secs(10))
         .await?;
 
-    if let Some(event) = events.first() {
+    // 置き換え可能イベントなので、最も新しいバージョンを採用する
+    if let Some(event) = events.into_iter().max_by_key(|e| e.created_at) {
         // We need author metadata as well
         let author_metadata = get_profile_metadata(event.pubkey, client).await?;
 
-        let title = event.tags.iter().find_map(|tag| {
-            if let Some(nostr::TagStandard::Title(title)) = tag.as_standardized() {
-                Some(title.clone())
-            } else {
-                None
-            }
-        }).unwrap_or_default();
-
-        let full_article = ArticleFull {
-            id: event.id,
-            kind: event.kind,
-            author_pubkey: event.pubkey,
-            author_metadata,
-            title,
-            content: event.content.clone(),
-            created_at: event.created_at,
-            tags: event.tags.clone().to_vec(),
-        };
+        let full_article = article_from_event(&event, author_metadata);
 
         // 3. Save the newly fetched article to cache
-        if let Err(e) = cache_db.write_cache(DB_ARTICLES, &key, &full_article) {
-            eprintln!("Failed to write article cache for {}: {}", key, e);
-        }
+        store_article(cache_db, &full_article);
 
         Ok(full_article)
     } else {
         Err("Article not found on network.".into())
     }
 }
+
+// --- NIP-21 nostr: URI の参照先 ---
+
+/// Profiles and events referenced from article content. Events are keyed by
+/// `nip23::reference_key` (event id, and coordinate key for addressable events).
+#[derive(Debug, Default)]
+pub struct ResolvedReferences {
+    pub profiles: HashMap<PublicKey, ProfileMetadata>,
+    pub events: HashMap<String, TimelinePost>,
+}
+
+// 他のユーザーのプロフィールを取得する (24時間は DB_PROFILES のキャッシュを使う)
+async fn fetch_profiles(
+    cache_db: &LmdbCache,
+    client: &Client,
+    public_keys: HashSet<PublicKey>,
+) -> Result<HashMap<PublicKey, ProfileMetadata>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut profiles = HashMap::new();
+    let mut missing = Vec::new();
+    for public_key in public_keys {
+        match cache_db.read_cache::<ProfileMetadata>(DB_PROFILES, &public_key.to_hex()) {
+            Ok(cache) => {
+                profiles.insert(public_key, cache.data);
+            }
+            Err(_) => missing.push(public_key),
+        }
+    }
+    if missing.is_empty() {
+        return Ok(profiles);
+    }
+
+    let filter = Filter::new().authors(missing.clone()).kind(Kind::Metadata);
+    let mut metadata_events: Vec<Event> = client
+        .fetch_events(filter, Duration::from_secs(5))
+        .await?
+        .into_iter()
+        .collect();
+    // 同じ著者のイベントが複数あれば新しいものを残す
+    metadata_events.sort_by_key(|event| event.created_at);
+    for event in metadata_events {
+        if let Ok(metadata) = serde_json::from_str::<ProfileMetadata>(&event.content) {
+            profiles.insert(event.pubkey, metadata);
+        }
+    }
+    for public_key in missing {
+        if let Some(metadata) = profiles.get(&public_key) {
+            if let Err(e) = cache_db.write_cache(DB_PROFILES, &public_key.to_hex(), metadata) {
+                eprintln!("Failed to write profile cache for {}: {}", public_key, e);
+            }
+        } else {
+            // 見つからなかったプロフィールも空のまま返して、再取得を繰り返さないようにする
+            profiles.insert(public_key, ProfileMetadata::default());
+        }
+    }
+    Ok(profiles)
+}
+
+// 本文中の nostr: URI が指すプロフィール・イベント・記事を取得する
+pub async fn resolve_nostr_references(
+    cache_db: &LmdbCache,
+    client: &Client,
+    entities: Vec<Nip19>,
+) -> Result<ResolvedReferences, Box<dyn std::error::Error + Send + Sync>> {
+    let mut resolved = ResolvedReferences::default();
+    let mut public_keys: HashSet<PublicKey> = HashSet::new();
+    let mut event_ids: Vec<EventId> = Vec::new();
+    let mut coordinates: Vec<Coordinate> = Vec::new();
+    for entity in entities {
+        match entity {
+            Nip19::Pubkey(public_key) => {
+                public_keys.insert(public_key);
+            }
+            Nip19::Profile(profile) => {
+                public_keys.insert(profile.public_key);
+            }
+            Nip19::EventId(event_id) => event_ids.push(event_id),
+            Nip19::Event(event) => event_ids.push(event.event_id),
+            Nip19::Coordinate(naddr) => {
+                let coordinate = naddr.coordinate;
+                let key = nip23::coordinate_key(coordinate.kind, &coordinate.public_key, &coordinate.identifier);
+                match load_cached_article(cache_db, &key) {
+                    Some(article) => {
+                        resolved.events.insert(key, timeline_post_from_article(&article));
+                    }
+                    None => coordinates.push(coordinate),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut events: Vec<Event> = Vec::new();
+    if !event_ids.is_empty() {
+        let filter = Filter::new().ids(event_ids);
+        events.extend(client.fetch_events(filter, Duration::from_secs(10)).await?);
+    }
+    let coordinate_fetches = coordinates.iter().map(|coordinate| {
+        let filter = Filter::new()
+            .kind(coordinate.kind)
+            .authors(vec![coordinate.public_key])
+            .identifier(coordinate.identifier.clone());
+        client.fetch_events(filter, Duration::from_secs(10))
+    });
+    for result in join_all(coordinate_fetches).await {
+        match result {
+            Ok(fetched) => events.extend(latest_by_coordinate(fetched)),
+            Err(e) => eprintln!("Failed to fetch referenced article: {e}"),
+        }
+    }
+
+    public_keys.extend(events.iter().map(|event| event.pubkey));
+    resolved.profiles = fetch_profiles(cache_db, client, public_keys).await?;
+
+    for event in events {
+        let author_metadata = resolved.profiles.get(&event.pubkey).cloned().unwrap_or_default();
+        if event.kind.is_addressable() {
+            let article = article_from_event(&event, author_metadata);
+            if article.kind == Kind::from(30023) {
+                store_article(cache_db, &article);
+            }
+            let post = timeline_post_from_article(&article);
+            resolved.events.insert(event.id.to_hex(), post.clone());
+            resolved.events.insert(article.coordinate_key(), post);
+        } else {
+            resolved.events.insert(event.id.to_hex(), timeline_post_from_note(&event, author_metadata));
+        }
+    }
+    Ok(resolved)
+}
+
+// --- NIP-84 ハイライト ---
+
+const KIND_HIGHLIGHT: u16 = 9802;
+
+fn highlight_from_event(event: &Event, author_metadata: ProfileMetadata) -> Highlight {
+    let comment = event
+        .tags
+        .iter()
+        .find_map(|tag| match tag.as_slice() {
+            [name, value, ..] if name == "comment" => Some(value.clone()),
+            _ => None,
+        })
+        .unwrap_or_default();
+    Highlight {
+        id: event.id,
+        author_pubkey: event.pubkey,
+        author_metadata,
+        content: event.content.clone(),
+        comment,
+        created_at: event.created_at,
+    }
+}
+
+// 記事に付けられたハイライトのうち、指定したユーザー (自分とフォロー中) のものを取得する
+pub async fn fetch_highlights(
+    cache_db: &LmdbCache,
+    client: &Client,
+    article: &ArticleFull,
+    authors: HashSet<PublicKey>,
+) -> Result<Vec<Highlight>, Box<dyn std::error::Error + Send + Sync>> {
+    let coordinate = article.coordinate();
+    let by_coordinate = Filter::new()
+        .kind(Kind::from(KIND_HIGHLIGHT))
+        .authors(authors.clone())
+        .coordinates([&coordinate]);
+    let by_id = Filter::new()
+        .kind(Kind::from(KIND_HIGHLIGHT))
+        .authors(authors)
+        .events([article.id]);
+
+    let mut events: HashMap<EventId, Event> = HashMap::new();
+    for filter in [by_coordinate, by_id] {
+        for event in client.fetch_events(filter, Duration::from_secs(5)).await? {
+            events.insert(event.id, event);
+        }
+    }
+
+    let author_pubkeys: HashSet<PublicKey> = events.values().map(|event| event.pubkey).collect();
+    let profiles = fetch_profiles(cache_db, client, author_pubkeys).await?;
+    let mut highlights: Vec<Highlight> = events
+        .values()
+        .map(|event| highlight_from_event(event, profiles.get(&event.pubkey).cloned().unwrap_or_default()))
+        .collect();
+    highlights.sort_by_key(|highlight| highlight.created_at);
+    Ok(highlights)
+}
+
+// 記事の一節をハイライトとして公開する (記事の a / e タグと著者の p タグで参照する)
+pub async fn publish_highlight(
+    client: &Client,
+    keys: &Keys,
+    article: &ArticleFull,
+    text: &str,
+    comment: &str,
+    my_metadata: ProfileMetadata,
+) -> Result<Highlight, Box<dyn std::error::Error + Send + Sync>> {
+    let author_hex = article.author_pubkey.to_hex();
+    let mut tags = vec![
+        NostrTag::parse(["a", article.coordinate_key().as_str()])?,
+        NostrTag::event(article.id),
+        NostrTag::parse(["p", author_hex.as_str(), "", "author"])?,
+    ];
+    if !comment.trim().is_empty() {
+        tags.push(NostrTag::parse(["comment", comment.trim()])?);
+    }
+    let event = EventBuilder::new(Kind::from(KIND_HIGHLIGHT), text)
+        .tags(tags)
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(highlight_from_event(&event, my_metadata))
+}
+
+// --- NIP-25 リアクション ---
+
+// 空の content は "+" と同じ扱い
+fn normalize_reaction(content: &str) -> String {
+    let content = content.trim();
+    if content.is_empty() { "+".to_string() } else { content.to_string() }
+}
+
+// LMDB に保存された集計 (24時間以内のもの) を読み込む
+pub fn load_cached_reactions(cache_db: &LmdbCache, coordinate_key: &str) -> Option<ReactionSummary> {
+    cache_db
+        .read_cache::<ReactionSummary>(DB_REACTIONS, coordinate_key)
+        .ok()
+        .map(|cache| cache.data)
+}
+
+pub fn save_reactions(cache_db: &LmdbCache, coordinate_key: &str, summary: &ReactionSummary) {
+    if let Err(e) = cache_db.write_cache(DB_REACTIONS, coordinate_key, summary) {
+        eprintln!("Failed to write reaction cache for {}: {}", coordinate_key, e);
+    }
+}
+
+const REACTION_PAGE_SIZE: usize = 500;
+const MAX_REACTION_PAGES: usize = 20;
+
+// 複数の記事へのリアクションを `a` タグと `e` タグの両方で取得して集計する
+// すべてのページを取得できたときだけ LMDB に保存する (途中までの件数を 24 時間残さないため)
+pub async fn fetch_reaction_summaries(
+    cache_db: &LmdbCache,
+    client: &Client,
+    articles: Vec<(EventId, Coordinate)>,
+    my_pubkey: Option<PublicKey>,
+) -> Result<HashMap<String, ReactionSummary>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut summaries: HashMap<String, ReactionSummary> = HashMap::new();
+    // `e` タグだけを付けたリアクションのためのイベント ID から記事への対応
+    let mut keys_by_id: HashMap<String, String> = HashMap::new();
+    for (event_id, c) in &articles {
+        let key = nip23::coordinate_key(c.kind, &c.public_key, &c.identifier);
+        keys_by_id.insert(event_id.to_hex(), key.clone());
+        summaries.insert(key, ReactionSummary::default());
+    }
+    if articles.is_empty() {
+        return Ok(summaries);
+    }
+
+    let coordinate_filter = Filter::new()
+        .kind(Kind::Reaction)
+        .coordinates(articles.iter().map(|(_, c)| c));
+    let id_filter = Filter::new()
+        .kind(Kind::Reaction)
+        .events(articles.iter().map(|(id, _)| *id));
+    let (by_coordinate, coordinates_complete) =
+        fetch_pages(client, coordinate_filter, REACTION_PAGE_SIZE, MAX_REACTION_PAGES).await?;
+    let (by_id, ids_complete) = fetch_pages(client, id_filter, REACTION_PAGE_SIZE, MAX_REACTION_PAGES).await?;
+
+    // 同じ人が同じ内容で何度送っても 1 件として数える
+    let mut seen: HashSet<(String, PublicKey, String)> = HashSet::new();
+    for event in by_coordinate.iter().chain(by_id.iter()) {
+        let Some(key) = event.tags.iter().find_map(|tag| match tag.as_slice() {
+            [name, value, ..] if name == "a" && summaries.contains_key(value) => Some(value.clone()),
+            [name, value, ..] if name == "e" => keys_by_id.get(value).cloned(),
+            _ => None,
+        }) else {
+            continue;
+        };
+        let content = normalize_reaction(&event.content);
+        if !seen.insert((key.clone(), event.pubkey, content.clone())) {
+            continue;
+        }
+        if let Some(summary) = summaries.get_mut(&key) {
+            summary.add(&content, my_pubkey == Some(event.pubkey));
+        }
+    }
+
+    if coordinates_complete && ids_complete {
+        for (key, summary) in &summaries {
+            save_reactions(cache_db, key, summary);
+        }
+    }
+    Ok(summaries)
+}
+
+// 記事にリアクション (kind 7) を送る
+pub async fn publish_reaction(
+    client: &Client,
+    keys: &Keys,
+    event_id: EventId,
+    coordinate: &Coordinate,
+    content: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let coordinate_key = nip23::coordinate_key(coordinate.kind, &coordinate.public_key, &coordinate.identifier);
+    let author = coordinate.public_key.to_hex();
+    let event = EventBuilder::new(Kind::Reaction, normalize_reaction(content))
+        .tags([
+            NostrTag::parse(["e", event_id.to_hex().as_str(), "", author.as_str()])?,
+            NostrTag::parse(["a", coordinate_key.as_str()])?,
+            NostrTag::parse(["p", author.as_str()])?,
+            NostrTag::parse(["k", coordinate.kind.as_u16().to_string().as_str()])?,
+        ])
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(())
+}
+
+// --- NIP-50 リレー検索 ---
+
+// 検索リレー (kind 10007) が未設定のときに使うリレー
+pub const DEFAULT_SEARCH_RELAYS: &str = "wss://relay.nostr.band\nwss://search.nos.today";
+
+/// Articles and profiles returned by the search relays, deduplicated by address
+/// and by public key.
+#[derive(Debug, Default)]
+pub struct RelaySearchResults {
+    pub articles: Vec<TimelinePost>,
+    pub profiles: Vec<(PublicKey, ProfileMetadata)>,
+}
+
+// 自分の検索リレーのリスト (NIP-51 kind 10007) を取得する。リストがなければ None
+pub async fn fetch_search_relays(
+    client: &Client,
+    keys: &Keys,
+) -> Result<Option<Vec<String>>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new().authors(vec![keys.public_key()]).kind(Kind::SearchRelays).limit(1);
+    let events = client.fetch_events(filter, Duration::from_secs(10)).await?;
+    Ok(events.into_iter().max_by_key(|e| e.created_at).map(|event| {
+        event
+            .tags
+            .iter()
+            .filter_map(|tag| match tag.as_slice() {
+                [name, url, ..] if name == "relay" => Some(url.clone()),
+                _ => None,
+            })
+            .collect()
+    }))
+}
+
+pub async fn publish_search_relays(
+    client: &Client,
+    keys: &Keys,
+    search_relays: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut tags = Vec::new();
+    for url in search_relays.lines().map(str::trim).filter(|url| !url.is_empty()) {
+        RelayUrl::parse(url).map_err(|e| format!("無効なリレーURLです ({url}): {e}"))?;
+        tags.push(NostrTag::parse(["relay", url])?);
+    }
+    let event = EventBuilder::new(Kind::SearchRelays, "").tags(tags).sign(keys).await?;
+    client.send_event(&event).await?;
+    Ok(())
+}
+
+// 検索リレーに NIP-50 の search フィルターで記事 (kind 30023) とプロフィール (kind 0) を問い合わせる
+pub async fn search_on_relays(
+    cache_db: &LmdbCache,
+    keys: &Keys,
+    search_relays: &str,
+    query: &str,
+) -> Result<RelaySearchResults, Box<dyn std::error::Error + Send + Sync>> {
+    let search_client = Client::new(keys.clone());
+    // 途中で失敗しても検索用のクライアントは必ず閉じる
+    let result = search_with_client(cache_db, &search_client, search_relays, query).await;
+    search_client.shutdown().await;
+    result
+}
+
+async fn search_with_client(
+    cache_db: &LmdbCache,
+    search_client: &Client,
+    search_relays: &str,
+    query: &str,
+) -> Result<RelaySearchResults, Box<dyn std::error::Error + Send + Sync>> {
+    for relay_url in search_relays.lines().filter(|url| !url.trim().is_empty()) {
+        search_client.add_relay(relay_url.trim()).await?;
+    }
+    search_client.connect().await;
+
+    let article_filter = Filter::new().kind(Kind::from(30023)).search(query).limit(30);
+    let profile_filter = Filter::new().kind(Kind::Metadata).search(query).limit(20);
+    let (article_events, profile_events) = tokio::join!(
+        search_client.fetch_events(article_filter, Duration::from_secs(10)),
+        search_client.fetch_events(profile_filter, Duration::from_secs(10)),
+    );
+    let article_events = latest_by_coordinate(article_events?);
+    let deletions = match fetch_deletions(search_client, &article_events).await {
+        Ok(deletions) => deletions,
+        Err(e) => {
+            eprintln!("Failed to fetch deletion requests: {e}");
+            Vec::new()
+        }
+    };
+    let article_events: Vec<Event> =
+        article_events.into_iter().filter(|event| !is_deleted(event, &deletions)).collect();
+
+    // 同じユーザーのプロフィールは新しいものを残す
+    let mut latest_profiles: HashMap<PublicKey, Event> = HashMap::new();
+    for event in profile_events? {
+        if latest_profiles.get(&event.pubkey).is_none_or(|existing| event.created_at > existing.created_at) {
+            latest_profiles.insert(event.pubkey, event);
+        }
+    }
+    let mut profiles: Vec<(PublicKey, ProfileMetadata)> = latest_profiles
+        .into_values()
+        .filter_map(|event| {
+            let metadata = serde_json::from_str::<ProfileMetadata>(&event.content).ok()?;
+            if let Err(e) = cache_db.write_cache(DB_PROFILES, &event.pubkey.to_hex(), &metadata) {
+                eprintln!("Failed to write profile cache for {}: {}", event.pubkey, e);
+            }
+            Some((event.pubkey, metadata))
+        })
+        .collect();
+    profiles.sort_by(|a, b| a.1.name.cmp(&b.1.name));
+
+    let author_pubkeys: HashSet<PublicKey> = article_events.iter().map(|e| e.pubkey).collect();
+    let authors = fetch_profiles(cache_db, search_client, author_pubkeys).await?;
+    let mut articles = Vec::new();
+    for event in article_events {
+        let article = article_from_event(&event, authors.get(&event.pubkey).cloned().unwrap_or_default());
+        store_article(cache_db, &article);
+        articles.push(timeline_post_from_article(&article));
+    }
+    articles.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+
+    Ok(RelaySearchResults { articles, profiles })
+}
+
+// --- NIP-51 興味リスト (kind 10015) とハッシュタグ ---
+
+// 最新の興味リストのタグ。リストがまだなければ None
+async fn fetch_interest_tags(
+    client: &Client,
+    keys: &Keys,
+) -> Result<Option<Vec<NostrTag>>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new().authors(vec![keys.public_key()]).kind(Kind::Interests).limit(1);
+    let events = client.fetch_events(filter, Duration::from_secs(10)).await?;
+    Ok(events.into_iter().max_by_key(|e| e.created_at).map(|event| event.tags.to_vec()))
+}
+
+// 興味リストの t タグ (ピン留めしたハッシュタグ)
+fn interests_from_tags(tags: &[NostrTag]) -> Vec<String> {
+    let mut interests: Vec<String> = Vec::new();
+    for tag in tags {
+        if let [name, value, ..] = tag.as_slice() {
+            let value = value.to_lowercase();
+            if name == "t" && !value.is_empty() && !interests.contains(&value) {
+                interests.push(value);
+            }
+        }
+    }
+    interests
+}
+
+// 興味リストのハッシュタグ。リストがまだなければ None
+pub async fn fetch_interests(
+    client: &Client,
+    keys: &Keys,
+) -> Result<Option<Vec<String>>, Box<dyn std::error::Error + Send + Sync>> {
+    Ok(fetch_interest_tags(client, keys).await?.map(|tags| interests_from_tags(&tags)))
+}
+
+// 最新の興味リストにハッシュタグを足して (または取り除いて) 公開し、公開したリストのハッシュタグを返す。
+// ほかのクライアントが入れたタグ (a タグの興味セットなど) と、その間にほかで変わったハッシュタグは残す。
+// リストを取得できないときは、読み込んだときにリストがないと確かめていた (list_missing) ときだけ新しく作る
+pub async fn update_interest(
+    client: &Client,
+    keys: &Keys,
+    label: &str,
+    pinned: bool,
+    list_missing: bool,
+) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut tags = match fetch_interest_tags(client, keys).await? {
+        Some(tags) => tags,
+        None if list_missing => Vec::new(),
+        None => return Err("興味リストを取得できませんでした".into()),
+    };
+    let is_label = |tag: &NostrTag| {
+        matches!(tag.as_slice(), [name, value, ..] if name == "t" && value.to_lowercase() == label)
+    };
+    if !pinned {
+        tags.retain(|tag| !is_label(tag));
+    } else if !tags.iter().any(is_label) {
+        tags.push(NostrTag::hashtag(label));
+    }
+    let event = EventBuilder::new(Kind::Interests, "").tags(tags.clone()).sign(keys).await?;
+    client.send_event(&event).await?;
+    Ok(interests_from_tags(&tags))
+}
+
+// ハッシュタグ (#t) の付いた記事をリレーに問い合わせる
+pub async fn fetch_hashtag_articles(
+    cache_db: &LmdbCache,
+    client: &Client,
+    hashtag: &str,
+) -> Result<Vec<TimelinePost>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new().kind(Kind::from(30023)).hashtag(hashtag.to_lowercase()).limit(50);
+    let events = client.fetch_events(filter, Duration::from_secs(10)).await?;
+    let events = latest_by_coordinate(events);
+    let deletions = fetch_deletions(client, &events).await.unwrap_or_else(|e| {
+        eprintln!("Failed to fetch deletion requests: {e}");
+        Vec::new()
+    });
+    let events: Vec<Event> = events.into_iter().filter(|event| !is_deleted(event, &deletions)).collect();
+
+    let authors: HashSet<PublicKey> = events.iter().map(|e| e.pubkey).collect();
+    let profiles = fetch_profiles(cache_db, client, authors).await?;
+    let mut posts: Vec<TimelinePost> = events
+        .iter()
+        .map(|event| {
+            let article = article_from_event(event, profiles.get(&event.pubkey).cloned().unwrap_or_default());
+            store_article(cache_db, &article);
+            timeline_post_from_article(&article)
+        })
+        .collect();
+    posts.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+    Ok(posts)
+}
+
+// --- NIP-51 ブックマーク ---
+
+// kind 10003 の公開タグと、content を復号した非公開タグ
+#[derive(Default)]
+struct BookmarkList {
+    public_tags: Vec<NostrTag>,
+    private_tags: Vec<Vec<String>>,
+}
+
+fn article_bookmark_key(tag: &[String]) -> Option<&str> {
+    match tag {
+        [name, value, ..] if name == "a" && value.starts_with("30023:") => Some(value.as_str()),
+        _ => None,
+    }
+}
+
+pub fn load_bookmarks(cache_db: &LmdbCache, owner: &PublicKey) -> Vec<Bookmark> {
+    cache_db
+        .read_persistent_cache::<Vec<Bookmark>>(DB_BOOKMARKS, &owner.to_hex())
+        .map(|cache| cache.data)
+        .unwrap_or_default()
+}
+
+// ブックマークを保存し、ブックマークした記事が期限切れで消えないよう固定する
+pub fn save_bookmarks(cache_db: &LmdbCache, owner: &PublicKey, bookmarks: &[Bookmark]) {
+    if let Err(e) = cache_db.write_cache(DB_BOOKMARKS, &owner.to_hex(), &bookmarks) {
+        eprintln!("Failed to write bookmarks: {e}");
+    }
+    for bookmark in bookmarks {
+        if let Err(e) = cache_db.set_pinned(DB_ARTICLES, &bookmark.coordinate_key, true) {
+            eprintln!("Failed to pin article {}: {}", bookmark.coordinate_key, e);
+        }
+    }
+}
+
+fn unpin_article(cache_db: &LmdbCache, coordinate_key: &str) {
+    if let Err(e) = cache_db.set_pinned(DB_ARTICLES, coordinate_key, false) {
+        eprintln!("Failed to unpin article {}: {}", coordinate_key, e);
+    }
+}
+
+// 最新のブックマークリストを取得する。リストがまだなければ None
+async fn fetch_bookmark_list(
+    client: &Client,
+    keys: &Keys,
+) -> Result<Option<BookmarkList>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new().authors(vec![keys.public_key()]).kind(Kind::Bookmarks).limit(1);
+    let events = client.fetch_events(filter, Duration::from_secs(10)).await?;
+    let Some(event) = events.into_iter().max_by_key(|e| e.created_at) else {
+        return Ok(None);
+    };
+    let private_tags = if event.content.is_empty() {
+        Vec::new()
+    } else {
+        // 復号できないまま上書きすると非公開のブックマークが消えるので、エラーにする
+        let plaintext = nip44::decrypt(keys.secret_key(), &keys.public_key(), &event.content)
+            .map_err(|e| format!("非公開ブックマークを復号できませんでした: {e}"))?;
+        serde_json::from_str(&plaintext)?
+    };
+    Ok(Some(BookmarkList {
+        public_tags: event.tags.to_vec(),
+        private_tags,
+    }))
+}
+
+// リストの記事ブックマークを取り出す。既読の状態は手元のものを引き継ぐ
+fn bookmarks_from_list(list: &BookmarkList, previous: &[Bookmark]) -> Vec<Bookmark> {
+    let public = list.public_tags.iter().filter_map(|tag| article_bookmark_key(tag.as_slice())).map(|key| (key, false));
+    let private = list.private_tags.iter().filter_map(|tag| article_bookmark_key(tag)).map(|key| (key, true));
+    let mut bookmarks: Vec<Bookmark> = Vec::new();
+    for (key, is_private) in public.chain(private) {
+        if bookmarks.iter().any(|b| b.coordinate_key == key) {
+            continue;
+        }
+        bookmarks.push(Bookmark {
+            coordinate_key: key.to_string(),
+            is_private,
+            is_read: previous.iter().any(|b| b.coordinate_key == key && b.is_read),
+        });
+    }
+    bookmarks
+}
+
+// リレーのブックマークリストを手元に反映する。リストが見つからなければ None を返す
+// (リレーに届かなかった場合も含まれるので、呼び出し側は手元のものを残す)
+pub async fn sync_bookmarks(
+    cache_db: &LmdbCache,
+    client: &Client,
+    keys: &Keys,
+    local: Vec<Bookmark>,
+) -> Result<Option<Vec<Bookmark>>, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(list) = fetch_bookmark_list(client, keys).await? else {
+        return Ok(None);
+    };
+    let bookmarks = bookmarks_from_list(&list, &local);
+    for removed in local.iter().filter(|b| !bookmarks.iter().any(|n| n.coordinate_key == b.coordinate_key)) {
+        unpin_article(cache_db, &removed.coordinate_key);
+    }
+    save_bookmarks(cache_db, &keys.public_key(), &bookmarks);
+    Ok(Some(bookmarks))
+}
+
+// 記事をブックマークに追加する (Some(非公開かどうか)) か、ブックマークから外す (None)。
+// ほかのクライアントが入れたブックマークはそのまま残す。
+// リストが取得できないときは、取得し損ねたリストを空で上書きしないよう、
+// 前回の同期でリストがないと確かめていた (list_missing) ときだけ新しく作る
+pub async fn update_bookmark(
+    cache_db: &LmdbCache,
+    client: &Client,
+    keys: &Keys,
+    local: Vec<Bookmark>,
+    coordinate_key: &str,
+    bookmark: Option<bool>,
+    list_missing: bool,
+) -> Result<Vec<Bookmark>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut list = match fetch_bookmark_list(client, keys).await? {
+        Some(list) => list,
+        None if list_missing => BookmarkList::default(),
+        None => return Err("ブックマークリストを取得できませんでした".into()),
+    };
+    list.public_tags.retain(|tag| article_bookmark_key(tag.as_slice()) != Some(coordinate_key));
+    list.private_tags.retain(|tag| article_bookmark_key(tag) != Some(coordinate_key));
+    match bookmark {
+        Some(true) => list.private_tags.push(vec!["a".to_string(), coordinate_key.to_string()]),
+        Some(false) => list.public_tags.push(NostrTag::parse(["a", coordinate_key])?),
+        None => {}
+    }
+
+    let content = if list.private_tags.is_empty() {
+        String::new()
+    } else {
+        nip44::encrypt(
+            keys.secret_key(),
+            &keys.public_key(),
+            serde_json::to_string(&list.private_tags)?,
+            nip44::Version::V2,
+        )?
+    };
+    let event = EventBuilder::new(Kind::Bookmarks, content)
+        .tags(list.public_tags.clone())
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+
+    if bookmark.is_none() {
+        unpin_article(cache_db, coordinate_key);
+    }
+    let bookmarks = bookmarks_from_list(&list, &local);
+    save_bookmarks(cache_db, &keys.public_key(), &bookmarks);
+    Ok(bookmarks)
+}
+
+// --- NIP-22 コメント ---
+
+const KIND_COMMENT: u16 = 1111;
+
+fn comment_from_event(event: &Event, author_metadata: ProfileMetadata) -> ArticleComment {
+    // 親がコメント (k = 1111) のときだけ e タグを返信先として扱う
+    let parent_is_comment = event.tags.iter().any(|tag| {
+        matches!(tag.as_slice(), [name, value, ..] if name == "k" && *value == KIND_COMMENT.to_string())
+    });
+    let parent_id = if parent_is_comment {
+        event.tags.iter().find_map(|tag| match tag.as_slice() {
+            [name, value, ..] if name == "e" => EventId::from_hex(value).ok(),
+            _ => None,
+        })
+    } else {
+        None
+    };
+    ArticleComment {
+        id: event.id,
+        author_pubkey: event.pubkey,
+        author_metadata,
+        content: event.content.clone(),
+        created_at: event.created_at,
+        parent_id,
+    }
+}
+
+// 記事をルート (A タグ) とするコメントを取得する。削除されたものは除く
+pub async fn fetch_comments(
+    cache_db: &LmdbCache,
+    client: &Client,
+    article: &ArticleFull,
+) -> Result<Vec<ArticleComment>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .kind(Kind::from(KIND_COMMENT))
+        .custom_tag(SingleLetterTag::uppercase(Alphabet::A), article.coordinate_key())
+        .limit(500);
+    let events: Vec<Event> = client
+        .fetch_events(filter, Duration::from_secs(10))
+        .await?
+        .into_iter()
+        .collect();
+
+    let deletions = match fetch_deletions(client, &events).await {
+        Ok(deletions) => deletions,
+        Err(e) => {
+            eprintln!("Failed to fetch deletion requests: {e}");
+            Vec::new()
+        }
+    };
+    let events: Vec<Event> = events.into_iter().filter(|event| !is_deleted(event, &deletions)).collect();
+
+    let author_pubkeys: HashSet<PublicKey> = events.iter().map(|event| event.pubkey).collect();
+    let profiles = fetch_profiles(cache_db, client, author_pubkeys).await?;
+    let mut comments: Vec<ArticleComment> = events
+        .iter()
+        .map(|event| comment_from_event(event, profiles.get(&event.pubkey).cloned().unwrap_or_default()))
+        .collect();
+    comments.sort_by_key(|comment| comment.created_at);
+    Ok(comments)
+}
+
+// 記事、または記事へのコメントに返信する
+pub async fn publish_comment(
+    client: &Client,
+    keys: &Keys,
+    article: &ArticleFull,
+    parent: Option<&ArticleComment>,
+    content: &str,
+    my_metadata: ProfileMetadata,
+) -> Result<ArticleComment, Box<dyn std::error::Error + Send + Sync>> {
+    let coordinate_key = article.coordinate_key();
+    let article_kind = article.kind.as_u16().to_string();
+    let article_author = article.author_pubkey.to_hex();
+    let mut tags = vec![
+        NostrTag::parse(["A", coordinate_key.as_str()])?,
+        NostrTag::parse(["K", article_kind.as_str()])?,
+        NostrTag::parse(["P", article_author.as_str()])?,
+    ];
+    match parent {
+        Some(parent) => {
+            let parent_id = parent.id.to_hex();
+            let parent_author = parent.author_pubkey.to_hex();
+            tags.push(NostrTag::parse(["e", parent_id.as_str(), "", parent_author.as_str()])?);
+            tags.push(NostrTag::parse(["k", KIND_COMMENT.to_string().as_str()])?);
+            tags.push(NostrTag::parse(["p", parent_author.as_str()])?);
+        }
+        None => {
+            tags.push(NostrTag::parse(["a", coordinate_key.as_str()])?);
+            tags.push(NostrTag::parse(["e", article.id.to_hex().as_str(), "", article_author.as_str()])?);
+            tags.push(NostrTag::parse(["k", article_kind.as_str()])?);
+            tags.push(NostrTag::parse(["p", article_author.as_str()])?);
+        }
+    }
+
+    let event = EventBuilder::new(Kind::from(KIND_COMMENT), content)
+        .tags(tags)
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(comment_from_event(&event, my_metadata))
+}
+
+// 自分のコメントに対して NIP-09 の削除リクエストを送る
+pub async fn delete_comment(
+    client: &Client,
+    keys: &Keys,
+    comment: &ArticleComment,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if comment.author_pubkey != keys.public_key() {
+        return Err("自分のコメント以外は削除できません".into());
+    }
+    let event = EventBuilder::new(Kind::EventDeletion, "")
+        .tags([
+            NostrTag::event(comment.id),
+            NostrTag::parse(["k", KIND_COMMENT.to_string().as_str()])?,
+        ])
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(())
+}
+
+// ユーザーのプロフィールと公開済みの記事を取得する
+pub async fn fetch_user_articles(
+    cache_db: &LmdbCache,
+    client: &Client,
+    public_key: PublicKey,
+) -> Result<(ProfileMetadata, Vec<TimelinePost>), Box<dyn std::error::Error + Send + Sync>> {
+    let metadata = fetch_profiles(cache_db, client, HashSet::from([public_key]))
+        .await?
+        .remove(&public_key)
+        .unwrap_or_default();
+
+    let filter = Filter::new()
+        .authors(vec![public_key])
+        .kind(Kind::from(30023))
+        .limit(50);
+    let events = latest_by_coordinate(client.fetch_events(filter, Duration::from_secs(10)).await?);
+    let deletions = match fetch_deletions(client, &events).await {
+        Ok(deletions) => deletions,
+        Err(e) => {
+            eprintln!("Failed to fetch deletion requests: {e}");
+            Vec::new()
+        }
+    };
+
+    let mut posts: Vec<TimelinePost> = events
+        .iter()
+        .filter(|event| !is_deleted(event, &deletions))
+        .map(|event| {
+            let article = article_from_event(event, metadata.clone());
+            store_article(cache_db, &article);
+            timeline_post_from_article(&article)
+        })
+        .collect();
+    posts.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+    Ok((metadata, posts))
+}
+
+// --- NIP-23 下書き (kind 30024) ---
+
+// 下書きは複数アカウントで混ざらないよう、所有者の公開鍵を先頭に付けたキーで保存する
+fn draft_key(owner: &PublicKey, identifier: &str) -> String {
+    format!("{}:{}", owner.to_hex(), identifier)
+}
+
+// ローカルに保存された下書きを新しい順に読み込む
+pub fn load_local_drafts(cache_db: &LmdbCache, owner: &PublicKey) -> Vec<ArticleDraft> {
+    let prefix = draft_key(owner, "");
+    let mut drafts: Vec<ArticleDraft> = match cache_db.read_all_cache::<ArticleDraft>(DB_DRAFTS) {
+        Ok(entries) => entries
+            .into_iter()
+            .filter(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, cache)| cache.data)
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to load drafts: {e}");
+            Vec::new()
+        }
+    };
+    drafts.sort_by_key(|d| std::cmp::Reverse(d.updated_at));
+    drafts
+}
+
+pub fn save_local_draft(cache_db: &LmdbCache, owner: &PublicKey, draft: &ArticleDraft) {
+    if let Err(e) = cache_db.write_cache(DB_DRAFTS, &draft_key(owner, &draft.identifier), draft) {
+        eprintln!("Failed to save draft {}: {}", draft.identifier, e);
+    }
+}
+
+pub fn delete_local_draft(cache_db: &LmdbCache, owner: &PublicKey, identifier: &str) {
+    if let Err(e) = cache_db.delete_cache(DB_DRAFTS, &draft_key(owner, identifier)) {
+        eprintln!("Failed to delete draft {identifier}: {e}");
+    }
+}
+
+// アカウントごとの設定を読み込む (未保存なら既定値)
+pub fn load_user_settings(cache_db: &LmdbCache, owner: &PublicKey) -> UserSettings {
+    cache_db
+        .read_persistent_cache::<UserSettings>(DB_SETTINGS, &owner.to_hex())
+        .map(|cache| cache.data)
+        .unwrap_or_default()
+}
+
+pub fn save_user_settings(cache_db: &LmdbCache, owner: &PublicKey, settings: &UserSettings) {
+    if let Err(e) = cache_db.write_cache(DB_SETTINGS, &owner.to_hex(), settings) {
+        eprintln!("Failed to save settings: {e}");
+    }
+}
+
+// 下書きを自分宛てに NIP-44 で暗号化し、kind 30024 としてリレーに保存する
+pub async fn publish_draft(
+    client: &Client,
+    keys: &Keys,
+    draft: &ArticleDraft,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let plaintext = serde_json::to_string(draft)?;
+    let encrypted = nip44::encrypt(
+        keys.secret_key(),
+        &keys.public_key(),
+        plaintext,
+        nip44::Version::V2,
+    )?;
+    let event = EventBuilder::new(Kind::from(30024), encrypted)
+        .tags([NostrTag::identifier(draft.identifier.clone())])
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(())
+}
+
+// リレーに保存された下書きを取得する
+// 暗号化されていない (他のクライアントで作成された) 下書きはタグから復元する
+pub async fn fetch_relay_drafts(
+    client: &Client,
+    keys: &Keys,
+) -> Result<Vec<ArticleDraft>, Box<dyn std::error::Error + Send + Sync>> {
+    let filter = Filter::new()
+        .authors(vec![keys.public_key()])
+        .kind(Kind::from(30024));
+    let events = client.fetch_events(filter, Duration::from_secs(10)).await?;
+
+    let mut drafts = Vec::new();
+    for event in latest_by_coordinate(events) {
+        let draft = match nip44::decrypt(keys.secret_key(), &keys.public_key(), &event.content)
+            .ok()
+            .and_then(|plaintext| serde_json::from_str::<ArticleDraft>(&plaintext).ok())
+        {
+            Some(draft) => draft,
+            None => {
+                let metadata = nip23::ArticleMetadata::from_tags(event.tags.iter());
+                ArticleDraft {
+                    identifier: metadata.identifier,
+                    title: metadata.title,
+                    content: event.content.clone(),
+                    summary: metadata.summary,
+                    image: metadata.image,
+                    hashtags: metadata.hashtags.join(", "),
+                    published_at: metadata.published_at,
+                    media: metadata.media,
+                    is_edit: false,
+                    updated_at: event.created_at,
+                }
+            }
+        };
+        // 中身が空の下書きは無視する
+        if draft.title.is_empty() && draft.content.is_empty() {
+            continue;
+        }
+        drafts.push(draft);
+    }
+    Ok(drafts)
+}
+
+// リレー上の下書きを削除する (NIP-09)
+pub async fn delete_relay_draft(
+    client: &Client,
+    keys: &Keys,
+    identifier: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let coordinate = nip23::coordinate_key(Kind::from(30024), &keys.public_key(), identifier);
+    let event = EventBuilder::new(Kind::EventDeletion, "")
+        .tags([
+            NostrTag::parse(["a", coordinate.as_str()])?,
+            NostrTag::parse(["k", "30024"])?,
+        ])
+        .sign(keys)
+        .await?;
+    client.send_event(&event).await?;
+    Ok(())
+}
+
+// ローカルとリレーの下書きをマージする (同じ識別子なら新しい方を残す)
+pub fn merge_drafts(
+    cache_db: &LmdbCache,
+    owner: &PublicKey,
+    local: &mut Vec<ArticleDraft>,
+    remote: Vec<ArticleDraft>,
+) {
+    for draft in remote {
+        match local.iter_mut().find(|d| d.identifier == draft.identifier) {
+            Some(existing) if existing.updated_at >= draft.updated_at => {}
+            Some(existing) => {
+                save_local_draft(cache_db, owner, &draft);
+                *existing = draft;
+            }
+            None => {
+                save_local_draft(cache_db, owner, &draft);
+                local.push(draft);
+            }
+        }
+    }
+    local.sort_by_key(|d| std::cmp::Reverse(d.updated_at));
+}
+
+// 再試行の間隔 (失敗するたびに倍にし、上限で止める)
+const PUBLISH_RETRY_BASE_SECS: u64 = 60;
+const PUBLISH_RETRY_MAX_SECS: u64 = 60 * 60;
+
+// 予約投稿キューを公開予定時刻の順に読み込む
+pub fn load_publish_queue(cache_db: &LmdbCache, owner: &PublicKey) -> Vec<ScheduledArticle> {
+    let mut queue: Vec<ScheduledArticle> = match cache_db.read_all_cache::<ScheduledArticle>(DB_PUBLISH_QUEUE) {
+        Ok(entries) => entries
+            .into_iter()
+            .map(|(_, cache)| cache.data)
+            .filter(|item| item.event.pubkey == *owner)
+            .collect(),
+        Err(e) => {
+            eprintln!("Failed to load publish queue: {e}");
+            Vec::new()
+        }
+    };
+    queue.sort_by_key(|item| item.publish_at);
+    queue
+}
+
+// 同じ記事の予約は1件だけ持つ (キーは記事のアドレス)
+pub fn save_scheduled_article(cache_db: &LmdbCache, item: &ScheduledArticle) {
+    if let Err(e) = cache_db.write_cache(DB_PUBLISH_QUEUE, &item.coordinate_key(), item) {
+        eprintln!("Failed to save scheduled article {}: {}", item.coordinate_key(), e);
+    }
+}
+
+pub fn remove_scheduled_article(cache_db: &LmdbCache, coordinate_key: &str) {
+    if let Err(e) = cache_db.delete_cache(DB_PUBLISH_QUEUE, coordinate_key) {
+        eprintln!("Failed to remove scheduled article {coordinate_key}: {e}");
+    }
+}
+
+/// Re-signs a queued article for a new publish time.
+/// The signed event's `created_at` is the publish time, so rescheduling needs a new
+/// signature. A `published_at` tag that matched the old time is moved too.
+pub async fn reschedule_article(
+    keys: &Keys,
+    item: &ScheduledArticle,
+    publish_at: Timestamp,
+) -> Result<ScheduledArticle, Box<dyn std::error::Error + Send + Sync>> {
+    let old_published_at = item.publish_at.as_u64().to_string();
+    let mut tags = Vec::new();
+    for tag in item.event.tags.iter() {
+        match tag.as_slice() {
+            [name, value] if name == "published_at" && *value == old_published_at => {
+                tags.push(NostrTag::parse(["published_at", publish_at.as_u64().to_string().as_str()])?);
+            }
+            _ => tags.push(tag.clone()),
+        }
+    }
+    let event = EventBuilder::new(item.event.kind, item.event.content.clone())
+        .tags(tags)
+        .custom_created_at(publish_at)
+        .sign(keys)
+        .await?;
+    Ok(ScheduledArticle::new(event, publish_at))
+}
+
+// 予約した記事をリレーに送信する (1つのリレーにも届かなければ失敗とする)
+pub async fn send_scheduled_article(
+    client: &Client,
+    item: &ScheduledArticle,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let output = client.send_event(&item.event).await?;
+    if output.success.is_empty() {
+        let reasons: Vec<String> = output.failed.values().cloned().collect();
+        return Err(format!("どのリレーにも送信できませんでした: {}", reasons.join(", ")).into());
+    }
+    Ok(())
+}
+
+// 送信に失敗した項目に次の再試行時刻を設定する
+pub fn schedule_retry(item: &mut ScheduledArticle, error: String) {
+    item.attempts += 1;
+    let delay = PUBLISH_RETRY_BASE_SECS
+        .saturating_mul(1 << item.attempts.min(10))
+        .min(PUBLISH_RETRY_MAX_SECS);
+    item.next_attempt_at = Timestamp::from(Timestamp::now().as_u64() + delay);
+    item.last_error = Some(error);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_tungstenite::tungstenite::Message;
+    use futures::{SinkExt, StreamExt};
+    use nostr::{filter::MatchEventOptions, ClientMessage, RelayMessage};
+    use std::sync::Arc;
+    use tokio::net::TcpListener;
+
+    // REQ を受け取るたびに、フィルターに合う `events` を新しい順に limit 件まで送ってから EOSE を返す
+    // リレーを起動し、その URL を返す
+    async fn start_mock_relay(events: Vec<Event>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        let events = Arc::new(events);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let events = events.clone();
+                tokio::spawn(async move {
+                    let Ok(mut ws) = async_tungstenite::tokio::accept_async(stream).await else {
+                        return;
+                    };
+                    while let Some(Ok(message)) = ws.next().await {
+                        let Message::Text(text) = message else {
+                            continue;
+                        };
+                        let Ok(ClientMessage::Req { subscription_id, filter }) = ClientMessage::from_json(&text) else {
+                            continue;
+                        };
+                        let subscription_id = subscription_id.into_owned();
+                        let mut matched: Vec<&Event> = events
+                            .iter()
+                            .filter(|event| filter.match_event(event, MatchEventOptions::new()))
+                            .collect();
+                        matched.sort_by_key(|event| std::cmp::Reverse(event.created_at));
+                        let mut replies: Vec<String> = matched
+                            .into_iter()
+                            .take(filter.limit.unwrap_or(usize::MAX))
+                            .map(|event| RelayMessage::event(subscription_id.clone(), event.clone()).as_json())
+                            .collect();
+                        replies.push(RelayMessage::eose(subscription_id).as_json());
+                        for reply in replies {
+                            if ws.send(Message::Text(reply)).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        url
+    }
+
+    fn article(keys: &Keys, identifier: &str, title: &str, content: &str) -> Event {
+        EventBuilder::new(Kind::LongFormTextNote, content)
+            .tags([
+                NostrTag::parse(["d", identifier]).unwrap(),
+                NostrTag::parse(["title", title]).unwrap(),
+            ])
+            .sign_with_keys(keys)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn searches_articles_and_profiles_on_relays() {
+        let author = Keys::generate();
+        let found = article(&author, "found", "検索の記事", "nostr のリレー検索を試す");
+        let deleted = article(&author, "deleted", "削除した記事", "nostr の削除された記事");
+        let deleted_key = nip23::coordinate_key(deleted.kind, &author.public_key(), "deleted");
+        let deletion = EventBuilder::new(Kind::EventDeletion, "")
+            .tags([NostrTag::parse(["a", deleted_key.as_str()]).unwrap()])
+            .sign_with_keys(&author)
+            .unwrap();
+        let profile = EventBuilder::new(Kind::Metadata, r#"{"name":"alice","about":"nostr で記事を書いています"}"#)
+            .sign_with_keys(&author)
+            .unwrap();
+        let unrelated = article(&Keys::generate(), "other", "関係ない記事", "検索語を含まない本文");
+        let relay_url = start_mock_relay(vec![found.clone(), deleted, deletion, profile, unrelated]).await;
+
+        let db_path = std::env::temp_dir().join(format!("relay-search-test-{}", std::process::id()));
+        let cache_db = LmdbCache::new(&db_path).unwrap();
+        let results = search_on_relays(&cache_db, &Keys::generate(), &relay_url, "nostr").await;
+        let _ = std::fs::remove_dir_all(&db_path);
+        let results = results.unwrap();
+
+        assert_eq!(results.articles.len(), 1);
+        assert_eq!(results.articles[0].id, found.id);
+        assert_eq!(results.articles[0].title, "検索の記事");
+        assert_eq!(results.articles[0].author_metadata.name, "alice");
+        assert_eq!(results.profiles.len(), 1);
+        assert_eq!(results.profiles[0].0, author.public_key());
+    }
+
+    #[tokio::test]
+    async fn relay_search_reports_invalid_relay_urls() {
+        let db_path = std::env::temp_dir().join(format!("relay-search-error-test-{}", std::process::id()));
+        let cache_db = LmdbCache::new(&db_path).unwrap();
+        let result = search_on_relays(&cache_db, &Keys::generate(), "not a relay url", "nostr").await;
+        let _ = std::fs::remove_dir_all(&db_path);
+        assert!(result.is_err());
+    }
+
+    async fn connect_to(relay_url: &str) -> Client {
+        let client = Client::new(Keys::generate());
+        client.add_relay(relay_url).await.unwrap();
+        client.connect().await;
+        client
+    }
+
+    fn article_at(keys: &Keys, identifier: &str, created_at: u64) -> Event {
+        EventBuilder::new(Kind::LongFormTextNote, "")
+            .tags([NostrTag::parse(["d", identifier]).unwrap()])
+            .custom_created_at(Timestamp::from(created_at))
+            .sign_with_keys(keys)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn newer_page_pages_back_to_since() {
+        let author = Keys::generate();
+        let since = 1_700_000_000;
+        let events: Vec<Event> = (0..250).map(|i| article_at(&author, &format!("a{i}"), since + i)).collect();
+        let client = connect_to(&start_mock_relay(events).await).await;
+
+        let filter = Filter::new().author(author.public_key()).kind(Kind::LongFormTextNote);
+        let (events, complete) = TimelinePage::Newer(Timestamp::from(since)).fetch(&client, filter).await.unwrap();
+        client.shutdown().await;
+
+        assert!(complete);
+        assert_eq!(events.len(), 250);
+    }
+
+    #[tokio::test]
+    async fn older_page_reaches_end_only_when_nothing_is_older() {
+        let author = Keys::generate();
+        let until = 1_700_000_000;
+        let client = connect_to(&start_mock_relay(vec![article_at(&author, "oldest", until)]).await).await;
+        let filter = Filter::new().author(author.public_key()).kind(Kind::LongFormTextNote);
+        let (_, reached_end) = TimelinePage::Older(Timestamp::from(until)).fetch(&client, filter).await.unwrap();
+        client.shutdown().await;
+        assert!(reached_end);
+
+        let client = connect_to(&start_mock_relay(vec![article_at(&author, "older", until - 60)]).await).await;
+        let filter = Filter::new().author(author.public_key()).kind(Kind::LongFormTextNote);
+        let (events, reached_end) = TimelinePage::Older(Timestamp::from(until)).fetch(&client, filter).await.unwrap();
+        client.shutdown().await;
+        assert!(!reached_end);
+        assert_eq!(events.len(), 1);
+    }
+}
@@ -0,0 +1,461 @@
+use nostr::EventId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{mpsc, OnceLock};
+
+use crate::cache_db::{LmdbCache, DB_ARTICLES, DB_SEARCH_DOCS, DB_SEARCH_INDEX};
+use crate::nostr_client::timeline_post_from_article;
+use crate::types::{ArticleFull, TimelinePost};
+
+// 文字 n-gram の長さ。日本語は単語がスペースで区切られないので 2-gram で引く
+const NGRAM: usize = 2;
+// タイトルに含まれる n-gram は本文の何倍に数えるか
+const TITLE_WEIGHT: u32 = 3;
+// スニペットでヒット箇所の前後に表示する文字数
+const SNIPPET_CONTEXT_CHARS: usize = 40;
+const MAX_RESULTS: usize = 50;
+// 索引の形式のバージョン。形式を変えたら上げると、起動時に索引を作り直す
+const INDEX_VERSION: u32 = 3;
+// 索引のバージョンを保存するキー (n-gram は空にならないので、投稿のキーとは重ならない)
+const VERSION_KEY: &str = "\0version";
+
+// 索引の更新は専用のスレッドで一つずつ行う
+static INDEXER: OnceLock<mpsc::Sender<IndexJob>> = OnceLock::new();
+
+enum IndexJob {
+    Add(Box<ArticleFull>),
+    Remove(String),
+}
+
+// coordinate_key -> 重み付きの出現回数
+type Postings = HashMap<String, u32>;
+
+// 索引済みの記事 (更新のときに古い n-gram を取り除くために保存する)
+#[derive(Serialize, Deserialize)]
+struct IndexedDoc {
+    event_id: EventId,
+    tokens: Vec<String>,
+    length: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub post: TimelinePost,
+    pub snippet: String,
+    // スニペット中のヒット箇所 (バイト範囲)
+    pub highlights: Vec<Range<usize>>,
+    pub score: f64,
+}
+
+// 半角カタカナ (U+FF66..=U+FF9F) に対応する全角の文字
+const HALF_WIDTH_KANA: &str = "ヲァィゥェォャュョッーアイウエオカキクケコサシスセソタチツテトナニヌネノハヒフヘホマミムメモヤユヨラリルレロワン゛゜";
+
+/// Normalizes a character for matching: full-width ASCII becomes half-width,
+/// half-width katakana becomes full-width, katakana is folded into hiragana and
+/// letters are lowercased. Always maps one character to one character, so
+/// positions in normalized text line up with the original.
+fn normalize_char(c: char) -> char {
+    let c = match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        '\u{3000}' => ' ',
+        '\u{FF66}'..='\u{FF9F}' => HALF_WIDTH_KANA.chars().nth(c as usize - 0xFF66).unwrap_or(c),
+        _ => c,
+    };
+    let c = match c {
+        '\u{30A1}'..='\u{30F6}' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    };
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn normalize(text: &str) -> Vec<char> {
+    text.chars().map(normalize_char).collect()
+}
+
+// 文字・数字の並びごとに n-gram に分ける (n 文字に満たない並びはそのまま 1 つの語にする)
+fn ngrams(chars: &[char]) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for run in chars.split(|c| !c.is_alphanumeric()).filter(|run| !run.is_empty()) {
+        if run.len() < NGRAM {
+            tokens.push(run.iter().collect());
+        } else {
+            tokens.extend(run.windows(NGRAM).map(|window| window.iter().collect::<String>()));
+        }
+    }
+    tokens
+}
+
+fn token_counts(text: &str) -> HashMap<String, u32> {
+    let mut counts = HashMap::new();
+    for token in ngrams(&normalize(text)) {
+        *counts.entry(token).or_insert(0) += 1;
+    }
+    counts
+}
+
+// 索引のキーは "n-gram\0coordinate_key" で、値は重み付きの出現回数
+// (記事ごとにキーを分けるので、更新のときに n-gram ごとの一覧を読み直さなくてよい)
+fn posting_key(token: &str, coordinate_key: &str) -> String {
+    format!("{token}\0{coordinate_key}")
+}
+
+/// Starts the thread that keeps the search index up to date, so that indexing
+/// never runs on the caller's thread (often the UI thread holding the app state).
+/// Rebuilds the index first when it was written in an older format.
+pub fn start_indexer(cache_db: LmdbCache) {
+    let (sender, receiver) = mpsc::channel();
+    if INDEXER.set(sender).is_err() {
+        return;
+    }
+    std::thread::spawn(move || {
+        if let Err(e) = migrate_index(&cache_db) {
+            eprintln!("Failed to rebuild the search index: {e}");
+        }
+        for job in receiver {
+            let (key, result) = match job {
+                IndexJob::Add(article) => {
+                    let key = article.coordinate_key();
+                    let result = update_index(&cache_db, &key, Some(&article));
+                    (key, result)
+                }
+                IndexJob::Remove(key) => {
+                    let result = update_index(&cache_db, &key, None);
+                    (key, result)
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to update the search index for {}: {}", key, e);
+            }
+        }
+    });
+}
+
+fn send_job(job: IndexJob) {
+    if let Some(sender) = INDEXER.get() {
+        let _ = sender.send(job);
+    }
+}
+
+/// Queues the article for indexing, replacing the n-grams of any earlier version
+/// with the same address.
+pub fn index_article(article: &ArticleFull) {
+    send_job(IndexJob::Add(Box::new(article.clone())));
+}
+
+pub fn remove_article(coordinate_key: &str) {
+    send_job(IndexJob::Remove(coordinate_key.to_string()));
+}
+
+fn update_index(
+    cache_db: &LmdbCache,
+    key: &str,
+    article: Option<&ArticleFull>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let old_doc = cache_db.read_persistent_cache::<IndexedDoc>(DB_SEARCH_DOCS, key).ok().map(|cache| cache.data);
+    if let (Some(old_doc), Some(article)) = (&old_doc, article) {
+        if old_doc.event_id == article.id {
+            return Ok(());
+        }
+    }
+
+    let mut weights = HashMap::new();
+    if let Some(article) = article {
+        weights = token_counts(&article.content);
+        for (token, count) in token_counts(&article.title) {
+            *weights.entry(token).or_insert(0) += count * TITLE_WEIGHT;
+        }
+    }
+
+    let old_tokens = old_doc.map(|doc| doc.tokens).unwrap_or_default();
+    let mut updates: Vec<(String, Option<u32>)> = old_tokens
+        .iter()
+        .filter(|token| !weights.contains_key(*token))
+        .map(|token| (posting_key(token, key), None))
+        .collect();
+    updates.extend(weights.iter().map(|(token, weight)| (posting_key(token, key), Some(*weight))));
+    cache_db.write_cache_batch(DB_SEARCH_INDEX, &updates)?;
+
+    match article {
+        Some(article) => {
+            let doc = IndexedDoc {
+                event_id: article.id,
+                length: weights.values().sum(),
+                tokens: weights.into_keys().collect(),
+            };
+            cache_db.write_cache(DB_SEARCH_DOCS, key, &doc)?;
+        }
+        None => cache_db.delete_cache(DB_SEARCH_DOCS, key)?,
+    }
+    Ok(())
+}
+
+// 索引がないか古い形式のときは、キャッシュされた記事から作り直す (アップデート後の起動で一度だけ行う)
+fn migrate_index(cache_db: &LmdbCache) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let version = cache_db.read_persistent_cache::<u32>(DB_SEARCH_INDEX, VERSION_KEY).map(|cache| cache.data);
+    if version.is_ok_and(|version| version == INDEX_VERSION) {
+        return Ok(());
+    }
+    cache_db.clear_cache(DB_SEARCH_INDEX)?;
+    cache_db.clear_cache(DB_SEARCH_DOCS)?;
+    for (key, cache) in cache_db.read_all_cache::<ArticleFull>(DB_ARTICLES)? {
+        if let Err(e) = update_index(cache_db, &key, Some(&cache.data)) {
+            eprintln!("Failed to index article {}: {}", key, e);
+        }
+    }
+    cache_db.write_cache(DB_SEARCH_INDEX, VERSION_KEY, &INDEX_VERSION)
+}
+
+// 検索語の n-gram の出現箇所。1 文字の語は、その文字で始まる n-gram をすべて集める
+fn postings_for_token(cache_db: &LmdbCache, token: &str) -> Result<Postings, Box<dyn std::error::Error + Send + Sync>> {
+    let prefix = if token.chars().count() >= NGRAM { format!("{token}\0") } else { token.to_string() };
+    let mut merged = Postings::new();
+    for (key, cache) in cache_db.read_prefix_cache::<u32>(DB_SEARCH_INDEX, &prefix)? {
+        if let Some((_, doc)) = key.split_once('\0') {
+            *merged.entry(doc.to_string()).or_insert(0) += cache.data;
+        }
+    }
+    Ok(merged)
+}
+
+fn find_chars(haystack: &[char], needle: &[char], from: usize) -> Option<usize> {
+    if needle.is_empty() || haystack.len() < needle.len() {
+        return None;
+    }
+    (from..=haystack.len() - needle.len()).find(|&i| haystack[i..i + needle.len()] == *needle)
+}
+
+// 最初にヒットした語の前後を切り出し、スニペット中のすべてのヒット箇所を返す
+fn snippet(content: &str, terms: &[Vec<char>]) -> (String, Vec<Range<usize>>) {
+    let original: Vec<char> = content.chars().map(|c| if c.is_whitespace() { ' ' } else { c }).collect();
+    let normalized = normalize(content);
+    let first_hit = terms.iter().filter_map(|term| find_chars(&normalized, term, 0)).min();
+    let start = first_hit.map_or(0, |hit| hit.saturating_sub(SNIPPET_CONTEXT_CHARS));
+    let end = first_hit.map_or(SNIPPET_CONTEXT_CHARS * 2, |hit| hit + SNIPPET_CONTEXT_CHARS * 2).min(original.len());
+
+    let mut marked = vec![false; end - start];
+    for term in terms {
+        let mut from = start;
+        while let Some(hit) = find_chars(&normalized[..end], term, from) {
+            for flag in &mut marked[hit - start..hit - start + term.len()] {
+                *flag = true;
+            }
+            from = hit + 1;
+        }
+    }
+
+    let mut text = String::new();
+    let mut highlights: Vec<Range<usize>> = Vec::new();
+    if start > 0 {
+        text.push('…');
+    }
+    for (i, c) in original[start..end].iter().enumerate() {
+        let byte = text.len();
+        text.push(*c);
+        if marked[i] {
+            match highlights.last_mut() {
+                Some(last) if last.end == byte => last.end = text.len(),
+                _ => highlights.push(byte..text.len()),
+            }
+        }
+    }
+    if end < original.len() {
+        text.push('…');
+    }
+    (text, highlights)
+}
+
+/// Searches the cached articles. Every whitespace-separated term must appear in
+/// the title or body; results are ranked by n-gram TF-IDF, normalized by length.
+pub fn search(cache_db: &LmdbCache, query: &str) -> Result<Vec<SearchHit>, Box<dyn std::error::Error + Send + Sync>> {
+    let terms: Vec<Vec<char>> = query.split_whitespace().map(normalize).collect();
+    let tokens: Vec<String> = terms.iter().flat_map(|term| ngrams(term)).collect();
+    if tokens.is_empty() {
+        return Ok(Vec::new());
+    }
+    let doc_count = cache_db.entry_count(DB_SEARCH_DOCS)?.max(1) as f64;
+
+    // すべての n-gram を含む記事だけを候補にする
+    let mut scores: Option<HashMap<String, f64>> = None;
+    for token in &tokens {
+        let postings = postings_for_token(cache_db, token)?;
+        let idf = (1.0 + doc_count / postings.len().max(1) as f64).ln();
+        let mut next = HashMap::new();
+        for (doc, weight) in postings {
+            let previous = match &scores {
+                Some(scores) => match scores.get(&doc) {
+                    Some(score) => *score,
+                    None => continue,
+                },
+                None => 0.0,
+            };
+            next.insert(doc, previous + weight as f64 * idf);
+        }
+        if next.is_empty() {
+            return Ok(Vec::new());
+        }
+        scores = Some(next);
+    }
+
+    let mut hits = Vec::new();
+    for (key, score) in scores.unwrap_or_default() {
+        let Ok(article) = cache_db.read_persistent_cache::<ArticleFull>(DB_ARTICLES, &key).map(|cache| cache.data) else {
+            continue;
+        };
+        // n-gram がそろっていても語として続いていないことがあるので、本文で確かめる
+        let title = normalize(&article.title);
+        let content = normalize(&article.content);
+        let matches_all = terms
+            .iter()
+            .all(|term| find_chars(&title, term, 0).is_some() || find_chars(&content, term, 0).is_some());
+        if !matches_all {
+            continue;
+        }
+        let length = cache_db
+            .read_persistent_cache::<IndexedDoc>(DB_SEARCH_DOCS, &key)
+            .map_or(1, |cache| cache.data.length.max(1));
+        let (snippet, highlights) = snippet(&article.content, &terms);
+        hits.push(SearchHit {
+            post: timeline_post_from_article(&article),
+            snippet,
+            highlights,
+            score: score / (length as f64).sqrt(),
+        });
+    }
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(MAX_RESULTS);
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nostr::{Keys, Kind, Timestamp};
+
+    fn test_db(name: &str) -> (LmdbCache, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("search-index-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        (LmdbCache::new(&path).unwrap(), path)
+    }
+
+    fn test_article(author: &Keys, identifier: &str, title: &str, content: &str, id_byte: u8) -> ArticleFull {
+        ArticleFull {
+            id: EventId::from_byte_array([id_byte; 32]),
+            kind: Kind::from(30023),
+            author_pubkey: author.public_key(),
+            author_metadata: Default::default(),
+            title: title.to_string(),
+            content: content.to_string(),
+            created_at: Timestamp::from(1_700_000_000),
+            tags: Vec::new(),
+            identifier: identifier.to_string(),
+        }
+    }
+
+    fn normalized(text: &str) -> String {
+        normalize(text).into_iter().collect()
+    }
+
+    #[test]
+    fn splits_runs_into_bigrams() {
+        assert_eq!(ngrams(&normalize("日本語")), vec!["日本", "本語"]);
+        assert_eq!(ngrams(&normalize("Rust言語、a")), vec!["ru", "us", "st", "t言", "言語", "a"]);
+        assert!(ngrams(&normalize("  !? ")).is_empty());
+    }
+
+    #[test]
+    fn normalizes_width_kana_and_case() {
+        assert_eq!(normalized("ＲＵＳＴ　１２３"), "rust 123");
+        assert_eq!(normalized("Nostr"), "nostr");
+        assert_eq!(normalized("カタカナ"), "かたかな");
+        assert_eq!(normalized("ｶﾀｶﾅｰ"), normalized("カタカナー"));
+        // 1 文字は必ず 1 文字になる
+        let text = "ＡｂｃｶﾞガＺ";
+        assert_eq!(normalize(text).len(), text.chars().count());
+    }
+
+    #[test]
+    fn snippet_highlights_fall_on_char_boundaries() {
+        let content = format!("{}ここで検索語のＲｕｓｔが見つかる。{}", "前置きの文章。".repeat(10), "後ろの文章です。".repeat(10));
+        let terms = vec![normalize("rust"), normalize("検索語")];
+        let (text, highlights) = snippet(&content, &terms);
+        assert!(text.starts_with('…') && text.ends_with('…'));
+        assert_eq!(highlights.len(), 2);
+        for range in &highlights {
+            assert!(text.is_char_boundary(range.start) && text.is_char_boundary(range.end));
+        }
+        assert_eq!(&text[highlights[0].clone()], "検索語");
+        assert_eq!(&text[highlights[1].clone()], "Ｒｕｓｔ");
+    }
+
+    #[test]
+    fn reindexing_an_article_replaces_its_postings() {
+        let (cache_db, path) = test_db("reindex");
+        let author = Keys::generate();
+        let old = test_article(&author, "post", "題名", "古い本文", 1);
+        let new = test_article(&author, "post", "題名", "新しい本文", 2);
+        let key = old.coordinate_key();
+
+        update_index(&cache_db, &key, Some(&old)).unwrap();
+        assert!(postings_for_token(&cache_db, "古い").unwrap().contains_key(&key));
+        update_index(&cache_db, &key, Some(&new)).unwrap();
+        let old_postings = postings_for_token(&cache_db, "古い").unwrap();
+        let new_postings = postings_for_token(&cache_db, "新し").unwrap();
+        let shared_postings = postings_for_token(&cache_db, "本文").unwrap();
+        update_index(&cache_db, &key, None).unwrap();
+        let removed_postings = postings_for_token(&cache_db, "本文").unwrap();
+        let _ = std::fs::remove_dir_all(&path);
+
+        assert!(old_postings.is_empty());
+        assert_eq!(new_postings.get(&key), Some(&1));
+        assert_eq!(shared_postings.get(&key), Some(&1));
+        assert!(removed_postings.is_empty());
+    }
+
+    #[test]
+    fn ranks_by_tf_idf_and_requires_every_term() {
+        let (cache_db, path) = test_db("ranking");
+        let author = Keys::generate();
+        let articles = [
+            test_article(&author, "a", "Nostr 入門", "Nostr の記事を書く。Nostr はリレーを使う。", 1),
+            test_article(&author, "b", "日記", "今日は Nostr について少し調べた。", 2),
+            test_article(&author, "c", "料理", "カレーを作った。", 3),
+        ];
+        for article in &articles {
+            cache_db.write_cache(DB_ARTICLES, &article.coordinate_key(), article).unwrap();
+            update_index(&cache_db, &article.coordinate_key(), Some(article)).unwrap();
+        }
+        let ranked = search(&cache_db, "ｎｏｓｔｒ").unwrap();
+        let both_terms = search(&cache_db, "nostr 日記").unwrap();
+        let katakana = search(&cache_db, "かれー").unwrap();
+        let _ = std::fs::remove_dir_all(&path);
+
+        let identifiers: Vec<&str> = ranked.iter().map(|hit| hit.post.identifier.as_str()).collect();
+        assert_eq!(identifiers, vec!["a", "b"]);
+        assert!(ranked[0].score > ranked[1].score);
+        assert_eq!(both_terms.len(), 1);
+        assert_eq!(both_terms[0].post.identifier, "b");
+        assert_eq!(katakana.len(), 1);
+        assert_eq!(katakana[0].post.identifier, "c");
+    }
+
+    #[test]
+    fn rebuilds_an_index_from_an_older_version() {
+        let (cache_db, path) = test_db("migrate");
+        let article = test_article(&Keys::generate(), "post", "題名", "索引し直す本文", 1);
+        let key = article.coordinate_key();
+        cache_db.write_cache(DB_ARTICLES, &key, &article).unwrap();
+        cache_db.write_cache(DB_SEARCH_INDEX, &posting_key("古い", "stale"), &1u32).unwrap();
+        cache_db.write_cache(DB_SEARCH_INDEX, VERSION_KEY, &(INDEX_VERSION - 1)).unwrap();
+
+        migrate_index(&cache_db).unwrap();
+        let stale = postings_for_token(&cache_db, "古い").unwrap();
+        let rebuilt = postings_for_token(&cache_db, "索引").unwrap();
+        let version = cache_db.read_persistent_cache::<u32>(DB_SEARCH_INDEX, VERSION_KEY).unwrap().data;
+        let _ = std::fs::remove_dir_all(&path);
+
+        assert!(stale.is_empty());
+        assert!(rebuilt.contains_key(&key));
+        assert_eq!(version, INDEX_VERSION);
+    }
+}
@@ -1,12 +1,16 @@
 use eframe::egui;
-use egui_commonmark::CommonMarkCache;
-use nostr::{nips::nip47::NostrWalletConnectURI, PublicKey, Timestamp, Keys, EventId, Kind};
+use nostr::{nips::{nip01::Coordinate, nip19::ToBech32, nip47::NostrWalletConnectURI}, Event, PublicKey, Timestamp, Keys, EventId, Kind};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 use chrono::{DateTime, Utc};
-use nostr_sdk::Client;
+use nostr_sdk::{Client, SubscriptionId};
 
 use crate::cache_db::LmdbCache;
+use crate::nip23;
+use crate::nostr_client;
+use crate::search_index::SearchHit;
+use crate::ui::nostr_content::RenderedContent;
 
 // --- Pub-used structs and enums ---
 
@@ -22,6 +26,9 @@ pub struct Config {
 pub struct Cache<T> {
     pub timestamp: DateTime<Utc>,
     pub data: T,
+    // ブックマークした記事など、古くなっても読み込めるように残しておくエントリ
+    #[serde(default)]
+    pub pinned: bool,
 }
 
 impl<T> Cache<T> {
@@ -29,9 +36,11 @@ impl<T> Cache<T> {
         Self {
             timestamp: Utc::now(),
             data,
+            pinned: false,
         }
     }
 
+    // 固定されていても古さは判定する (再取得するかどうかの判断に使う)
     pub fn is_expired(&self) -> bool {
         let now = Utc::now();
         // 24 hours
@@ -56,6 +65,20 @@ pub struct ProfileMetadata {
     pub extra: HashMap<String, serde_json::Value>,
 }
 
+/// The name shown for a user: the profile's `display_name`, then its `name`, or
+/// the start of the npub when the profile has neither.
+pub fn display_name(pubkey: &PublicKey, metadata: &ProfileMetadata) -> String {
+    let display_name = metadata.extra.get("display_name").and_then(|v| v.as_str()).unwrap_or_default();
+    if !display_name.trim().is_empty() {
+        display_name.to_string()
+    } else if !metadata.name.is_empty() {
+        metadata.name.clone()
+    } else {
+        let npub = pubkey.to_bech32().unwrap_or_default();
+        format!("{}...", npub.chars().take(12).collect::<String>())
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct EditableRelay {
     pub url: String,
@@ -63,10 +86,25 @@ pub struct EditableRelay {
     pub write: bool,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ImageKind {
     Avatar,
     ProfilePicture,
+    ArticleHeader,
+    // 記事本文中の画像 (幅を制限して表示する)
+    ArticleImage,
+}
+
+impl ImageKind {
+    // デコード後に縮小する最大サイズ (幅, 高さ)
+    pub fn thumbnail_size(&self) -> (u32, u32) {
+        match self {
+            ImageKind::Avatar => (32, 32),
+            ImageKind::ProfilePicture => (100, 100),
+            ImageKind::ArticleHeader => (480, 270),
+            ImageKind::ArticleImage => (1400, 4096),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -99,6 +137,47 @@ pub struct TimelinePost {
     pub created_at: Timestamp,
     #[serde(default)]
     pub tags: Vec<nostr::Tag>,
+    #[serde(default)]
+    pub identifier: String,
+    #[serde(default)]
+    pub image: String,
+    // フォロー中のユーザーのリポスト (NIP-18) で流れてきた記事
+    #[serde(default)]
+    pub reposted_by: Option<RepostInfo>,
+}
+
+impl TimelinePost {
+    pub fn coordinate(&self) -> Coordinate {
+        Coordinate::new(self.kind, self.author_pubkey).identifier(self.identifier.clone())
+    }
+
+    pub fn coordinate_key(&self) -> String {
+        nip23::coordinate_key(self.kind, &self.author_pubkey, &self.identifier)
+    }
+
+    // タイムラインに並べる時刻 (リポストはリポストされた時刻)
+    pub fn timeline_at(&self) -> Timestamp {
+        self.reposted_by.as_ref().map_or(self.created_at, |repost| repost.reposted_at)
+    }
+
+    // t タグ (小文字にそろえる)
+    pub fn hashtags(&self) -> Vec<String> {
+        let mut hashtags: Vec<String> = Vec::new();
+        for hashtag in nip23::ArticleMetadata::from_tags(self.tags.iter()).hashtags {
+            let hashtag = hashtag.to_lowercase();
+            if !hashtags.contains(&hashtag) {
+                hashtags.push(hashtag);
+            }
+        }
+        hashtags
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepostInfo {
+    pub reposter_pubkey: PublicKey,
+    pub reposter_metadata: ProfileMetadata,
+    pub reposted_at: Timestamp,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -112,6 +191,246 @@ pub struct ArticleFull {
     pub created_at: Timestamp,
     #[serde(default)]
     pub tags: Vec<nostr::Tag>,
+    #[serde(default)]
+    pub identifier: String,
+}
+
+impl ArticleFull {
+    pub fn metadata(&self) -> nip23::ArticleMetadata {
+        nip23::ArticleMetadata::from_tags(self.tags.iter())
+    }
+
+    pub fn coordinate(&self) -> Coordinate {
+        Coordinate::new(self.kind, self.author_pubkey).identifier(self.identifier.clone())
+    }
+
+    pub fn coordinate_key(&self) -> String {
+        nip23::coordinate_key(self.kind, &self.author_pubkey, &self.identifier)
+    }
+}
+
+// 編集中の記事の下書き (LMDB と kind 30024 の暗号化コンテンツに保存する)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ArticleDraft {
+    pub identifier: String,
+    pub title: String,
+    pub content: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub image: String,
+    #[serde(default)]
+    pub hashtags: String,
+    #[serde(default)]
+    pub published_at: Option<Timestamp>,
+    #[serde(default)]
+    pub media: Vec<Vec<String>>,
+    // 公開済みの記事を編集中の下書きかどうか
+    #[serde(default)]
+    pub is_edit: bool,
+    pub updated_at: Timestamp,
+}
+
+impl ArticleDraft {
+    pub fn display_title(&self) -> &str {
+        if self.title.trim().is_empty() {
+            "(無題)"
+        } else {
+            &self.title
+        }
+    }
+}
+
+// アカウントごとの設定 (LMDB に保存して再起動後も引き継ぐ)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UserSettings {
+    #[serde(default)]
+    pub sync_drafts_to_relays: bool,
+}
+
+// 画像のアップロード先サーバーの種類
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum MediaServerKind {
+    Nip96,
+    Blossom,
+}
+
+impl MediaServerKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MediaServerKind::Nip96 => "NIP-96",
+            MediaServerKind::Blossom => "Blossom",
+        }
+    }
+}
+
+// 予約投稿キューの項目 (署名済みの kind 30023 イベントと公開予定時刻)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledArticle {
+    pub event: Event,
+    pub publish_at: Timestamp,
+    #[serde(default)]
+    pub attempts: u32,
+    // リレーへの送信に失敗した場合の次の再試行時刻
+    pub next_attempt_at: Timestamp,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+impl ScheduledArticle {
+    pub fn new(event: Event, publish_at: Timestamp) -> Self {
+        Self {
+            event,
+            publish_at,
+            attempts: 0,
+            next_attempt_at: publish_at,
+            last_error: None,
+        }
+    }
+
+    pub fn title(&self) -> String {
+        nip23::title_from_tags(self.event.tags.iter())
+    }
+
+    pub fn coordinate_key(&self) -> String {
+        nip23::coordinate_key(
+            self.event.kind,
+            &self.event.pubkey,
+            &nip23::identifier_from_tags(self.event.tags.iter()),
+        )
+    }
+
+    pub fn is_due(&self, now: Timestamp) -> bool {
+        self.next_attempt_at <= now
+    }
+}
+
+// NIP-84 ハイライト (kind 9802)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Highlight {
+    pub id: EventId,
+    pub author_pubkey: PublicKey,
+    pub author_metadata: ProfileMetadata,
+    pub content: String,
+    pub comment: String,
+    pub created_at: Timestamp,
+}
+
+// NIP-25 リアクションの集計 (記事の coordinate_key ごとに DB_REACTIONS に保存する)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReactionSummary {
+    // リアクションの内容 ("+" や絵文字) ごとの件数
+    pub counts: BTreeMap<String, u32>,
+    // 自分が送ったリアクションの内容
+    #[serde(default)]
+    pub mine: Vec<String>,
+}
+
+impl ReactionSummary {
+    pub fn add(&mut self, content: &str, is_mine: bool) {
+        *self.counts.entry(content.to_string()).or_insert(0) += 1;
+        if is_mine && !self.mine.iter().any(|c| c == content) {
+            self.mine.push(content.to_string());
+        }
+    }
+
+    // 件数の多い順
+    pub fn sorted_counts(&self) -> Vec<(&str, u32)> {
+        let mut counts: Vec<(&str, u32)> = self.counts.iter().map(|(c, n)| (c.as_str(), *n)).collect();
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        counts
+    }
+}
+
+// "+" は いいね として表示する
+pub fn reaction_label(content: &str) -> &str {
+    match content {
+        "+" => "❤",
+        "-" => "👎",
+        other => other,
+    }
+}
+
+// NIP-51 ブックマーク (kind 10003) に入れた記事。既読かどうかはローカルにだけ保存する
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub coordinate_key: String,
+    // 暗号化して content に入れるブックマーク
+    pub is_private: bool,
+    #[serde(default)]
+    pub is_read: bool,
+}
+
+// NIP-22 記事へのコメント (kind 1111)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArticleComment {
+    pub id: EventId,
+    pub author_pubkey: PublicKey,
+    pub author_metadata: ProfileMetadata,
+    pub content: String,
+    pub created_at: Timestamp,
+    // 返信先のコメント (記事への直接のコメントは None)
+    pub parent_id: Option<EventId>,
+}
+
+// 表示中の記事の読んでいる位置 (一定間隔で LMDB に保存する)
+#[derive(Debug, Clone)]
+pub struct ReadingState {
+    pub coordinate_key: String,
+    pub offset: f32,
+    pub saved_offset: f32,
+    pub saved_at: std::time::Instant,
+}
+
+// @メンションの候補
+#[derive(Debug, Clone)]
+pub struct MentionCandidate {
+    pub public_key: PublicKey,
+    pub name: String,
+    pub nip05: String,
+    pub is_followed: bool,
+}
+
+impl MentionCandidate {
+    pub fn label(&self) -> String {
+        if self.nip05.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} ({})", self.name, self.nip05)
+        }
+    }
+
+    /// NIP-27 reference inserted into the article body.
+    pub fn nostr_uri(&self) -> String {
+        format!("nostr:{}", self.public_key.to_bech32().unwrap_or_default())
+    }
+}
+
+// Markdown ファイルから取り込んだ記事
+#[derive(Debug, Clone)]
+pub struct ImportedArticle {
+    pub path: std::path::PathBuf,
+    pub metadata: nip23::ArticleMetadata,
+    pub content: String,
+    pub selected: bool,
+    // 同じ識別子の記事がすでに公開されている (公開すると上書きされる)
+    pub is_update: bool,
+    pub error: Option<String>,
+    pub published: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum ArticleEditorMode {
+    Edit,
+    Preview,
+    Split,
+}
+
+// 分割表示でスクロール位置を決める側のペイン
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum EditorPane {
+    Editor,
+    Preview,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
@@ -126,6 +445,17 @@ pub enum AppTab {
     Home,
     Profile,
     ArticleView,
+    PublishQueue,
+    UserProfile,
+    ReadingList,
+    Search,
+}
+
+// 検索の対象 (キャッシュした記事か、NIP-50 の検索リレーか)
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum SearchMode {
+    Local,
+    Relay,
 }
 
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
@@ -163,6 +493,20 @@ pub struct NostrStatusAppInternal {
     pub is_logged_in: bool,
     pub article_title_input: String,
     pub article_content_input: String,
+    pub article_summary_input: String,
+    pub article_image_input: String,
+    pub article_hashtags_input: String,
+    // 編集中の記事の `d` タグ (まだ公開も下書き保存もしていない場合は None)
+    pub editing_article_identifier: Option<String>,
+    // 公開済みの記事を編集しているかどうか
+    pub is_editing_published_article: bool,
+    // 編集中の記事の最初の公開日時 (編集しても変えない)
+    pub editing_article_published_at: Option<Timestamp>,
+    // アップロードした画像の `imeta` タグ
+    pub article_media_tags: Vec<Vec<String>>,
+    // 予約投稿
+    pub schedule_article: bool,
+    pub schedule_time_input: String,
     pub show_post_dialog: bool,
     pub secret_key_input: String,
     pub passphrase_input: String,
@@ -172,11 +516,37 @@ pub struct NostrStatusAppInternal {
     pub followed_pubkeys: HashSet<PublicKey>,
     pub followed_pubkeys_display: String,
     pub timeline_posts: Vec<TimelinePost>,
+    // 古いページを読み込み中か、これ以上古い記事がないか
+    pub is_loading_older_posts: bool,
+    pub timeline_exhausted: bool,
+    pub timeline_status: String,
+    // 新しい記事を前回の読み込みまでさかのぼりきれず、間に読み込めていない記事があるか
+    pub timeline_gap: bool,
+    // ライブ購読で届いた、まだタイムラインに入れていない記事
+    pub pending_posts: Vec<TimelinePost>,
+    pub timeline_subscription: Option<SubscriptionId>,
+    // 購読しているフォローの一覧 (フォローが変わったら購読し直す)
+    pub timeline_stream_authors: HashSet<PublicKey>,
     pub should_repaint: bool,
     pub is_loading: bool,
     pub current_tab: AppTab,
     pub current_profile_sub_view: ProfileSubView,
     pub selected_label: Option<String>,
+    // Labels
+    // ピン留めしたハッシュタグ (NIP-51 kind 10015)
+    pub interests: Vec<String>,
+    // 興味リストを読み込めたか (読み込めるまではピン留めを変えられない)
+    pub interests_loaded: bool,
+    pub is_loading_interests: bool,
+    pub interests_load_failed: bool,
+    // 読み込んだときにリレーに興味リストがなかったか (このときだけ新しいリストを作ってよい)
+    pub interests_list_missing: bool,
+    pub interest_input: String,
+    pub is_saving_interests: bool,
+    // 選んだラベルの記事 (読み込み済みのものとリレーの #t 検索の結果)
+    pub label_posts: Vec<TimelinePost>,
+    pub is_loading_label_posts: bool,
+    pub label_status: String,
     pub connected_relays_display: String,
     pub nip01_profile_display: String,
     pub editable_profile: ProfileMetadata,
@@ -185,7 +555,8 @@ pub struct NostrStatusAppInternal {
     pub discover_relays_editor: String,
     pub default_relays_editor: String,
     pub current_theme: AppTheme,
-    pub image_cache: HashMap<String, ImageState>,
+    // 同じ URL でも種類ごとに縮小サイズが違うので、種類も含めてキーにする
+    pub image_cache: HashMap<(String, ImageKind), ImageState>,
 
     // NWC
     pub nwc_passphrase_input: String,
@@ -200,9 +571,425 @@ pub struct NostrStatusAppInternal {
     pub zap_amount_input: String,
     pub zap_target_post: Option<TimelinePost>,
     // Article View
-    pub viewing_article_id: Option<EventId>,
+    pub viewing_article_coordinate: Option<Coordinate>,
     pub viewing_article: Option<ArticleFull>,
+    pub confirm_delete_article: bool,
+    // Revision history
+    pub show_article_history: bool,
+    pub article_history: Vec<ArticleFull>,
+    // 差分を表示する2つの版 (article_history のインデックス)
+    pub history_compare: [usize; 2],
+    pub is_fetching_history: bool,
+    // Outline
+    pub show_article_outline: bool,
+    // 本文の中で表示位置にある見出し (RenderedContent::headings のインデックス)
+    pub current_heading: Option<usize>,
+    pub scroll_to_heading: Option<usize>,
+    pub reading_state: Option<ReadingState>,
+    // Highlights
+    pub article_highlights: Vec<Highlight>,
+    // ハイライトを取得済みの記事 (coordinate_key)
+    pub highlights_article_key: Option<String>,
+    pub is_selecting_highlight: bool,
+    pub highlight_comment_input: String,
+    pub is_publishing_highlight: bool,
+    pub highlight_status: String,
+    // Reactions (キーは coordinate_key)
+    pub reaction_summaries: HashMap<String, ReactionSummary>,
+    pub requested_reactions: HashSet<String>,
+    // このセッションでリポストした記事 (coordinate_key)
+    pub reposted_articles: HashSet<String>,
+    // Bookmarks / reading list
+    pub bookmarks: Vec<Bookmark>,
+    pub is_syncing_bookmarks: bool,
+    pub bookmarks_synced: bool,
+    // 同期したときにリレーにブックマークリストがなかったか (このときだけ新しいリストを作ってよい)
+    pub bookmark_list_missing: bool,
+    pub bookmark_status: String,
+    pub reading_list_unread_only: bool,
+    // リーディングリストに表示する記事 (キーは coordinate_key)
+    pub reading_list_posts: HashMap<String, TimelinePost>,
+    pub requested_reading_list_posts: HashSet<String>,
+    // Search
+    pub search_query: String,
+    // 結果を表示している検索語
+    pub search_submitted_query: String,
+    pub search_results: Vec<SearchHit>,
+    pub is_searching: bool,
+    pub search_mode: SearchMode,
+    pub search_relays_editor: String,
+    pub search_relays_loaded: bool,
+    // 検索リレーのリストを公開したときのエラー (リレー設定画面に表示する)
+    pub search_relays_status: String,
+    pub relay_search_articles: Vec<TimelinePost>,
+    pub relay_search_profiles: Vec<(PublicKey, ProfileMetadata)>,
+    // 検索画面に表示するエラー
+    pub search_status: String,
+    // Comments
+    pub article_comments: Vec<ArticleComment>,
+    // コメントを取得済みの記事 (coordinate_key)
+    pub comments_article_key: Option<String>,
+    pub is_loading_comments: bool,
+    pub comment_input: String,
+    pub reply_target: Option<EventId>,
+    pub reply_input: String,
+    pub is_posting_comment: bool,
+    pub comment_status: String,
+    // nostr: URI の参照先 (キーは nip23::reference_key)
+    pub nostr_ref_profiles: HashMap<PublicKey, ProfileMetadata>,
+    pub nostr_ref_events: HashMap<String, TimelinePost>,
+    pub requested_nostr_refs: HashSet<String>,
+    // 取得できなかった引用先
+    pub missing_nostr_refs: HashSet<String>,
+    // 解析済みの本文 (キーは記事の ID かプレビューの名前)
+    pub rendered_contents: HashMap<String, Arc<RenderedContent>>,
+    // User Profile
+    pub viewing_profile: Option<PublicKey>,
+    // None はまだ取得していない
+    pub viewing_profile_articles: Option<Vec<TimelinePost>>,
+    pub is_loading_user_profile: bool,
+    // Markdown preview
+    pub article_editor_mode: ArticleEditorMode,
+    pub editor_scroll_leader: EditorPane,
+    pub editor_scroll_fraction: f32,
+    pub editor_scroll_max: [f32; 2],
+    // Mentions
+    // 本文入力欄のカーソル位置 (文字単位)
+    pub editor_cursor: Option<usize>,
+    // None はまだ読み込んでいない
+    pub mention_candidates: Option<Vec<MentionCandidate>>,
+    pub mention_selected: usize,
+    // Escape で閉じた @ の位置 (同じ @ では候補を出さない)
+    pub mention_dismissed_at: Option<usize>,
+    pub show_article_link_picker: bool,
+    pub article_link_query: String,
+    pub article_link_candidates: Vec<TimelinePost>,
+    // Drafts
+    pub drafts: Vec<ArticleDraft>,
+    pub sync_drafts_to_relays: bool,
+    pub draft_status: String,
+    pub draft_last_edit: Option<std::time::Instant>,
+    pub draft_last_relay_sync: Option<std::time::Instant>,
+    // エディタを開いたときにあった同じ識別子の下書き (破棄したときに元に戻す)
+    pub editor_draft_at_open: Option<ArticleDraft>,
+    // Markdown import
+    pub show_import_dialog: bool,
+    pub import_items: Vec<ImportedArticle>,
+    pub import_preview_index: Option<usize>,
+    pub import_status: String,
+    // Export
+    pub show_export_dialog: bool,
+    pub export_static_site: bool,
+    pub is_exporting: bool,
+    pub export_status: String,
+    // Publish queue
+    pub publish_queue: Vec<ScheduledArticle>,
+    pub reschedule_inputs: HashMap<String, String>,
+    pub publish_queue_status: String,
+    // Media upload
+    pub media_server_kind: MediaServerKind,
+    pub media_server_url: String,
+    pub is_uploading_media: bool,
+    pub media_upload_status: String,
+    // アップロードが終わり、カーソル位置への挿入を待っている画像 (Markdown リンクと imeta タグ)
+    pub uploaded_media: Vec<(String, Vec<String>)>,
+    // エディタを開き直すたびに変わる番号 (前の記事のアップロード結果を混ぜない)
+    pub editor_session: u64,
     // UI State
     pub show_profile_menu: bool,
-    pub commonmark_cache: CommonMarkCache,
+}
+
+impl NostrStatusAppInternal {
+    /// Resets every field of the compose dialog.
+    pub fn clear_article_editor(&mut self) {
+        self.article_title_input.clear();
+        self.article_content_input.clear();
+        self.article_summary_input.clear();
+        self.article_image_input.clear();
+        self.article_hashtags_input.clear();
+        self.editing_article_identifier = None;
+        self.is_editing_published_article = false;
+        self.editing_article_published_at = None;
+        self.article_media_tags.clear();
+        self.media_upload_status.clear();
+        self.is_uploading_media = false;
+        self.uploaded_media.clear();
+        self.editor_session = self.editor_session.wrapping_add(1);
+        self.schedule_article = false;
+        self.schedule_time_input.clear();
+        self.editor_cursor = None;
+        self.mention_candidates = None;
+        self.mention_dismissed_at = None;
+        self.show_article_link_picker = false;
+        self.draft_last_edit = None;
+        self.draft_last_relay_sync = None;
+        self.editor_draft_at_open = None;
+    }
+
+    /// Loads the locally saved state of the account that just logged in.
+    pub fn load_account_state(&mut self, keys: &Keys) {
+        let public_key = keys.public_key();
+        self.my_keys = Some(keys.clone());
+        self.drafts = nostr_client::load_local_drafts(&self.cache_db, &public_key);
+        self.sync_drafts_to_relays = nostr_client::load_user_settings(&self.cache_db, &public_key).sync_drafts_to_relays;
+        self.publish_queue = nostr_client::load_publish_queue(&self.cache_db, &public_key);
+        self.bookmarks = nostr_client::load_bookmarks(&self.cache_db, &public_key);
+        self.reposted_articles = nostr_client::load_reposted_articles(&self.cache_db, &public_key);
+    }
+
+    /// Clears everything that belongs to the logged-in account. The clients are
+    /// returned so the caller can shut them down.
+    pub fn reset_account_state(&mut self) -> Vec<Client> {
+        let clients = [self.nostr_client.take(), self.nwc_client.take()].into_iter().flatten().collect();
+
+        self.is_logged_in = false;
+        self.is_loading = false;
+        self.my_keys = None;
+        self.secret_key_input.clear();
+        self.passphrase_input.clear();
+        self.confirm_passphrase_input.clear();
+        self.current_tab = AppTab::Home;
+        self.current_profile_sub_view = ProfileSubView::Profile;
+        self.show_profile_menu = false;
+
+        // Profile / relays
+        self.followed_pubkeys.clear();
+        self.followed_pubkeys_display.clear();
+        self.connected_relays_display.clear();
+        self.nip01_profile_display.clear();
+        self.editable_profile = ProfileMetadata::default();
+        self.profile_fetch_status = "ログインしてください".to_string();
+        self.nip65_relays.clear();
+
+        // Timeline
+        self.timeline_posts.clear();
+        self.is_loading_older_posts = false;
+        self.timeline_exhausted = false;
+        self.timeline_gap = false;
+        self.timeline_status.clear();
+        self.pending_posts.clear();
+        self.timeline_subscription = None;
+        self.timeline_stream_authors.clear();
+
+        // Labels
+        self.selected_label = None;
+        self.interests.clear();
+        self.interests_loaded = false;
+        self.is_loading_interests = false;
+        self.interests_load_failed = false;
+        self.interests_list_missing = false;
+        self.interest_input.clear();
+        self.is_saving_interests = false;
+        self.label_posts.clear();
+        self.is_loading_label_posts = false;
+        self.label_status.clear();
+
+        // NWC / ZAP
+        self.nwc_passphrase_input.clear();
+        self.nwc = None;
+        self.nwc_error = None;
+        self.zap_history.clear();
+        self.zap_history_fetch_status.clear();
+        self.is_fetching_zap_history = false;
+        self.show_zap_dialog = false;
+        self.zap_amount_input.clear();
+        self.zap_target_post = None;
+
+        // Article view
+        self.viewing_article_coordinate = None;
+        self.viewing_article = None;
+        self.confirm_delete_article = false;
+        self.show_article_history = false;
+        self.article_history.clear();
+        self.is_fetching_history = false;
+        self.current_heading = None;
+        self.scroll_to_heading = None;
+        self.reading_state = None;
+        self.article_highlights.clear();
+        self.highlights_article_key = None;
+        self.is_selecting_highlight = false;
+        self.highlight_comment_input.clear();
+        self.is_publishing_highlight = false;
+        self.highlight_status.clear();
+        self.reaction_summaries.clear();
+        self.requested_reactions.clear();
+        self.reposted_articles.clear();
+        self.article_comments.clear();
+        self.comments_article_key = None;
+        self.is_loading_comments = false;
+        self.comment_input.clear();
+        self.reply_target = None;
+        self.reply_input.clear();
+        self.is_posting_comment = false;
+        self.comment_status.clear();
+        self.nostr_ref_profiles.clear();
+        self.nostr_ref_events.clear();
+        self.requested_nostr_refs.clear();
+        self.missing_nostr_refs.clear();
+        self.rendered_contents.clear();
+
+        // Bookmarks / reading list
+        self.bookmarks.clear();
+        self.is_syncing_bookmarks = false;
+        self.bookmarks_synced = false;
+        self.bookmark_list_missing = false;
+        self.bookmark_status.clear();
+        self.reading_list_posts.clear();
+        self.requested_reading_list_posts.clear();
+
+        // Search
+        self.search_query.clear();
+        self.search_submitted_query.clear();
+        self.search_results.clear();
+        self.is_searching = false;
+        self.search_relays_editor = nostr_client::DEFAULT_SEARCH_RELAYS.to_string();
+        self.search_relays_loaded = false;
+        self.search_relays_status.clear();
+        self.relay_search_articles.clear();
+        self.relay_search_profiles.clear();
+        self.search_status.clear();
+
+        // User profile
+        self.viewing_profile = None;
+        self.viewing_profile_articles = None;
+        self.is_loading_user_profile = false;
+
+        // Editor / drafts / import / export / publish queue
+        self.clear_article_editor();
+        self.show_post_dialog = false;
+        self.article_link_query.clear();
+        self.article_link_candidates.clear();
+        self.drafts.clear();
+        self.sync_drafts_to_relays = false;
+        self.draft_status.clear();
+        self.show_import_dialog = false;
+        self.import_items.clear();
+        self.import_preview_index = None;
+        self.import_status.clear();
+        self.show_export_dialog = false;
+        self.is_exporting = false;
+        self.export_status.clear();
+        self.publish_queue.clear();
+        self.reschedule_inputs.clear();
+        self.publish_queue_status.clear();
+
+        self.should_repaint = true;
+        clients
+    }
+
+    /// Switches to the article view and lets the update loop fetch the article.
+    pub fn open_article(&mut self, coordinate: Coordinate) {
+        self.viewing_article_coordinate = Some(coordinate);
+        self.viewing_article = None; // Clear previous article
+        self.show_article_history = false;
+        self.confirm_delete_article = false;
+        self.current_tab = AppTab::ArticleView;
+    }
+
+    pub fn image_state(&self, url: &str, kind: ImageKind) -> Option<&ImageState> {
+        self.image_cache.get(&(url.to_string(), kind))
+    }
+
+    pub fn bookmark(&self, coordinate_key: &str) -> Option<&Bookmark> {
+        self.bookmarks.iter().find(|b| b.coordinate_key == coordinate_key)
+    }
+
+    /// Counts the hashtags (`t` tags) of the loaded timeline, most used first.
+    pub fn label_counts(&self) -> Vec<(String, usize)> {
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for post in &self.timeline_posts {
+            for hashtag in post.hashtags() {
+                *counts.entry(hashtag).or_insert(0) += 1;
+            }
+        }
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Switches to another user's profile page.
+    pub fn open_user_profile(&mut self, public_key: PublicKey) {
+        if self.viewing_profile != Some(public_key) {
+            self.viewing_profile_articles = None;
+        }
+        self.viewing_profile = Some(public_key);
+        self.current_tab = AppTab::UserProfile;
+    }
+
+    /// Loads an existing article into the compose dialog so it can be republished
+    /// under the same address.
+    pub fn load_article_into_editor(&mut self, article: &ArticleFull) {
+        let metadata = article.metadata();
+        self.article_title_input = article.title.clone();
+        self.article_content_input = article.content.clone();
+        self.article_summary_input = metadata.summary;
+        self.article_image_input = metadata.image;
+        self.article_hashtags_input = metadata.hashtags.join(", ");
+        self.editing_article_identifier = Some(article.identifier.clone());
+        self.is_editing_published_article = true;
+        // published_at のない記事は、最初の版の作成日時がわからないので今の版の日時を公開日とみなす
+        self.editing_article_published_at = Some(metadata.published_at.unwrap_or(article.created_at));
+        self.article_media_tags = metadata.media;
+        self.editor_session = self.editor_session.wrapping_add(1);
+        self.editor_draft_at_open = self.drafts.iter().find(|d| d.identifier == article.identifier).cloned();
+    }
+
+    pub fn load_draft_into_editor(&mut self, draft: &ArticleDraft) {
+        self.article_title_input = draft.title.clone();
+        self.article_content_input = draft.content.clone();
+        self.article_summary_input = draft.summary.clone();
+        self.article_image_input = draft.image.clone();
+        self.article_hashtags_input = draft.hashtags.clone();
+        self.editing_article_identifier = Some(draft.identifier.clone());
+        self.is_editing_published_article = draft.is_edit;
+        self.editing_article_published_at = draft.published_at;
+        self.article_media_tags = draft.media.clone();
+        self.editor_session = self.editor_session.wrapping_add(1);
+        self.draft_last_edit = None;
+        self.draft_last_relay_sync = None;
+        self.editor_draft_at_open = Some(draft.clone());
+    }
+
+    /// Snapshots the compose dialog as a draft, reserving a `d` identifier if the
+    /// article does not have one yet.
+    pub fn draft_from_editor(&mut self) -> ArticleDraft {
+        let identifier = self
+            .editing_article_identifier
+            .get_or_insert_with(|| nip23::generate_identifier(&self.article_title_input))
+            .clone();
+        ArticleDraft {
+            identifier,
+            title: self.article_title_input.clone(),
+            content: self.article_content_input.clone(),
+            summary: self.article_summary_input.clone(),
+            image: self.article_image_input.clone(),
+            hashtags: self.article_hashtags_input.clone(),
+            published_at: self.editing_article_published_at,
+            media: self.article_media_tags.clone(),
+            is_edit: self.is_editing_published_article,
+            updated_at: Timestamp::now(),
+        }
+    }
+
+    /// Builds the NIP-23 metadata from the compose dialog.
+    /// `published_at` is set to now for the first publish and kept unchanged on edits.
+    pub fn article_metadata_from_editor(&self, identifier: String) -> nip23::ArticleMetadata {
+        nip23::ArticleMetadata {
+            identifier,
+            title: self.article_title_input.trim().to_string(),
+            summary: self.article_summary_input.trim().to_string(),
+            image: self.article_image_input.trim().to_string(),
+            published_at: Some(self.editing_article_published_at.unwrap_or_else(Timestamp::now)),
+            hashtags: nip23::parse_hashtags(&self.article_hashtags_input),
+            // 本文から削除された画像の imeta タグは付けない
+            media: self
+                .article_media_tags
+                .iter()
+                .filter(|imeta| {
+                    nip23::imeta_url(imeta).is_some_and(|url| self.article_content_input.contains(url))
+                })
+                .cloned()
+                .collect(),
+        }
+    }
 }
@@ -6,6 +6,21 @@ pub mod wallet_view;
 pub mod image_cache;
 pub mod zap;
 pub mod article_view;
+pub mod article_editor;
+pub mod publish_queue_view;
+pub mod import_view;
+pub mod export_view;
+pub mod mention_picker;
+pub mod nostr_content;
+pub mod highlight_view;
+pub mod comment_view;
+pub mod reaction_view;
+pub mod repost_view;
+pub mod reading_list_view;
+pub mod search_view;
+pub mod label_view;
+pub mod timeline_stream;
+pub mod user_profile_view;
 
 use eframe::egui::{self, Margin};
 // nostr v0.43.0 / nostr-sdk: RelayMetadata は nostr_sdk::nips::nip65 に移動したため import する
@@ -16,13 +31,32 @@ use crate::{
 };
 
 use crate::nostr_client;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// Runs `task` on the runtime, then hands its result to `apply` with the app state
+/// locked and asks for a repaint.
+pub fn spawn_update<T: Send + 'static>(
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    task: impl Future<Output = T> + Send + 'static,
+    apply: impl FnOnce(&mut NostrStatusAppInternal, T) + Send + 'static,
+) {
+    let app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = task.await;
+        let mut data = app_data_arc.lock().unwrap();
+        apply(&mut data, result);
+        data.should_repaint = true;
+    });
+}
 
 impl eframe::App for NostrStatusApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let mut app_data = self.data.lock().unwrap();
 
         // --- Article Fetching Logic ---
-        if let Some(event_id) = app_data.viewing_article_id {
+        if let Some(coordinate) = app_data.viewing_article_coordinate.clone() {
             if app_data.viewing_article.is_none() && !app_data.is_loading {
                 app_data.is_loading = true;
                 app_data.should_repaint = true;
@@ -35,7 +69,11 @@ impl eframe::App for NostrStatusApp {
                         (data.cache_db.clone(), data.nostr_client.as_ref().unwrap().clone())
                     };
 
-                    let result = nostr_client::fetch_article(&cache_db, &client, event_id).await;
+                    let result = nostr_client::fetch_article(&cache_db, &client, &coordinate).await;
+                    // 開いた記事だけを版の履歴に残す
+                    if let Ok(article) = &result {
+                        nostr_client::record_revisions(&cache_db, &article.coordinate_key(), vec![article.clone()]);
+                    }
 
                     let mut data = app_data_arc.lock().unwrap();
                     match result {
@@ -45,7 +83,7 @@ impl eframe::App for NostrStatusApp {
                         Err(e) => {
                             eprintln!("Failed to fetch article: {}", e);
                             // Optionally, reset the view or show an error
-                            data.viewing_article_id = None;
+                            data.viewing_article_coordinate = None;
                             data.current_tab = AppTab::Home;
                         }
                     }
@@ -98,24 +136,18 @@ impl eframe::App for NostrStatusApp {
                     ui.style_mut().spacing.item_spacing.y = 12.0;
 
                     ui.selectable_value(&mut app_data.current_tab, AppTab::Home, home_tab_text);
+                    if app_data.is_logged_in {
+                        let unread = app_data.bookmarks.iter().filter(|b| !b.is_read).count();
+                        let reading_list_text = if unread > 0 {
+                            format!("リーディングリスト ({unread})")
+                        } else {
+                            "リーディングリスト".to_string()
+                        };
+                        ui.selectable_value(&mut app_data.current_tab, AppTab::ReadingList, reading_list_text);
+                    }
 
                     ui.add_space(15.0);
-                    ui.label(egui::RichText::new("ラベル一覧").strong());
-                    ui.add_space(10.0);
-
-                    // Placeholder labels
-                    let labels = vec!["すべて", "テクノロジー", "音楽", "Rust", "Nostr"];
-                    for label in labels {
-                        let is_selected = app_data.selected_label == Some(label.to_string());
-                        if ui.selectable_label(is_selected, label).clicked() {
-                            if is_selected {
-                                // If clicked again, deselect
-                                app_data.selected_label = None;
-                            } else {
-                                app_data.selected_label = Some(label.to_string());
-                            }
-                        }
-                    }
+                    label_view::draw_label_sidebar(ui, &mut app_data, &app_data_arc_clone, &runtime_handle);
                 });
 
                 if app_data.is_logged_in {
@@ -128,8 +160,29 @@ impl eframe::App for NostrStatusApp {
                         .corner_radius(egui::CornerRadius::from(8.0));
 
                     if ui.add(button).clicked() {
+                        // 編集中でなければ新規投稿として開く
+                        if !app_data.show_post_dialog {
+                            app_data.clear_article_editor();
+                        }
                         app_data.show_post_dialog = true;
                     }
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Markdownを取り込む").clicked() {
+                            app_data.show_import_dialog = true;
+                        }
+                        if ui.button("書き出す").clicked() {
+                            app_data.show_export_dialog = true;
+                        }
+                    });
+
+                    if !app_data.publish_queue.is_empty() {
+                        ui.add_space(10.0);
+                        let queue_text = format!("予約投稿 ({})", app_data.publish_queue.len());
+                        ui.selectable_value(&mut app_data.current_tab, AppTab::PublishQueue, queue_text);
+                    }
+
+                    article_editor::draw_drafts_section(ui, &mut app_data, app_data_arc_clone.clone(), runtime_handle.clone());
                 }
             });
 
@@ -145,7 +198,7 @@ impl eframe::App for NostrStatusApp {
                                 let avatar_size = egui::vec2(40.0, 40.0); // Increased size
                                 let avatar_url = app_data.editable_profile.picture.clone();
                                 let image_state = if !avatar_url.is_empty() {
-                                    app_data.image_cache.get(&avatar_url).cloned()
+                                    app_data.image_state(&avatar_url, ImageKind::Avatar).cloned()
                                 } else {
                                     None
                                 };
@@ -201,6 +254,9 @@ impl eframe::App for NostrStatusApp {
                                         app_data.show_profile_menu = false;
                                     }
                                 }
+
+                                ui.add_space(10.0);
+                                search_view::draw_search_box(ui, &mut app_data, &app_data_arc_clone, &runtime_handle);
                             }
                         });
                     });
@@ -231,81 +287,75 @@ impl eframe::App for NostrStatusApp {
                         AppTab::ArticleView => {
                             article_view::draw_article_view(ui, ctx, &mut app_data, app_data_arc_clone.clone(), runtime_handle.clone(), &mut urls_to_load);
                         }
+                        AppTab::PublishQueue => {
+                            publish_queue_view::draw_publish_queue_view(ui, &mut app_data, app_data_arc_clone.clone(), runtime_handle.clone());
+                        }
+                        AppTab::UserProfile => {
+                            user_profile_view::draw_user_profile_view(ui, ctx, &mut app_data, app_data_arc_clone.clone(), runtime_handle.clone(), &mut urls_to_load);
+                        }
+                        AppTab::ReadingList => {
+                            reading_list_view::draw_reading_list_view(ui, &mut app_data, app_data_arc_clone.clone(), runtime_handle.clone());
+                        }
+                        AppTab::Search => {
+                            search_view::draw_search_view(ui, &mut app_data, app_data_arc_clone.clone(), runtime_handle.clone(), &mut urls_to_load);
+                        }
+                    }
+
+                    if app_data.show_post_dialog {
+                        article_editor::draw_article_editor(ctx, &mut app_data, app_data_arc_clone.clone(), runtime_handle.clone(), &mut urls_to_load);
+                    }
+                    if app_data.show_import_dialog {
+                        import_view::draw_import_dialog(ctx, &mut app_data, app_data_arc_clone.clone(), runtime_handle.clone(), &mut urls_to_load);
+                    }
+                    if app_data.show_export_dialog {
+                        export_view::draw_export_dialog(ctx, &mut app_data, app_data_arc_clone.clone(), runtime_handle.clone());
                     }
                 }
         });
 
         // --- Image Loading Logic ---
-        let cache_db = app_data.cache_db.clone();
-        let mut still_to_load = Vec::new();
+        // LMDB からの読み込みとデコードも UI スレッドを止めないようにランタイムで行う
         for (url_key, kind) in urls_to_load {
-            if let Some(image_bytes) = image_cache::load_from_lmdb(&cache_db, &url_key) {
-                if let Ok(mut dynamic_image) = image::load_from_memory(&image_bytes) {
-                    let (width, height) = match kind {
-                        ImageKind::Avatar => (32, 32),
-                        ImageKind::ProfilePicture => (100, 100),
-                    };
-                    dynamic_image = dynamic_image.thumbnail(width, height);
-                    let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                        [dynamic_image.width() as usize, dynamic_image.height() as usize],
-                        dynamic_image.to_rgba8().as_flat_samples().as_slice(),
-                    );
-                    let texture_handle = ctx.load_texture(
-                        &url_key,
-                        color_image,
-                        Default::default()
-                    );
-                    app_data.image_cache.insert(url_key, ImageState::Loaded(texture_handle));
-                } else {
-                    app_data.image_cache.insert(url_key, ImageState::Failed);
-                }
-            } else {
-                still_to_load.push((url_key, kind));
+            if app_data.image_cache.contains_key(&(url_key.clone(), kind)) {
+                continue;
             }
-        }
-
-        let data_clone = self.data.clone();
-        for (url_key, kind) in still_to_load {
-            app_data.image_cache.insert(url_key.clone(), ImageState::Loading);
+            app_data.image_cache.insert((url_key.clone(), kind), ImageState::Loading);
             app_data.should_repaint = true;
-            let app_data_clone = data_clone.clone();
+            let app_data_clone = self.data.clone();
             let ctx_clone = ctx.clone();
-            let cache_db_for_fetch = app_data.cache_db.clone();
-            let request = ehttp::Request::get(&url_key);
-            ehttp::fetch(request, move |result| {
-                let new_state = match result {
-                    Ok(response) => {
-                        if response.ok {
-                            image_cache::save_to_lmdb(&cache_db_for_fetch, &response.url, &response.bytes);
-                            match image::load_from_memory(&response.bytes) {
-                                Ok(mut dynamic_image) => {
-                                    let (width, height) = match kind {
-                                        ImageKind::Avatar => (32, 32),
-                                        ImageKind::ProfilePicture => (100, 100),
-                                    };
-                                    dynamic_image = dynamic_image.thumbnail(width, height);
-                                    let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                                        [dynamic_image.width() as usize, dynamic_image.height() as usize],
-                                        dynamic_image.to_rgba8().as_flat_samples().as_slice(),
-                                    );
-                                    let texture_handle = ctx_clone.load_texture(
-                                        &response.url,
-                                        color_image,
-                                        Default::default()
-                                    );
-                                    ImageState::Loaded(texture_handle)
+            let cache_db = app_data.cache_db.clone();
+            self.runtime.spawn_blocking(move || {
+                if let Some(image_bytes) = image_cache::load_from_lmdb(&cache_db, &url_key) {
+                    let new_state = match image_cache::decode_texture(&ctx_clone, &url_key, &image_bytes, kind) {
+                        Some(texture_handle) => ImageState::Loaded(texture_handle),
+                        None => ImageState::Failed,
+                    };
+                    app_data_clone.lock().unwrap().image_cache.insert((url_key, kind), new_state);
+                    ctx_clone.request_repaint();
+                    return;
+                }
+
+                let request = ehttp::Request::get(&url_key);
+                ehttp::fetch(request, move |result| {
+                    let new_state = match result {
+                        Ok(response) => {
+                            if response.ok {
+                                // リダイレクトされても元の URL で引けるように、要求した URL をキーにする
+                                image_cache::save_to_lmdb(&cache_db, &url_key, &response.bytes);
+                                match image_cache::decode_texture(&ctx_clone, &url_key, &response.bytes, kind) {
+                                    Some(texture_handle) => ImageState::Loaded(texture_handle),
+                                    None => ImageState::Failed,
                                 }
-                                Err(_) => ImageState::Failed,
+                            } else {
+                                ImageState::Failed
                             }
-                        } else {
-                            ImageState::Failed
                         }
-                    }
-                    Err(_) => ImageState::Failed,
-                };
-                let mut app_data = app_data_clone.lock().unwrap();
-                app_data.image_cache.insert(url_key, new_state);
-                ctx_clone.request_repaint();
+                        Err(_) => ImageState::Failed,
+                    };
+                    let mut app_data = app_data_clone.lock().unwrap();
+                    app_data.image_cache.insert((url_key, kind), new_state);
+                    ctx_clone.request_repaint();
+                });
             });
         }
 
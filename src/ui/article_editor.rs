@@ -0,0 +1,798 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{
+    media_upload, nip23,
+    nostr_client::{
+        article_from_event, delete_local_draft, delete_relay_draft, fetch_relay_drafts,
+        merge_drafts, publish_draft, save_local_draft, save_user_settings, store_article_with_history,
+        timeline_post_from_article,
+    },
+    cache_db::DB_ARTICLES,
+    types::*,
+    ui::{mention_picker, nostr_content, publish_queue_view},
+};
+
+const BODY_EDITOR_ID: &str = "article_body_editor";
+// 最後の入力からこの時間が経つと下書きをLMDBに自動保存する
+const DRAFT_AUTOSAVE_DELAY: Duration = Duration::from_secs(2);
+// 下書きをリレーに同期する最短間隔
+const DRAFT_RELAY_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn draw_article_editor(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let new_post_window_title_text = "新規投稿";
+    let edit_post_window_title_text = "記事を編集";
+    let publish_button_text = "公開";
+    let update_button_text = "更新";
+    let close_button_text = "下書きに保存して閉じる";
+    let discard_button_text = "変更を破棄して閉じる";
+    let sync_drafts_checkbox_text = "下書きをリレーに同期 (暗号化)";
+    let schedule_checkbox_text = "予約投稿";
+    let schedule_button_text = "予約";
+
+    if let Some(last_edit) = app_data.draft_last_edit {
+        if last_edit.elapsed() >= DRAFT_AUTOSAVE_DELAY {
+            save_editor_draft(app_data, &app_data_arc, &runtime_handle, false);
+        } else {
+            ctx.request_repaint_after(DRAFT_AUTOSAVE_DELAY);
+        }
+    }
+
+    // ダイアログにドロップされた画像をアップロードする
+    let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
+    for path in dropped_files.into_iter().filter_map(|file| file.path) {
+        start_media_upload(app_data, &app_data_arc, &runtime_handle, path);
+    }
+    let is_hovering_files = ctx.input(|i| !i.raw.hovered_files.is_empty());
+
+    let is_editing = app_data.is_editing_published_article;
+    let window_title = if is_editing { edit_post_window_title_text } else { new_post_window_title_text };
+
+    let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Background, "dim_layer".into()));
+    let screen_rect = ctx.screen_rect();
+    painter.add(egui::Shape::rect_filled(screen_rect, 0.0, egui::Color32::from_black_alpha(128)));
+
+    egui::Window::new(window_title)
+        .id(egui::Id::new("article_editor_window"))
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .collapsible(false)
+        .resizable(true)
+        .default_size([760.0, 560.0])
+        .show(ctx, |ui| {
+            egui::TopBottomPanel::bottom("post_dialog_buttons")
+                .show_inside(ui, |ui| {
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        let sync_checkbox = ui.checkbox(&mut app_data.sync_drafts_to_relays, sync_drafts_checkbox_text);
+                        if sync_checkbox.changed() {
+                            if let Some(keys) = &app_data.my_keys {
+                                let settings = UserSettings { sync_drafts_to_relays: app_data.sync_drafts_to_relays };
+                                save_user_settings(&app_data.cache_db, &keys.public_key(), &settings);
+                            }
+                            if app_data.sync_drafts_to_relays {
+                                sync_drafts_from_relays(app_data, &app_data_arc, &runtime_handle);
+                            }
+                        }
+                        ui.checkbox(&mut app_data.schedule_article, schedule_checkbox_text);
+                        if app_data.schedule_article {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut app_data.schedule_time_input)
+                                    .desired_width(130.0)
+                                    .hint_text("YYYY-MM-DD HH:MM"),
+                            );
+                        }
+                        if !app_data.draft_status.is_empty() {
+                            ui.label(egui::RichText::new(&app_data.draft_status).small().weak());
+                        }
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button(close_button_text).clicked() {
+                                save_editor_draft(app_data, &app_data_arc, &runtime_handle, true);
+                                app_data.show_post_dialog = false;
+                                app_data.clear_article_editor();
+                            }
+                            if ui.button(discard_button_text).clicked() {
+                                discard_editor_changes(app_data, &runtime_handle);
+                                app_data.show_post_dialog = false;
+                                app_data.clear_article_editor();
+                            }
+                            let submit_text = if app_data.schedule_article {
+                                schedule_button_text
+                            } else if is_editing {
+                                update_button_text
+                            } else {
+                                publish_button_text
+                            };
+                            if ui.button(submit_text).clicked() && !app_data.is_loading {
+                                let article_title = app_data.article_title_input.clone();
+                                let article_content = app_data.article_content_input.clone();
+
+                                if article_title.is_empty() {
+                                    eprintln!("Title cannot be empty.");
+                                    return;
+                                }
+                                if article_content.is_empty() {
+                                    eprintln!("Content cannot be empty.");
+                                    return;
+                                }
+
+                                // 編集時は既存の `d` タグを使い回し、同じアドレスの記事を置き換える
+                                let identifier = app_data
+                                    .editing_article_identifier
+                                    .clone()
+                                    .unwrap_or_else(|| nip23::generate_identifier(&article_title));
+                                let mut metadata = app_data.article_metadata_from_editor(identifier);
+
+                                // 予約投稿の場合は公開予定時刻を created_at にして署名しておく
+                                let scheduled_at = if app_data.schedule_article {
+                                    match publish_queue_view::parse_local_time(&app_data.schedule_time_input) {
+                                        Some(publish_at) => Some(publish_at),
+                                        None => {
+                                            app_data.draft_status = "予約日時の形式が正しくありません (例: 2025-01-31 09:00)".to_string();
+                                            return;
+                                        }
+                                    }
+                                } else {
+                                    None
+                                };
+                                if let Some(publish_at) = scheduled_at {
+                                    if app_data.editing_article_published_at.is_none() {
+                                        metadata.published_at = Some(publish_at);
+                                    }
+                                }
+
+                                let client_clone = app_data.nostr_client.as_ref().unwrap().clone();
+                                let keys_clone = app_data.my_keys.clone().unwrap();
+                                let my_metadata = app_data.editable_profile.clone();
+                                let cache_db_clone = app_data.cache_db.clone();
+                                let sync_drafts = app_data.sync_drafts_to_relays;
+
+                                app_data.is_loading = true;
+                                app_data.should_repaint = true;
+                                println!("Publishing NIP-23 article...");
+
+                                let cloned_app_data_arc = app_data_arc.clone();
+                                runtime_handle.spawn(async move {
+                                    // Create the NIP-23 event (kind 30023)
+                                    let mut builder = nip23::article_builder(&metadata, &article_content);
+                                    if let Some(publish_at) = scheduled_at {
+                                        builder = builder.custom_created_at(publish_at);
+                                    }
+                                    let event_result = builder.sign(&keys_clone).await;
+
+                                    match (event_result, scheduled_at) {
+                                        (Ok(event), Some(publish_at)) => {
+                                            let item = ScheduledArticle::new(event, publish_at);
+                                            delete_local_draft(&cache_db_clone, &keys_clone.public_key(), &metadata.identifier);
+                                            if sync_drafts {
+                                                if let Err(e) = delete_relay_draft(&client_clone, &keys_clone, &metadata.identifier).await {
+                                                    eprintln!("Failed to delete relay draft: {e}");
+                                                }
+                                            }
+
+                                            let mut data = cloned_app_data_arc.lock().unwrap();
+                                            publish_queue_view::enqueue_article(&mut data, item);
+                                            data.drafts.retain(|d| d.identifier != metadata.identifier);
+                                            data.show_post_dialog = false;
+                                            data.clear_article_editor();
+                                            data.draft_status.clear();
+                                            data.publish_queue_status = "記事を予約しました".to_string();
+                                        }
+                                        (Ok(event), None) => match client_clone.send_event(&event).await {
+                                            Ok(event_id) => {
+                                                println!("Article published with event id: {event_id:?}");
+                                                let article = article_from_event(&event, my_metadata);
+                                                store_article_with_history(&cache_db_clone, &article);
+
+                                                // 公開した記事の下書きは不要になる
+                                                delete_local_draft(&cache_db_clone, &keys_clone.public_key(), &article.identifier);
+                                                if sync_drafts {
+                                                    if let Err(e) = delete_relay_draft(&client_clone, &keys_clone, &article.identifier).await {
+                                                        eprintln!("Failed to delete relay draft: {e}");
+                                                    }
+                                                }
+
+                                                let mut data = cloned_app_data_arc.lock().unwrap();
+                                                data.drafts.retain(|d| d.identifier != article.identifier);
+                                                data.show_post_dialog = false;
+                                                data.clear_article_editor();
+                                                data.draft_status.clear();
+                                                let is_viewing_same_article = data
+                                                    .viewing_article
+                                                    .as_ref()
+                                                    .is_some_and(|a| a.coordinate_key() == article.coordinate_key());
+                                                if is_viewing_same_article {
+                                                    data.viewing_article = Some(article);
+                                                }
+                                            }
+                                            Err(e) => {
+                                                eprintln!("Failed to publish article: {e}");
+                                            }
+                                        },
+                                        (Err(e), _) => {
+                                            eprintln!("Failed to create event: {e}");
+                                        }
+                                    }
+                                    let mut data = cloned_app_data_arc.lock().unwrap();
+                                    data.is_loading = false;
+                                    data.should_repaint = true;
+                                });
+                            }
+                        });
+                    });
+                });
+
+            egui::CentralPanel::default().show_inside(ui, |ui| {
+                let mut edited = false;
+                ui.add_space(10.0);
+                ui.label("タイトル:");
+                edited |= ui.add(
+                    egui::TextEdit::singleline(&mut app_data.article_title_input)
+                        .desired_width(f32::INFINITY)
+                        .hint_text("記事のタイトル"),
+                ).changed();
+                if let Some(identifier) = &app_data.editing_article_identifier {
+                    ui.label(egui::RichText::new(format!("識別子 (d): {identifier}")).small().weak());
+                }
+                ui.add_space(5.0);
+                egui::Grid::new("article_metadata_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("概要:");
+                        edited |= ui.add(
+                            egui::TextEdit::multiline(&mut app_data.article_summary_input)
+                                .desired_rows(2)
+                                .desired_width(f32::INFINITY)
+                                .hint_text("タイムラインに表示される短い紹介文"),
+                        ).changed();
+                        ui.end_row();
+
+                        ui.label("ヘッダー画像URL:");
+                        edited |= ui.add(
+                            egui::TextEdit::singleline(&mut app_data.article_image_input)
+                                .desired_width(f32::INFINITY)
+                                .hint_text("https://..."),
+                        ).changed();
+                        ui.end_row();
+
+                        ui.label("ハッシュタグ:");
+                        edited |= ui.add(
+                            egui::TextEdit::singleline(&mut app_data.article_hashtags_input)
+                                .desired_width(f32::INFINITY)
+                                .hint_text("rust, nostr"),
+                        ).changed();
+                        ui.end_row();
+                    });
+                if let Some(published_at) = app_data.editing_article_published_at {
+                    let date = chrono::DateTime::<chrono::Utc>::from_timestamp(published_at.as_u64() as i64, 0)
+                        .map(|dt| dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_default();
+                    ui.label(egui::RichText::new(format!("初回公開日時: {date}")).small().weak());
+                }
+                ui.add_space(5.0);
+                draw_media_upload_row(ui, app_data, &app_data_arc, &runtime_handle, is_hovering_files);
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.label("本文:");
+                    if ui.button("記事リンク").on_hover_text("他の記事への nostr:naddr リンクを挿入").clicked() {
+                        open_article_link_picker(app_data);
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.selectable_value(&mut app_data.article_editor_mode, ArticleEditorMode::Preview, "プレビュー");
+                        ui.selectable_value(&mut app_data.article_editor_mode, ArticleEditorMode::Split, "分割");
+                        ui.selectable_value(&mut app_data.article_editor_mode, ArticleEditorMode::Edit, "編集");
+                    });
+                });
+                match app_data.article_editor_mode {
+                    ArticleEditorMode::Edit => {
+                        egui::ScrollArea::vertical().id_salt("article_editor_input").show(ui, |ui| {
+                            edited |= draw_body_editor(ui, app_data);
+                        });
+                    }
+                    ArticleEditorMode::Preview => {
+                        egui::ScrollArea::vertical().id_salt("article_editor_preview").show(ui, |ui| {
+                            draw_preview(ui, app_data, &app_data_arc, &runtime_handle, urls_to_load);
+                        });
+                    }
+                    ArticleEditorMode::Split => {
+                        edited |= draw_split_editor(ui, app_data, &app_data_arc, &runtime_handle, urls_to_load);
+                    }
+                }
+
+                // アップロードした画像はカーソルのある行に挿入する
+                for (markdown, imeta) in std::mem::take(&mut app_data.uploaded_media) {
+                    let len = app_data.article_content_input.chars().count();
+                    let cursor = app_data.editor_cursor.unwrap_or(len).min(len);
+                    let before = &app_data.article_content_input[..mention_picker::byte_index(&app_data.article_content_input, cursor)];
+                    let prefix = if before.is_empty() || before.ends_with('\n') { "" } else { "\n" };
+                    let new_cursor = mention_picker::replace_and_move_cursor(
+                        ctx,
+                        egui::Id::new(BODY_EDITOR_ID),
+                        &mut app_data.article_content_input,
+                        cursor,
+                        cursor,
+                        &format!("{prefix}{markdown}\n"),
+                    );
+                    app_data.editor_cursor = Some(new_cursor);
+                    app_data.article_media_tags.push(imeta);
+                    edited = true;
+                }
+
+                if app_data.show_article_link_picker {
+                    if let Some(uri) = mention_picker::draw_article_link_picker(ctx, app_data) {
+                        let len = app_data.article_content_input.chars().count();
+                        let cursor = app_data.editor_cursor.unwrap_or(len).min(len);
+                        let id = egui::Id::new(BODY_EDITOR_ID);
+                        let new_cursor = mention_picker::replace_and_move_cursor(
+                            ctx,
+                            id,
+                            &mut app_data.article_content_input,
+                            cursor,
+                            cursor,
+                            &uri,
+                        );
+                        app_data.editor_cursor = Some(new_cursor);
+                        edited = true;
+                    }
+                }
+
+                if edited {
+                    app_data.draft_last_edit = Some(Instant::now());
+                }
+            });
+        });
+}
+
+// 画像のアップロード先の設定とファイル選択ボタン
+fn draw_media_upload_row(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    is_hovering_files: bool,
+) {
+    ui.horizontal(|ui| {
+        ui.label("画像:");
+        egui::ComboBox::from_id_salt("media_server_kind")
+            .selected_text(app_data.media_server_kind.label())
+            .show_ui(ui, |ui| {
+                for kind in [MediaServerKind::Nip96, MediaServerKind::Blossom] {
+                    ui.selectable_value(&mut app_data.media_server_kind, kind, kind.label());
+                }
+            });
+        ui.add(
+            egui::TextEdit::singleline(&mut app_data.media_server_url)
+                .desired_width(200.0)
+                .hint_text("https://nostr.build"),
+        );
+        let upload_button = ui.add_enabled(!app_data.is_uploading_media, egui::Button::new("画像を選択..."));
+        if upload_button.clicked() {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("画像", &["png", "jpg", "jpeg", "gif", "webp"])
+                .pick_file()
+            {
+                start_media_upload(app_data, app_data_arc, runtime_handle, path);
+            }
+        }
+        if app_data.is_uploading_media {
+            ui.spinner();
+        }
+        if is_hovering_files {
+            ui.label(egui::RichText::new("ドロップしてアップロード").strong());
+        } else if !app_data.media_upload_status.is_empty() {
+            ui.label(egui::RichText::new(&app_data.media_upload_status).small().weak());
+        }
+    });
+}
+
+// 画像をアップロードし、完了したら本文に Markdown の画像リンクを追加する
+fn start_media_upload(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    path: std::path::PathBuf,
+) {
+    let Some(keys) = app_data.my_keys.clone() else {
+        return;
+    };
+    let server_kind = app_data.media_server_kind;
+    let server_url = app_data.media_server_url.clone();
+    let session = app_data.editor_session;
+    app_data.is_uploading_media = true;
+    app_data.media_upload_status = "アップロード中...".to_string();
+    app_data.should_repaint = true;
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = media_upload::upload_file(server_kind, &server_url, &keys, &path).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        // アップロード中にエディタを閉じたり別の記事を開いたりした場合は結果を捨てる
+        if data.editor_session != session {
+            return;
+        }
+        match result {
+            Ok(media) => {
+                let alt = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+                data.uploaded_media.push((media.markdown(alt), media.imeta_tag()));
+                data.media_upload_status = "アップロードしました".to_string();
+            }
+            Err(e) => {
+                eprintln!("Failed to upload media: {e}");
+                data.media_upload_status = format!("アップロードに失敗しました: {e}");
+            }
+        }
+        data.is_uploading_media = false;
+        data.should_repaint = true;
+    });
+}
+
+fn markdown_text_edit(content: &mut String) -> egui::TextEdit<'_> {
+    egui::TextEdit::multiline(content)
+        .id(egui::Id::new(BODY_EDITOR_ID))
+        .desired_rows(15)
+        .desired_width(f32::INFINITY)
+        .hint_text("記事の内容をMarkdownで記述...")
+}
+
+// 本文の入力欄。カーソルの直前が @名前 のときはメンションの候補を表示する
+fn draw_body_editor(ui: &mut egui::Ui, app_data: &mut NostrStatusAppInternal) -> bool {
+    let id = egui::Id::new(BODY_EDITOR_ID);
+    let mut edited = false;
+    let had_focus = ui.memory(|m| m.has_focus(id));
+
+    // 候補の選択キーは入力欄より先に処理する
+    if had_focus {
+        if let Some(cursor) = app_data.editor_cursor {
+            let mention = mention_picker::mention_query(&app_data.article_content_input, cursor)
+                .filter(|(at, _)| app_data.mention_dismissed_at != Some(*at));
+            if let Some((at, query)) = mention {
+                let candidates = mention_picker::matching_candidates(app_data, &query);
+                if mention_picker::handle_mention_keys(ui, app_data, at, candidates.len()) {
+                    if let Some(candidate) = candidates.get(app_data.mention_selected) {
+                        insert_mention(ui.ctx(), app_data, at, cursor, candidate);
+                        edited = true;
+                    }
+                }
+            }
+        }
+    }
+
+    let output = markdown_text_edit(&mut app_data.article_content_input).show(ui);
+    edited |= output.response.changed();
+    if let Some(range) = output.cursor_range {
+        app_data.editor_cursor = Some(range.primary.index);
+    }
+    if !had_focus && !output.response.has_focus() {
+        return edited;
+    }
+    let Some(cursor) = app_data.editor_cursor else {
+        return edited;
+    };
+
+    let mention = mention_picker::mention_query(&app_data.article_content_input, cursor);
+    let Some((at, query)) = mention else {
+        app_data.mention_dismissed_at = None;
+        app_data.mention_selected = 0;
+        return edited;
+    };
+    if app_data.mention_dismissed_at == Some(at) {
+        return edited;
+    }
+    if app_data.mention_candidates.is_none() {
+        mention_picker::load_mention_candidates(app_data);
+    }
+    let candidates = mention_picker::matching_candidates(app_data, &query);
+    if candidates.is_empty() {
+        return edited;
+    }
+    if app_data.mention_selected >= candidates.len() {
+        app_data.mention_selected = 0;
+    }
+
+    let cursor_rect = output.galley.pos_from_cursor(egui::text::CCursor::new(cursor));
+    let position = output.galley_pos + cursor_rect.left_bottom().to_vec2();
+    if let Some(candidate) = mention_picker::draw_mention_popup(ui.ctx(), app_data, &candidates, position) {
+        insert_mention(ui.ctx(), app_data, at, cursor, &candidate);
+        ui.memory_mut(|m| m.request_focus(id));
+        edited = true;
+    }
+    edited
+}
+
+// @名前 を nostr:npub… に置き換える (p タグは公開時に本文から付ける)
+fn insert_mention(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    at: usize,
+    cursor: usize,
+    candidate: &MentionCandidate,
+) {
+    let replacement = format!("{} ", candidate.nostr_uri());
+    let id = egui::Id::new(BODY_EDITOR_ID);
+    let new_cursor =
+        mention_picker::replace_and_move_cursor(ctx, id, &mut app_data.article_content_input, at, cursor, &replacement);
+    app_data.editor_cursor = Some(new_cursor);
+    app_data.mention_selected = 0;
+}
+
+// 記事リンクの候補 (キャッシュ済みの記事とタイムライン) を新しい順に集める
+fn open_article_link_picker(app_data: &mut NostrStatusAppInternal) {
+    let mut candidates: Vec<TimelinePost> = app_data
+        .cache_db
+        .read_all_cache::<ArticleFull>(DB_ARTICLES)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(_, cache)| timeline_post_from_article(&cache.data))
+        .collect();
+    for post in &app_data.timeline_posts {
+        if !candidates.iter().any(|c| c.coordinate_key() == post.coordinate_key()) {
+            candidates.push(post.clone());
+        }
+    }
+    candidates.sort_by_key(|p| std::cmp::Reverse(p.created_at));
+    app_data.article_link_candidates = candidates;
+    app_data.article_link_query.clear();
+    app_data.show_article_link_picker = true;
+}
+
+// 左に入力欄、右にプレビューを並べて表示する
+// マウスが乗っている側のスクロール位置 (割合) にもう一方を合わせる
+fn draw_split_editor(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) -> bool {
+    let mut edited = false;
+    ui.columns(2, |columns| {
+        if columns[0].rect_contains_pointer(columns[0].max_rect()) {
+            app_data.editor_scroll_leader = EditorPane::Editor;
+        } else if columns[1].rect_contains_pointer(columns[1].max_rect()) {
+            app_data.editor_scroll_leader = EditorPane::Preview;
+        }
+        let leader = app_data.editor_scroll_leader;
+        let fraction = app_data.editor_scroll_fraction;
+
+        let mut editor_area = egui::ScrollArea::vertical().id_salt("article_split_input");
+        if leader == EditorPane::Preview {
+            editor_area = editor_area.vertical_scroll_offset(fraction * app_data.editor_scroll_max[0]);
+        }
+        let editor_output = editor_area.show(&mut columns[0], |ui| {
+            edited |= draw_body_editor(ui, app_data);
+        });
+
+        let mut preview_area = egui::ScrollArea::vertical().id_salt("article_split_preview");
+        if leader == EditorPane::Editor {
+            preview_area = preview_area.vertical_scroll_offset(fraction * app_data.editor_scroll_max[1]);
+        }
+        let preview_output = preview_area.show(&mut columns[1], |ui| {
+            draw_preview(ui, app_data, app_data_arc, runtime_handle, urls_to_load);
+        });
+
+        let editor_max = (editor_output.content_size.y - editor_output.inner_rect.height()).max(0.0);
+        let preview_max = (preview_output.content_size.y - preview_output.inner_rect.height()).max(0.0);
+        app_data.editor_scroll_max = [editor_max, preview_max];
+
+        let (offset, max_offset) = match leader {
+            EditorPane::Editor => (editor_output.state.offset.y, editor_max),
+            EditorPane::Preview => (preview_output.state.offset.y, preview_max),
+        };
+        app_data.editor_scroll_fraction = if max_offset > 0.0 { (offset / max_offset).clamp(0.0, 1.0) } else { 0.0 };
+    });
+    edited
+}
+
+// 記事ページと同じレンダラーで本文をプレビューする
+fn draw_preview(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    // 後ろに表示している記事の目次の現在位置を上書きしない
+    let current_heading = app_data.current_heading;
+    let content = app_data.article_content_input.clone();
+    nostr_content::draw_nostr_content(
+        ui,
+        app_data,
+        app_data_arc,
+        runtime_handle,
+        "editor_preview",
+        &content,
+        &[],
+        urls_to_load,
+    );
+    app_data.current_heading = current_heading;
+}
+
+// 編集中の内容を下書きとしてLMDBに保存し、必要ならリレーにも同期する
+fn save_editor_draft(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    force_relay_sync: bool,
+) {
+    app_data.draft_last_edit = None;
+    if app_data.article_title_input.trim().is_empty() && app_data.article_content_input.trim().is_empty() {
+        return;
+    }
+    let Some(keys) = app_data.my_keys.clone() else {
+        return;
+    };
+
+    let draft = app_data.draft_from_editor();
+    save_local_draft(&app_data.cache_db, &keys.public_key(), &draft);
+    app_data.drafts.retain(|d| d.identifier != draft.identifier);
+    app_data.drafts.insert(0, draft.clone());
+    app_data.draft_status = format!("下書きを保存しました ({})", chrono::Local::now().format("%H:%M:%S"));
+
+    let should_sync = app_data.sync_drafts_to_relays
+        && (force_relay_sync
+            || app_data
+                .draft_last_relay_sync
+                .is_none_or(|last_sync| last_sync.elapsed() >= DRAFT_RELAY_SYNC_INTERVAL));
+    if !should_sync {
+        return;
+    }
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    app_data.draft_last_relay_sync = Some(Instant::now());
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = publish_draft(&client, &keys, &draft).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        match result {
+            Ok(_) => {
+                data.draft_status = format!("下書きをリレーに同期しました ({})", chrono::Local::now().format("%H:%M:%S"));
+            }
+            Err(e) => {
+                eprintln!("Failed to sync draft: {e}");
+                data.draft_status = format!("下書きの同期に失敗しました: {e}");
+            }
+        }
+        data.should_repaint = true;
+    });
+}
+
+// 自動保存された分も含めて、エディタを開いたときの下書きの状態に戻す
+fn discard_editor_changes(app_data: &mut NostrStatusAppInternal, runtime_handle: &tokio::runtime::Handle) {
+    app_data.draft_last_edit = None;
+    let (Some(keys), Some(identifier)) = (app_data.my_keys.clone(), app_data.editing_article_identifier.clone()) else {
+        return;
+    };
+    // 一度もリレーに同期していなければリレー上の下書きは開いたときのまま
+    let synced_to_relays = app_data.draft_last_relay_sync.is_some();
+    let client = app_data.nostr_client.clone().filter(|_| synced_to_relays);
+
+    match app_data.editor_draft_at_open.take() {
+        Some(original) => {
+            save_local_draft(&app_data.cache_db, &keys.public_key(), &original);
+            app_data.drafts.retain(|d| d.identifier != identifier);
+            app_data.drafts.push(original.clone());
+            app_data.drafts.sort_by_key(|d| std::cmp::Reverse(d.updated_at));
+            if let Some(client) = client {
+                runtime_handle.spawn(async move {
+                    if let Err(e) = publish_draft(&client, &keys, &original).await {
+                        eprintln!("Failed to restore relay draft: {e}");
+                    }
+                });
+            }
+        }
+        None => {
+            if !app_data.drafts.iter().any(|d| d.identifier == identifier) {
+                return;
+            }
+            app_data.drafts.retain(|d| d.identifier != identifier);
+            delete_local_draft(&app_data.cache_db, &keys.public_key(), &identifier);
+            if let Some(client) = client {
+                runtime_handle.spawn(async move {
+                    if let Err(e) = delete_relay_draft(&client, &keys, &identifier).await {
+                        eprintln!("Failed to delete relay draft: {e}");
+                    }
+                });
+            }
+        }
+    }
+    app_data.draft_status.clear();
+}
+
+// リレーに保存された下書きを取得してローカルの下書きとマージする
+pub fn sync_drafts_from_relays(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let cache_db = app_data.cache_db.clone();
+    app_data.draft_status = "リレーから下書きを取得中...".to_string();
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = fetch_relay_drafts(&client, &keys).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        match result {
+            Ok(remote_drafts) => {
+                let mut drafts = std::mem::take(&mut data.drafts);
+                merge_drafts(&cache_db, &keys.public_key(), &mut drafts, remote_drafts);
+                data.drafts = drafts;
+                data.draft_status = "リレーの下書きを取得しました".to_string();
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch relay drafts: {e}");
+                data.draft_status = format!("下書きの取得に失敗しました: {e}");
+            }
+        }
+        data.should_repaint = true;
+    });
+}
+
+// サイドバーの下書き一覧
+pub fn draw_drafts_section(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    if app_data.drafts.is_empty() {
+        return;
+    }
+
+    ui.add_space(15.0);
+    ui.label(egui::RichText::new("下書き").strong());
+    ui.add_space(5.0);
+
+    let mut draft_to_open = None;
+    let mut draft_to_delete = None;
+    for (i, draft) in app_data.drafts.iter().enumerate() {
+        ui.horizontal(|ui| {
+            let is_open = app_data.show_post_dialog
+                && app_data.editing_article_identifier.as_deref() == Some(draft.identifier.as_str());
+            if ui.selectable_label(is_open, draft.display_title()).clicked() {
+                draft_to_open = Some(draft.identifier.clone());
+            }
+            if ui.small_button("🗑").on_hover_text("下書きを削除").clicked() {
+                draft_to_delete = Some(i);
+            }
+        });
+    }
+
+    if let Some(identifier) = draft_to_open {
+        // 開いている記事の変更を失わないよう先に保存する
+        if app_data.show_post_dialog {
+            save_editor_draft(app_data, &app_data_arc, &runtime_handle, true);
+        }
+        if let Some(draft) = app_data.drafts.iter().find(|d| d.identifier == identifier).cloned() {
+            app_data.load_draft_into_editor(&draft);
+            app_data.show_post_dialog = true;
+        }
+    }
+
+    if let Some(i) = draft_to_delete {
+        let draft = app_data.drafts.remove(i);
+        if let Some(keys) = app_data.my_keys.clone() {
+            delete_local_draft(&app_data.cache_db, &keys.public_key(), &draft.identifier);
+            if app_data.sync_drafts_to_relays {
+                if let Some(client) = app_data.nostr_client.clone() {
+                    runtime_handle.spawn(async move {
+                        if let Err(e) = delete_relay_draft(&client, &keys, &draft.identifier).await {
+                            eprintln!("Failed to delete relay draft: {e}");
+                        }
+                    });
+                }
+            }
+        }
+    }
+}
@@ -1,36 +1,151 @@
 use eframe::egui;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use nostr::prelude::ToBech32;
-use egui_commonmark::CommonMarkViewer;
+use nostr::nips::nip19::Nip19Coordinate;
+use similar::{ChangeTag, TextDiff};
 
+use crate::nostr_client::{
+    article_from_event, delete_article, fetch_article_history, forget_article, load_article_history,
+    load_reading_position, merge_timeline_posts, restore_revision, save_reading_position,
+    store_article_with_history, timeline_post_from_article,
+};
 use crate::types::*;
+use crate::ui::{comment_view, highlight_view, nostr_content, reaction_view, reading_list_view, repost_view};
+
+// 読んでいる位置を LMDB に書き込む最短間隔
+const READING_POSITION_SAVE_INTERVAL: Duration = Duration::from_secs(2);
 
 pub fn draw_article_view(
     ui: &mut egui::Ui,
-    _ctx: &egui::Context,
+    ctx: &egui::Context,
     app_data: &mut NostrStatusAppInternal,
-    _app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
-    _runtime_handle: tokio::runtime::Handle,
-    _urls_to_load: &mut Vec<(String, ImageKind)>,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
 ) {
-    if let Some(post) = &app_data.viewing_article {
-        // Back button
-        if ui.button("← Back").clicked() {
-            app_data.viewing_article = None;
-            app_data.current_tab = AppTab::Home;
+    if let Some(post) = app_data.viewing_article.clone() {
+        let is_my_article = app_data
+            .my_keys
+            .as_ref()
+            .is_some_and(|keys| keys.public_key() == post.author_pubkey);
+
+        ui.horizontal(|ui| {
+            // Back button
+            if ui.button("← Back").clicked() {
+                save_current_reading_position(app_data);
+                app_data.confirm_delete_article = false;
+                app_data.show_article_history = false;
+                app_data.viewing_article = None;
+                app_data.viewing_article_coordinate = None;
+                app_data.current_tab = AppTab::Home;
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if ui.button("naddrをコピー").clicked() {
+                    match Nip19Coordinate::new(post.coordinate(), Vec::new()).to_bech32() {
+                        Ok(naddr) => ctx.copy_text(naddr),
+                        Err(e) => eprintln!("Failed to encode naddr: {e}"),
+                    }
+                }
+                reading_list_view::draw_bookmark_button(ui, app_data, &app_data_arc, &runtime_handle, &post.coordinate_key());
+                ui.toggle_value(&mut app_data.show_article_outline, "目次");
+                ui.toggle_value(&mut app_data.is_selecting_highlight, "ハイライト")
+                    .on_hover_text("本文を選択してハイライトを公開する");
+                if ui.button("履歴").clicked() {
+                    open_history(app_data, &app_data_arc, &runtime_handle, &post);
+                }
+                if is_my_article && ui.button("削除").clicked() {
+                    app_data.confirm_delete_article = true;
+                }
+                if is_my_article && ui.button("編集").clicked() {
+                    app_data.load_article_into_editor(&post);
+                    app_data.show_post_dialog = true;
+                }
+            });
+        });
+        if app_data.show_article_history {
+            draw_history_window(ctx, app_data, app_data_arc.clone(), runtime_handle.clone(), is_my_article);
+        }
+        if app_data.confirm_delete_article {
+            draw_delete_confirmation(ctx, app_data, app_data_arc.clone(), runtime_handle.clone(), &post);
+        }
+        if app_data.current_tab != AppTab::ArticleView {
             return;
         }
         ui.separator();
         ui.add_space(10.0);
 
-        egui::ScrollArea::vertical().show(ui, |ui| {
+        // 別の記事を開いたら、前回読んでいた位置から表示する
+        let coordinate_key = post.coordinate_key();
+        let mut scroll_area = egui::ScrollArea::vertical().id_salt("article_view_scroll");
+        if app_data.reading_state.as_ref().map(|state| &state.coordinate_key) != Some(&coordinate_key) {
+            save_current_reading_position(app_data);
+            let offset = load_reading_position(&app_data.cache_db, &coordinate_key).unwrap_or(0.0);
+            scroll_area = scroll_area.vertical_scroll_offset(offset);
+            app_data.reading_state = Some(ReadingState {
+                coordinate_key: coordinate_key.clone(),
+                offset,
+                saved_offset: offset,
+                saved_at: Instant::now(),
+            });
+            app_data.current_heading = None;
+            app_data.scroll_to_heading = None;
+            reading_list_view::mark_read(app_data, &coordinate_key, true);
+        }
+
+        highlight_view::ensure_highlights_loaded(app_data, &app_data_arc, &runtime_handle, &post);
+        comment_view::ensure_comments_loaded(app_data, &app_data_arc, &runtime_handle, &post);
+        reaction_view::request_reactions(app_data, &app_data_arc, &runtime_handle, vec![(post.id, post.coordinate())]);
+        if app_data.is_selecting_highlight {
+            egui::TopBottomPanel::bottom("highlight_form_panel").show_inside(ui, |ui| {
+                highlight_view::draw_highlight_form(ui, app_data, &app_data_arc, &runtime_handle, &post);
+            });
+        }
+
+        let article_key = post.id.to_hex();
+        let rendered = nostr_content::rendered_content(app_data, &article_key, &post.content);
+        let headings = &rendered.headings;
+        if !headings.is_empty() {
+            egui::SidePanel::right("article_outline_panel")
+                .resizable(false)
+                .default_width(200.0)
+                .show_animated_inside(ui, app_data.show_article_outline, |ui| {
+                    draw_outline(ui, app_data, headings);
+                });
+        }
+
+        let scroll_output = scroll_area.show(ui, |ui| {
             // Constrain the width for readability
             ui.set_max_width(700.0);
 
+            let metadata = post.metadata();
+
+            // Header image
+            if !metadata.image.is_empty() {
+                match app_data.image_state(&metadata.image, ImageKind::ArticleHeader).cloned() {
+                    Some(ImageState::Loaded(texture_handle)) => {
+                        ui.add(egui::Image::new(&texture_handle).corner_radius(8.0).max_width(700.0));
+                        ui.add_space(10.0);
+                    }
+                    Some(ImageState::Failed) => {}
+                    image_state => {
+                        if image_state.is_none() && !urls_to_load.iter().any(|(u, _)| u == &metadata.image) {
+                            urls_to_load.push((metadata.image.clone(), ImageKind::ArticleHeader));
+                        }
+                        ui.spinner();
+                    }
+                }
+            }
+
             // Big Title
             ui.heading(&post.title);
             ui.add_space(5.0);
 
+            if !metadata.summary.is_empty() {
+                ui.label(egui::RichText::new(&metadata.summary).italics().weak());
+                ui.add_space(5.0);
+            }
+
             // Author info
             ui.horizontal(|ui| {
                 // Simplified author display for now
@@ -42,15 +157,64 @@ pub fn draw_article_view(
                 };
                 ui.label("by");
                 ui.label(egui::RichText::new(display_name).strong());
+
+                let published_at = metadata.published_at.unwrap_or(post.created_at);
+                if let Some(date) = chrono::DateTime::<chrono::Utc>::from_timestamp(published_at.as_u64() as i64, 0) {
+                    ui.label(egui::RichText::new(date.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string()).weak());
+                }
+            });
+            if !metadata.hashtags.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    for hashtag in &metadata.hashtags {
+                        ui.label(egui::RichText::new(format!("#{hashtag}")).small().color(ui.visuals().hyperlink_color));
+                    }
+                });
+            }
+            ui.horizontal(|ui| {
+                let summary = app_data.reaction_summaries.get(&coordinate_key);
+                if let Some(content) = reaction_view::draw_reaction_bar(ui, summary, app_data.my_keys.is_some()) {
+                    reaction_view::send_reaction(app_data, &app_data_arc, &runtime_handle, post.id, post.coordinate(), content);
+                }
+                if !is_my_article && repost_view::draw_repost_button(ui, app_data, &coordinate_key) {
+                    repost_view::send_repost(app_data, &app_data_arc, &runtime_handle, post.id, post.coordinate());
+                }
             });
             ui.add_space(15.0);
             ui.separator();
             ui.add_space(15.0);
 
             // Full Content
-            CommonMarkViewer::new().show(ui, &mut app_data.commonmark_cache, &post.content);
+            if app_data.is_selecting_highlight {
+                highlight_view::draw_selectable_content(ui, app_data, &post);
+            } else {
+                let highlights = app_data.article_highlights.clone();
+                nostr_content::draw_nostr_content(
+                    ui,
+                    app_data,
+                    &app_data_arc,
+                    &runtime_handle,
+                    &article_key,
+                    &post.content,
+                    &highlights,
+                    urls_to_load,
+                );
+            }
+            highlight_view::draw_highlight_list(ui, app_data);
+            comment_view::draw_comments_section(ui, app_data, &app_data_arc, &runtime_handle, &post, urls_to_load);
         });
 
+        let offset = scroll_output.state.offset.y;
+        if let Some(state) = app_data.reading_state.as_mut() {
+            state.offset = offset;
+            if (state.offset - state.saved_offset).abs() > 1.0 && state.saved_at.elapsed() >= READING_POSITION_SAVE_INTERVAL {
+                save_reading_position(&app_data.cache_db, &state.coordinate_key, offset);
+                state.saved_offset = offset;
+                state.saved_at = Instant::now();
+            } else if (state.offset - state.saved_offset).abs() > 1.0 {
+                ctx.request_repaint_after(READING_POSITION_SAVE_INTERVAL);
+            }
+        }
+
     } else {
         // Article is being loaded, show a spinner
         ui.vertical_centered(|ui| {
@@ -62,9 +226,252 @@ pub fn draw_article_view(
 
         // Also provide a way to go back if it gets stuck
         if ui.button("← Back").clicked() {
-            app_data.viewing_article_id = None;
+            app_data.viewing_article_coordinate = None;
             app_data.viewing_article = None;
             app_data.current_tab = AppTab::Home;
         }
     }
 }
+
+// 目次。クリックした見出しまでスクロールし、表示中の見出しを強調する
+fn draw_outline(ui: &mut egui::Ui, app_data: &mut NostrStatusAppInternal, headings: &[nostr_content::Heading]) {
+    ui.label(egui::RichText::new("目次").strong());
+    ui.separator();
+    let min_level = headings.iter().map(|heading| heading.level).min().unwrap_or(1);
+    egui::ScrollArea::vertical().id_salt("article_outline_scroll").show(ui, |ui| {
+        for (i, heading) in headings.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.add_space((heading.level - min_level) as f32 * 12.0);
+                let title = if heading.title.is_empty() { "(無題)" } else { heading.title.as_str() };
+                if ui.selectable_label(app_data.current_heading == Some(i), title).clicked() {
+                    app_data.scroll_to_heading = Some(i);
+                }
+            });
+        }
+    });
+}
+
+// 記事を閉じるときに読んでいた位置をすぐに保存する
+fn save_current_reading_position(app_data: &mut NostrStatusAppInternal) {
+    if let Some(state) = app_data.reading_state.take() {
+        if (state.offset - state.saved_offset).abs() > 1.0 {
+            save_reading_position(&app_data.cache_db, &state.coordinate_key, state.offset);
+        }
+    }
+}
+
+// 削除の確認ダイアログ (削除リクエストは取り消せないため確認する)
+fn draw_delete_confirmation(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+    post: &ArticleFull,
+) {
+    egui::Window::new("記事を削除")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+        .show(ctx, |ui| {
+            ui.label(format!("「{}」を削除しますか？", post.title));
+            ui.label(egui::RichText::new("リレーに削除リクエストを送信します。すべてのリレーから消えるとは限りません。").small().weak());
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                if ui.add_enabled(!app_data.is_loading, egui::Button::new("削除する")).clicked() {
+                    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+                        return;
+                    };
+                    let cache_db = app_data.cache_db.clone();
+                    let article = post.clone();
+                    app_data.is_loading = true;
+                    app_data.should_repaint = true;
+
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let result = delete_article(&client, &keys, &article).await;
+                        let mut data = cloned_app_data_arc.lock().unwrap();
+                        match result {
+                            Ok(()) => {
+                                let key = article.coordinate_key();
+                                forget_article(&cache_db, &keys.public_key(), &key);
+                                data.timeline_posts.retain(|p| p.coordinate_key() != key);
+                                data.viewing_article = None;
+                                data.viewing_article_coordinate = None;
+                                data.current_tab = AppTab::Home;
+                            }
+                            Err(e) => {
+                                eprintln!("Failed to delete article: {e}");
+                            }
+                        }
+                        data.confirm_delete_article = false;
+                        data.is_loading = false;
+                        data.should_repaint = true;
+                    });
+                }
+                if ui.button("キャンセル").clicked() {
+                    app_data.confirm_delete_article = false;
+                }
+            });
+        });
+}
+
+fn format_revision_time(revision: &ArticleFull) -> String {
+    chrono::DateTime::<chrono::Utc>::from_timestamp(revision.created_at.as_u64() as i64, 0)
+        .map(|dt| dt.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+// ローカルの履歴をすぐに表示し、リレーにある版をバックグラウンドで取得する
+fn open_history(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    post: &ArticleFull,
+) {
+    app_data.article_history = load_article_history(&app_data.cache_db, &post.coordinate_key());
+    app_data.history_compare = [1.min(app_data.article_history.len().saturating_sub(1)), 0];
+    app_data.show_article_history = true;
+
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    let cache_db = app_data.cache_db.clone();
+    let coordinate = post.coordinate();
+    app_data.is_fetching_history = true;
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = fetch_article_history(&cache_db, &client, &coordinate).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        match result {
+            Ok(history) => {
+                if history.len() != data.article_history.len() {
+                    data.history_compare = [1.min(history.len().saturating_sub(1)), 0];
+                }
+                data.article_history = history;
+            }
+            Err(e) => eprintln!("Failed to fetch article history: {e}"),
+        }
+        data.is_fetching_history = false;
+        data.should_repaint = true;
+    });
+}
+
+// 版の一覧と、選んだ2つの版の行単位の差分
+fn draw_history_window(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+    is_my_article: bool,
+) {
+    let mut is_open = true;
+    let mut revision_to_restore = None;
+
+    egui::Window::new("変更履歴")
+        .open(&mut is_open)
+        .default_size([720.0, 520.0])
+        .show(ctx, |ui| {
+            if app_data.is_fetching_history {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("リレーから履歴を取得中...");
+                });
+            }
+            if app_data.article_history.is_empty() {
+                ui.label("履歴はまだありません。");
+                return;
+            }
+
+            ui.label(format!("{}件の版", app_data.article_history.len()));
+            egui::ScrollArea::vertical()
+                .id_salt("article_revisions")
+                .max_height(160.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("article_revisions_grid").striped(true).show(ui, |ui| {
+                        ui.label("比較元");
+                        ui.label("比較先");
+                        ui.label("日時");
+                        ui.label("タイトル");
+                        ui.end_row();
+                        for (i, revision) in app_data.article_history.iter().enumerate() {
+                            ui.radio_value(&mut app_data.history_compare[0], i, "");
+                            ui.radio_value(&mut app_data.history_compare[1], i, "");
+                            ui.label(format_revision_time(revision));
+                            ui.horizontal(|ui| {
+                                ui.label(&revision.title);
+                                if i == 0 {
+                                    ui.label(egui::RichText::new("(最新)").small().weak());
+                                } else if is_my_article && ui.small_button("この版を復元").clicked() {
+                                    revision_to_restore = Some(revision.clone());
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+                });
+
+            ui.separator();
+            let [old_index, new_index] = app_data.history_compare;
+            let (Some(old), Some(new)) = (
+                app_data.article_history.get(old_index),
+                app_data.article_history.get(new_index),
+            ) else {
+                return;
+            };
+            ui.label(format!("{} → {}", format_revision_time(old), format_revision_time(new)));
+            if old.title != new.title {
+                ui.label(format!("タイトル: {} → {}", old.title, new.title));
+            }
+
+            let removed_color = egui::Color32::from_rgb(200, 60, 60);
+            let added_color = egui::Color32::from_rgb(40, 150, 70);
+            egui::ScrollArea::vertical().id_salt("article_revision_diff").show(ui, |ui| {
+                let diff = TextDiff::from_lines(&old.content, &new.content);
+                for change in diff.iter_all_changes() {
+                    let line = change.value().trim_end_matches('\n');
+                    let text = match change.tag() {
+                        ChangeTag::Delete => egui::RichText::new(format!("- {line}")).color(removed_color),
+                        ChangeTag::Insert => egui::RichText::new(format!("+ {line}")).color(added_color),
+                        ChangeTag::Equal => egui::RichText::new(format!("  {line}")).weak(),
+                    };
+                    ui.label(text.monospace());
+                }
+            });
+        });
+
+    if !is_open {
+        app_data.show_article_history = false;
+    }
+
+    if let Some(revision) = revision_to_restore {
+        let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+            return;
+        };
+        let cache_db = app_data.cache_db.clone();
+        app_data.is_loading = true;
+        app_data.should_repaint = true;
+
+        let cloned_app_data_arc = app_data_arc.clone();
+        runtime_handle.spawn(async move {
+            let result = restore_revision(&client, &keys, &revision).await;
+            let mut data = cloned_app_data_arc.lock().unwrap();
+            match result {
+                Ok(event) => {
+                    let article = article_from_event(&event, revision.author_metadata.clone());
+                    store_article_with_history(&cache_db, &article);
+                    let key = article.coordinate_key();
+                    if data.timeline_posts.iter().any(|p| p.coordinate_key() == key) {
+                        merge_timeline_posts(&mut data.timeline_posts, vec![timeline_post_from_article(&article)]);
+                    }
+                    data.article_history = load_article_history(&cache_db, &article.coordinate_key());
+                    data.history_compare = [1.min(data.article_history.len().saturating_sub(1)), 0];
+                    data.viewing_article = Some(article);
+                }
+                Err(e) => eprintln!("Failed to restore revision: {e}"),
+            }
+            data.is_loading = false;
+            data.should_repaint = true;
+        });
+    }
+}
@@ -0,0 +1,312 @@
+use eframe::egui::{self, Sense};
+use nostr::EventId;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    nostr_client::{delete_comment, fetch_comments, publish_comment},
+    types::*,
+};
+
+const AVATAR_SIZE: f32 = 28.0;
+// これより深い返信はインデントしない
+const MAX_INDENT_DEPTH: usize = 5;
+const INDENT_WIDTH: f32 = 24.0;
+
+enum CommentAction {
+    StartReply(EventId),
+    CancelReply,
+    Submit(Option<ArticleComment>),
+    Delete(ArticleComment),
+}
+
+// 記事を開いたときにコメントを取得する
+pub fn ensure_comments_loaded(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    article: &ArticleFull,
+) {
+    let key = article.coordinate_key();
+    if app_data.comments_article_key.as_ref() == Some(&key) {
+        return;
+    }
+    app_data.comments_article_key = Some(key.clone());
+    app_data.article_comments.clear();
+    app_data.comment_input.clear();
+    app_data.reply_target = None;
+    app_data.reply_input.clear();
+    app_data.comment_status.clear();
+
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    let cache_db = app_data.cache_db.clone();
+    let article = article.clone();
+    app_data.is_loading_comments = true;
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = fetch_comments(&cache_db, &client, &article).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        if data.comments_article_key.as_ref() != Some(&key) {
+            return;
+        }
+        match result {
+            Ok(comments) => data.article_comments = comments,
+            Err(e) => eprintln!("Failed to fetch comments: {e}"),
+        }
+        data.is_loading_comments = false;
+        data.should_repaint = true;
+    });
+}
+
+fn draw_avatar(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    picture: &str,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let avatar_size = egui::vec2(AVATAR_SIZE, AVATAR_SIZE);
+    let (avatar_rect, _) = ui.allocate_exact_size(avatar_size, Sense::hover());
+    match app_data.image_state(picture, ImageKind::Avatar) {
+        Some(ImageState::Loaded(texture_handle)) => {
+            let image_widget = egui::Image::new(texture_handle)
+                .fit_to_exact_size(avatar_size)
+                .corner_radius(AVATAR_SIZE / 2.0);
+            ui.put(avatar_rect, image_widget);
+        }
+        image_state => {
+            if image_state.is_none() && !picture.is_empty() && !urls_to_load.iter().any(|(u, _)| u == picture) {
+                urls_to_load.push((picture.to_string(), ImageKind::Avatar));
+            }
+            ui.painter()
+                .circle_filled(avatar_rect.center(), AVATAR_SIZE / 2.0, ui.style().visuals.extreme_bg_color);
+        }
+    }
+}
+
+fn draw_reply_form(ui: &mut egui::Ui, app_data: &mut NostrStatusAppInternal, parent: &ArticleComment, actions: &mut Vec<CommentAction>) {
+    ui.add(
+        egui::TextEdit::multiline(&mut app_data.reply_input)
+            .desired_rows(2)
+            .desired_width(f32::INFINITY)
+            .hint_text(format!("{} さんに返信", display_name(&parent.author_pubkey, &parent.author_metadata))),
+    );
+    ui.horizontal(|ui| {
+        let can_submit = !app_data.reply_input.trim().is_empty() && !app_data.is_posting_comment;
+        if ui.add_enabled(can_submit, egui::Button::new("返信する")).clicked() {
+            actions.push(CommentAction::Submit(Some(parent.clone())));
+        }
+        if ui.button("キャンセル").clicked() {
+            actions.push(CommentAction::CancelReply);
+        }
+    });
+}
+
+// parent_id を親に持つコメントを、返信ごと再帰的に表示する
+#[allow(clippy::too_many_arguments)]
+fn draw_thread(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    comments: &[ArticleComment],
+    roots: &[&ArticleComment],
+    depth: usize,
+    my_pubkey: Option<nostr::PublicKey>,
+    actions: &mut Vec<CommentAction>,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    for comment in roots {
+        ui.horizontal_top(|ui| {
+            ui.add_space(INDENT_WIDTH * depth.min(MAX_INDENT_DEPTH) as f32);
+            draw_avatar(ui, app_data, &comment.author_metadata.picture, urls_to_load);
+            ui.vertical(|ui| {
+                ui.horizontal(|ui| {
+                    let name = display_name(&comment.author_pubkey, &comment.author_metadata);
+                    let name_response = ui.add(
+                        egui::Label::new(egui::RichText::new(name).small().strong())
+                            .sense(Sense::click()),
+                    );
+                    if name_response.on_hover_cursor(egui::CursorIcon::PointingHand).clicked() {
+                        app_data.open_user_profile(comment.author_pubkey);
+                    }
+                    if let Some(date) =
+                        chrono::DateTime::<chrono::Utc>::from_timestamp(comment.created_at.as_u64() as i64, 0)
+                    {
+                        let date = date.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string();
+                        ui.label(egui::RichText::new(date).small().weak());
+                    }
+                });
+                ui.label(&comment.content);
+                ui.horizontal(|ui| {
+                    if my_pubkey.is_some() && ui.small_button("返信").clicked() {
+                        actions.push(CommentAction::StartReply(comment.id));
+                    }
+                    if my_pubkey == Some(comment.author_pubkey) && ui.small_button("削除").clicked() {
+                        actions.push(CommentAction::Delete((*comment).clone()));
+                    }
+                });
+                if app_data.reply_target == Some(comment.id) {
+                    draw_reply_form(ui, app_data, comment, actions);
+                }
+            });
+        });
+        ui.add_space(8.0);
+
+        let replies: Vec<&ArticleComment> =
+            comments.iter().filter(|reply| reply.parent_id == Some(comment.id)).collect();
+        if !replies.is_empty() {
+            draw_thread(ui, app_data, comments, &replies, depth + 1, my_pubkey, actions, urls_to_load);
+        }
+    }
+}
+
+// 記事の下に表示するコメント欄
+pub fn draw_comments_section(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    article: &ArticleFull,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    ui.add_space(20.0);
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.heading(format!("コメント ({})", app_data.article_comments.len()));
+        if app_data.is_loading_comments {
+            ui.spinner();
+        }
+    });
+    ui.add_space(10.0);
+
+    let my_pubkey = app_data.my_keys.as_ref().map(|keys| keys.public_key());
+    let mut actions = Vec::new();
+    if my_pubkey.is_some() {
+        ui.add(
+            egui::TextEdit::multiline(&mut app_data.comment_input)
+                .desired_rows(3)
+                .desired_width(f32::INFINITY)
+                .hint_text("この記事にコメントする"),
+        );
+        ui.horizontal(|ui| {
+            let can_submit = !app_data.comment_input.trim().is_empty() && !app_data.is_posting_comment;
+            if ui.add_enabled(can_submit, egui::Button::new("コメントを投稿")).clicked() {
+                actions.push(CommentAction::Submit(None));
+            }
+            if app_data.is_posting_comment {
+                ui.spinner();
+            }
+        });
+        if !app_data.comment_status.is_empty() {
+            ui.label(egui::RichText::new(&app_data.comment_status).small().weak());
+        }
+        ui.add_space(10.0);
+    }
+
+    let comments = app_data.article_comments.clone();
+    if comments.is_empty() && !app_data.is_loading_comments {
+        ui.label(egui::RichText::new("まだコメントはありません。").weak());
+    }
+    // 返信先が見つからないコメントもトップレベルに表示する
+    let ids: HashSet<EventId> = comments.iter().map(|comment| comment.id).collect();
+    let roots: Vec<&ArticleComment> = comments
+        .iter()
+        .filter(|comment| comment.parent_id.is_none_or(|parent_id| !ids.contains(&parent_id)))
+        .collect();
+    draw_thread(ui, app_data, &comments, &roots, 0, my_pubkey, &mut actions, urls_to_load);
+
+    for action in actions {
+        match action {
+            CommentAction::StartReply(id) => {
+                if app_data.reply_target != Some(id) {
+                    app_data.reply_target = Some(id);
+                    app_data.reply_input.clear();
+                }
+            }
+            CommentAction::CancelReply => {
+                app_data.reply_target = None;
+                app_data.reply_input.clear();
+            }
+            CommentAction::Submit(parent) => start_publish(app_data, app_data_arc, runtime_handle, article, parent),
+            CommentAction::Delete(comment) => start_delete(app_data, app_data_arc, runtime_handle, comment),
+        }
+    }
+}
+
+fn start_publish(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    article: &ArticleFull,
+    parent: Option<ArticleComment>,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let content = if parent.is_some() {
+        app_data.reply_input.trim().to_string()
+    } else {
+        app_data.comment_input.trim().to_string()
+    };
+    let my_metadata = app_data.editable_profile.clone();
+    let article = article.clone();
+    app_data.is_posting_comment = true;
+    app_data.comment_status = "コメントを投稿中...".to_string();
+    app_data.should_repaint = true;
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = publish_comment(&client, &keys, &article, parent.as_ref(), &content, my_metadata).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        match result {
+            Ok(comment) => {
+                if data.comments_article_key.as_ref() == Some(&article.coordinate_key()) {
+                    data.article_comments.push(comment);
+                }
+                if parent.is_some() {
+                    data.reply_input.clear();
+                    data.reply_target = None;
+                } else {
+                    data.comment_input.clear();
+                }
+                data.comment_status = "コメントを投稿しました".to_string();
+            }
+            Err(e) => {
+                eprintln!("Failed to publish comment: {e}");
+                data.comment_status = format!("コメントの投稿に失敗しました: {e}");
+            }
+        }
+        data.is_posting_comment = false;
+        data.should_repaint = true;
+    });
+}
+
+fn start_delete(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    comment: ArticleComment,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    app_data.comment_status = "コメントを削除中...".to_string();
+    app_data.should_repaint = true;
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = delete_comment(&client, &keys, &comment).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        match result {
+            Ok(()) => {
+                data.article_comments.retain(|c| c.id != comment.id);
+                data.comment_status = "コメントを削除しました".to_string();
+            }
+            Err(e) => {
+                eprintln!("Failed to delete comment: {e}");
+                data.comment_status = format!("コメントの削除に失敗しました: {e}");
+            }
+        }
+        data.should_repaint = true;
+    });
+}
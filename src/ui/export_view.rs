@@ -0,0 +1,90 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+
+use crate::{markdown_io, nostr_client::fetch_my_articles, types::*};
+
+pub fn draw_export_dialog(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    let mut is_open = true;
+    egui::Window::new("記事を書き出す")
+        .id(egui::Id::new("article_export_window"))
+        .open(&mut is_open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            ui.label("公開済みの記事 (リレーとローカルのキャッシュ) を、フロントマター付きのMarkdownファイルとして書き出します。");
+            ui.add_space(5.0);
+            ui.checkbox(&mut app_data.export_static_site, "静的HTMLサイトも作成する (index.html と articles/)");
+            ui.add_space(10.0);
+            ui.horizontal(|ui| {
+                let export_button = egui::Button::new("書き出し先を選択して書き出す");
+                if ui.add_enabled(!app_data.is_exporting, export_button).clicked() {
+                    if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                        start_export(app_data, &app_data_arc, &runtime_handle, dir);
+                    }
+                }
+                if app_data.is_exporting {
+                    ui.spinner();
+                }
+            });
+            if !app_data.export_status.is_empty() {
+                ui.add_space(5.0);
+                ui.label(egui::RichText::new(&app_data.export_status).small().weak());
+            }
+        });
+
+    if !is_open {
+        app_data.show_export_dialog = false;
+        app_data.export_status.clear();
+    }
+}
+
+fn start_export(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    dir: std::path::PathBuf,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let cache_db = app_data.cache_db.clone();
+    let my_metadata = app_data.editable_profile.clone();
+    let with_site = app_data.export_static_site;
+    let site_title = if my_metadata.name.is_empty() {
+        "Articles".to_string()
+    } else {
+        format!("{} の記事", my_metadata.name)
+    };
+    app_data.is_exporting = true;
+    app_data.export_status = "記事を集めています...".to_string();
+    app_data.should_repaint = true;
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = async {
+            let articles = fetch_my_articles(&cache_db, &client, &keys, my_metadata).await?;
+            let written = markdown_io::export_markdown(&dir.join("markdown"), &articles)?;
+            if with_site {
+                markdown_io::export_static_site(&dir.join("site"), &site_title, &articles)?;
+            }
+            Ok::<usize, Box<dyn std::error::Error + Send + Sync>>(written)
+        }
+        .await;
+
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        data.export_status = match result {
+            Ok(written) => format!("{}件の記事を {} に書き出しました", written, dir.display()),
+            Err(e) => {
+                eprintln!("Failed to export articles: {e}");
+                format!("書き出しに失敗しました: {e}")
+            }
+        };
+        data.is_exporting = false;
+        data.should_repaint = true;
+    });
+}
@@ -0,0 +1,204 @@
+use eframe::egui;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    nostr_client::{fetch_highlights, publish_highlight},
+    types::*,
+    ui::{nostr_content, spawn_update},
+};
+
+const SELECTION_TEXT_ID: &str = "highlight_selection_text";
+
+// 記事を開いたときに、自分とフォロー中のユーザーのハイライトを取得する
+pub fn ensure_highlights_loaded(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    article: &ArticleFull,
+) {
+    let key = article.coordinate_key();
+    if app_data.highlights_article_key.as_ref() == Some(&key) {
+        return;
+    }
+    app_data.highlights_article_key = Some(key.clone());
+    app_data.article_highlights.clear();
+    app_data.is_selecting_highlight = false;
+    app_data.highlight_comment_input.clear();
+    app_data.highlight_status.clear();
+
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let mut authors: HashSet<_> = app_data.followed_pubkeys.clone();
+    authors.insert(keys.public_key());
+    let cache_db = app_data.cache_db.clone();
+    let article = article.clone();
+    spawn_update(
+        app_data_arc,
+        runtime_handle,
+        async move { fetch_highlights(&cache_db, &client, &article, authors).await },
+        move |data, result| {
+            if data.highlights_article_key.as_ref() != Some(&key) {
+                return;
+            }
+            match result {
+                Ok(highlights) => data.article_highlights = highlights,
+                Err(e) => eprintln!("Failed to fetch highlights: {e}"),
+            }
+        },
+    );
+}
+
+// 選択範囲は本文の表示どおりのプレーンテキスト上の位置なので、Markdown の記号を含まない
+fn selected_text(ctx: &egui::Context, content: &str) -> String {
+    let Some(range) = egui::TextEdit::load_state(ctx, egui::Id::new(SELECTION_TEXT_ID))
+        .and_then(|state| state.cursor.char_range())
+    else {
+        return String::new();
+    };
+    let range = range.as_sorted_char_range();
+    content.chars().skip(range.start).take(range.len()).collect::<String>().trim().to_string()
+}
+
+// 選択中の一節とコメントの入力欄、公開ボタン
+pub fn draw_highlight_form(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    article: &ArticleFull,
+) {
+    let rendered = nostr_content::rendered_content(app_data, &article.id.to_hex(), &article.content);
+    let text = selected_text(ui.ctx(), &rendered.plain_text);
+    ui.add_space(5.0);
+    if text.is_empty() {
+        ui.label(egui::RichText::new("ハイライトしたい部分を選択してください。").weak());
+    } else {
+        let preview: String = text.chars().take(120).collect();
+        let ellipsis = if text.chars().count() > 120 { "..." } else { "" };
+        ui.label(egui::RichText::new(format!("「{preview}{ellipsis}」")).italics());
+    }
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::TextEdit::singleline(&mut app_data.highlight_comment_input)
+                .desired_width(ui.available_width() - 180.0)
+                .hint_text("コメント (任意)"),
+        );
+        let can_publish = !text.is_empty() && !app_data.is_publishing_highlight;
+        if ui.add_enabled(can_publish, egui::Button::new("ハイライトを公開")).clicked() {
+            start_publish(app_data, app_data_arc, runtime_handle, article, text);
+        }
+        if app_data.is_publishing_highlight {
+            ui.spinner();
+        }
+    });
+    if !app_data.highlight_status.is_empty() {
+        ui.label(egui::RichText::new(&app_data.highlight_status).small().weak());
+    }
+    ui.add_space(5.0);
+}
+
+fn start_publish(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    article: &ArticleFull,
+    text: String,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let comment = app_data.highlight_comment_input.clone();
+    let my_metadata = app_data.editable_profile.clone();
+    let article = article.clone();
+    app_data.is_publishing_highlight = true;
+    app_data.highlight_status = "ハイライトを公開中...".to_string();
+    app_data.should_repaint = true;
+
+    let key = article.coordinate_key();
+    spawn_update(
+        app_data_arc,
+        runtime_handle,
+        async move { publish_highlight(&client, &keys, &article, &text, &comment, my_metadata).await },
+        move |data, result| {
+            match result {
+                Ok(highlight) => {
+                    if data.highlights_article_key.as_ref() == Some(&key) {
+                        data.article_highlights.push(highlight);
+                    }
+                    data.highlight_comment_input.clear();
+                    data.is_selecting_highlight = false;
+                    data.highlight_status = "ハイライトを公開しました".to_string();
+                }
+                Err(e) => {
+                    eprintln!("Failed to publish highlight: {e}");
+                    data.highlight_status = format!("ハイライトの公開に失敗しました: {e}");
+                }
+            }
+            data.is_publishing_highlight = false;
+        },
+    );
+}
+
+// 選択モードでは本文を表示どおりのプレーンテキストにして、選択できるようにする
+pub fn draw_selectable_content(ui: &mut egui::Ui, app_data: &mut NostrStatusAppInternal, article: &ArticleFull) {
+    let rendered = nostr_content::rendered_content(app_data, &article.id.to_hex(), &article.content);
+    let mut text = rendered.plain_text.as_str();
+    egui::TextEdit::multiline(&mut text)
+        .id(egui::Id::new(SELECTION_TEXT_ID))
+        .desired_width(f32::INFINITY)
+        .font(egui::TextStyle::Body)
+        .frame(false)
+        .show(ui);
+}
+
+fn author_name(highlight: &Highlight) -> String {
+    display_name(&highlight.author_pubkey, &highlight.author_metadata)
+}
+
+/// The text shown when hovering a marked passage.
+pub fn highlight_summary(highlight: &Highlight) -> String {
+    if highlight.comment.is_empty() {
+        author_name(highlight)
+    } else {
+        format!("{}: {}", author_name(highlight), highlight.comment)
+    }
+}
+
+// 記事の下に表示するハイライトの一覧
+pub fn draw_highlight_list(ui: &mut egui::Ui, app_data: &NostrStatusAppInternal) {
+    if app_data.article_highlights.is_empty() {
+        return;
+    }
+    ui.add_space(20.0);
+    ui.separator();
+    ui.heading(format!("ハイライト ({})", app_data.article_highlights.len()));
+    ui.add_space(10.0);
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(10),
+        corner_radius: 6.0.into(),
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+    for highlight in &app_data.article_highlights {
+        card_frame.show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            ui.label(egui::RichText::new(format!("「{}」", highlight.content)).italics());
+            if !highlight.comment.is_empty() {
+                ui.label(&highlight.comment);
+            }
+            ui.horizontal(|ui| {
+                ui.label(egui::RichText::new(author_name(highlight)).small().strong());
+                if let Some(date) =
+                    chrono::DateTime::<chrono::Utc>::from_timestamp(highlight.created_at.as_u64() as i64, 0)
+                {
+                    let date = date.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string();
+                    ui.label(egui::RichText::new(date).small().weak());
+                }
+            });
+        });
+        ui.add_space(6.0);
+    }
+}
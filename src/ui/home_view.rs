@@ -1,14 +1,91 @@
 use eframe::egui;
 use std::sync::{Arc, Mutex};
-use nostr::{EventBuilder, Kind, PublicKey, Tag, nips::nip19::ToBech32, EventId};
+use nostr::{PublicKey, nips::nip19::ToBech32};
 
 use crate::{
     types::*,
-    nostr_client::{update_contact_list, fetch_timeline_events},
+    nostr_client::{update_contact_list, fetch_timeline_events, merge_timeline_posts, save_timeline, TimelinePage},
     cache_db::DB_FOLLOWED,
-    ui::zap,
+    ui::{reaction_view, repost_view, timeline_stream, zap},
 };
 
+// タイムラインの 1 ページを取得してマージし、読み込んだ履歴を DB_TIMELINE に保存する
+fn fetch_timeline_page(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    page: TimelinePage,
+) {
+    let Some(my_keys) = app_data.my_keys.clone() else {
+        return;
+    };
+    let followed_pubkeys = app_data.followed_pubkeys.clone();
+    let discover_relays = app_data.discover_relays_editor.clone();
+    let cache_db_clone = app_data.cache_db.clone();
+    let is_older = matches!(page, TimelinePage::Older(_));
+    if is_older {
+        app_data.is_loading_older_posts = true;
+        app_data.timeline_status.clear();
+    } else {
+        app_data.is_loading = true;
+    }
+    app_data.should_repaint = true;
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let timeline_result =
+            fetch_timeline_events(&my_keys, &discover_relays, &followed_pubkeys, &cache_db_clone, page).await;
+
+        let mut app_data_async = cloned_app_data_arc.lock().unwrap();
+        if is_older {
+            app_data_async.is_loading_older_posts = false;
+        } else {
+            app_data_async.is_loading = false;
+        }
+        match timeline_result {
+            Ok(timeline) => {
+                if !timeline.deleted.is_empty() {
+                    app_data_async
+                        .timeline_posts
+                        .retain(|p| !timeline.deleted.contains(&p.coordinate_key()));
+                }
+                let added_posts = merge_timeline_posts(&mut app_data_async.timeline_posts, timeline.posts);
+                // ライブ購読で届いていた記事が取得で入ったら、お知らせから外す
+                let app_data_async = &mut *app_data_async;
+                let timeline_posts = &app_data_async.timeline_posts;
+                app_data_async.pending_posts.retain(|pending| {
+                    !timeline_posts
+                        .iter()
+                        .any(|p| p.coordinate_key() == pending.coordinate_key() && p.created_at >= pending.created_at)
+                });
+                if added_posts > 0 {
+                    println!("Added {} new statuses to the timeline.", added_posts);
+                } else {
+                    println!("No new statuses found.");
+                }
+                // リレーがすべて EOSE を返し、より古い記事がなかったときだけ、それ以上はさかのぼらない
+                // (タイムアウトしただけなら、次にスクロールしたときにもう一度試す)
+                if is_older && timeline.reached_end {
+                    app_data_async.timeline_exhausted = true;
+                }
+                if !is_older {
+                    app_data_async.timeline_gap = timeline.gap;
+                }
+                if added_posts > 0 || !timeline.deleted.is_empty() {
+                    save_timeline(&cache_db_clone, &my_keys.public_key(), &app_data_async.timeline_posts);
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch timeline: {e}");
+                if is_older {
+                    app_data_async.timeline_status = format!("古い記事の読み込みに失敗しました: {e}");
+                }
+            }
+        }
+        app_data_async.should_repaint = true;
+    });
+}
+
 pub fn draw_home_view(
     ui: &mut egui::Ui,
     ctx: &egui::Context,
@@ -17,9 +94,6 @@ pub fn draw_home_view(
     runtime_handle: tokio::runtime::Handle,
     urls_to_load: &mut Vec<(String, ImageKind)>,
 ) {
-    let new_post_window_title_text = "新規投稿";
-    let publish_button_text = "公開";
-    let cancel_button_text = "キャンセル";
     let timeline_heading_text = "ホーム";
     let fetch_latest_button_text = "最新の投稿を取得";
     let no_timeline_message_text = "タイムラインに投稿はまだありません。";
@@ -32,6 +106,8 @@ pub fn draw_home_view(
         ..Default::default()
     };
 
+    timeline_stream::ensure_timeline_stream(app_data, &app_data_arc, &runtime_handle);
+
     // --- ZAP Dialog ---
     if app_data.show_zap_dialog {
         if let Some(post_to_zap) = app_data.zap_target_post.clone() {
@@ -126,157 +202,14 @@ pub fn draw_home_view(
     }
 
 
-    if app_data.show_post_dialog {
-        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Background, "dim_layer".into()));
-        let screen_rect = ctx.screen_rect();
-        painter.add(egui::Shape::rect_filled(screen_rect, 0.0, egui::Color32::from_black_alpha(128)));
-
-        egui::Window::new(new_post_window_title_text)
-            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
-            .collapsible(false)
-            .resizable(true)
-            .show(ctx, |ui| {
-                egui::TopBottomPanel::bottom("post_dialog_buttons")
-                    .show_inside(ui, |ui| {
-                        ui.add_space(10.0);
-                        ui.horizontal(|ui| {
-                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                if ui.button(cancel_button_text).clicked() {
-                                    app_data.show_post_dialog = false;
-                                app_data.article_title_input.clear();
-                                app_data.article_content_input.clear();
-                                }
-                                if ui.button(publish_button_text).clicked() && !app_data.is_loading {
-                                let article_title = app_data.article_title_input.clone();
-                                let article_content = app_data.article_content_input.clone();
-
-                                if article_title.is_empty() {
-                                    eprintln!("Title cannot be empty.");
-                                        return;
-                                    }
-                                if article_content.is_empty() {
-                                    eprintln!("Content cannot be empty.");
-                                    return;
-                                }
-
-                                let client_clone = app_data.nostr_client.as_ref().unwrap().clone();
-                                let keys_clone = app_data.my_keys.clone().unwrap();
-
-                                app_data.is_loading = true;
-                                app_data.should_repaint = true;
-                                println!("Publishing NIP-23 article...");
-
-                                    let cloned_app_data_arc = app_data_arc.clone();
-                                    runtime_handle.spawn(async move {
-                                        let mut tags: Vec<Tag> = Vec::new();
-
-                                    // Add the 't' tag for the title, as per NIP-23
-                                    tags.push(Tag::from_standardized(nostr::TagStandard::Title(article_title)));
-
-                                    // Create the NIP-23 event (kind 30023)
-                                    let event_result = EventBuilder::new(Kind::from(30023), article_content)
-                                            .tags(tags)
-                                        .sign(&keys_clone)
-                                            .await;
-
-                                        match event_result {
-                                        Ok(event) => match client_clone.send_event(&event).await {
-                                                Ok(event_id) => {
-                                                println!("Article published with event id: {event_id:?}");
-                                                    let mut data = cloned_app_data_arc.lock().unwrap();
-                                                data.show_post_dialog = false;
-                                                data.article_title_input.clear();
-                                                data.article_content_input.clear();
-                                                }
-                                                Err(e) => {
-                                                eprintln!("Failed to publish article: {e}");
-                                                }
-                                            },
-                                            Err(e) => {
-                                                eprintln!("Failed to create event: {e}");
-                                            }
-                                        }
-                                        let mut data = cloned_app_data_arc.lock().unwrap();
-                                        data.is_loading = false;
-                                        data.should_repaint = true;
-                                    });
-                                }
-                            });
-                        });
-                    });
-
-                egui::CentralPanel::default().show_inside(ui, |ui| {
-                    ui.add_space(10.0);
-                    ui.label("タイトル:");
-                    ui.add(
-                        egui::TextEdit::singleline(&mut app_data.article_title_input)
-                            .desired_width(f32::INFINITY)
-                            .hint_text("記事のタイトル"),
-                    );
-                    ui.add_space(5.0);
-                    ui.label("本文:");
-                    egui::ScrollArea::vertical().show(ui, |ui| {
-                        ui.add(
-                            egui::TextEdit::multiline(&mut app_data.article_content_input)
-                                .desired_rows(15)
-                                .desired_width(f32::INFINITY)
-                                .hint_text("記事の内容をMarkdownで記述..."),
-                        );
-                    });
-                });
-            });
-
-    }
-
     card_frame.show(ui, |ui| {
         ui.horizontal(|ui| {
             ui.heading(timeline_heading_text);
 
             let fetch_button = egui::Button::new(egui::RichText::new(fetch_latest_button_text).strong());
             if ui.add_enabled(!app_data.is_loading, fetch_button).clicked() {
-                let followed_pubkeys = app_data.followed_pubkeys.clone();
-                let discover_relays = app_data.discover_relays_editor.clone();
-                let my_keys = app_data.my_keys.clone().unwrap();
-                let cache_db_clone = app_data.cache_db.clone();
-
-                app_data.is_loading = true;
-                app_data.should_repaint = true;
-
-                let cloned_app_data_arc = app_data_arc.clone();
-                runtime_handle.spawn(async move {
-                    let timeline_result = fetch_timeline_events(&my_keys, &discover_relays, &followed_pubkeys, &cache_db_clone).await;
-
-                    let mut app_data_async = cloned_app_data_arc.lock().unwrap();
-                    app_data_async.is_loading = false;
-                    match timeline_result {
-                        Ok(new_posts) => {
-                            if !new_posts.is_empty() {
-                                let mut existing_ids: std::collections::HashSet<EventId> = app_data_async.timeline_posts.iter().map(|p| p.id).collect();
-                                let mut added_posts = 0;
-                                for post in new_posts {
-                                    if !existing_ids.contains(&post.id) {
-                                        existing_ids.insert(post.id);
-                                        app_data_async.timeline_posts.push(post);
-                                        added_posts += 1;
-                                    }
-                                }
-
-                                if added_posts > 0 {
-                                    app_data_async.timeline_posts.sort_by_key(|p| std::cmp::Reverse(p.created_at));
-                                    println!("Added {} new statuses to the timeline.", added_posts);
-                                } else {
-                                    println!("No new statuses found.");
-                                }
-                            } else {
-                                println!("Fetched 0 statuses.");
-                            }
-                        },
-                        Err(e) => {
-                            eprintln!("Failed to fetch timeline: {e}");
-                        }
-                    }
-                    app_data_async.should_repaint = true;
-                });
+                let page = TimelinePage::newer_than(&app_data.timeline_posts);
+                fetch_timeline_page(app_data, &app_data_arc, &runtime_handle, page);
             }
 
             if app_data.is_loading {
@@ -288,106 +221,232 @@ pub fn draw_home_view(
         ui.add_space(10.0);
         let pubkey_to_modify: Option<(PublicKey, bool)> = None;
 
-        let heading_text = app_data.selected_label.as_deref().unwrap_or("ホーム");
-        ui.heading(heading_text);
+        ui.horizontal(|ui| {
+            match &app_data.selected_label {
+                Some(label) => ui.heading(format!("#{label}")),
+                None => ui.heading("ホーム"),
+            };
+            if app_data.is_loading_label_posts {
+                ui.spinner();
+            }
+        });
         ui.add_space(10.0);
 
-        if app_data.timeline_posts.is_empty() {
-            ui.label(no_timeline_message_text);
+        // ライブ購読で届いた記事は、読んでいる途中の並びを崩さないようにお知らせだけ出す
+        let mut scroll_to_start = false;
+        if app_data.selected_label.is_none() && !app_data.pending_posts.is_empty() {
+            let banner_text = format!("🔔 {}件の新しい記事", app_data.pending_posts.len());
+            let banner = egui::Button::new(egui::RichText::new(banner_text).strong())
+                .min_size(egui::vec2(ui.available_width(), 32.0))
+                .corner_radius(egui::CornerRadius::from(8.0));
+            if ui.add(banner).clicked() {
+                timeline_stream::show_pending_posts(app_data);
+                scroll_to_start = true;
+            }
+            ui.add_space(10.0);
+        }
+        if app_data.selected_label.is_none() && app_data.timeline_gap {
+            ui.label(
+                egui::RichText::new("新しい記事が多いため、前回の読み込みとの間の記事を一部読み込めていません").small().weak(),
+            );
+            ui.add_space(10.0);
+        }
+
+        let mut article_to_open = None;
+        let mut reaction_to_send = None;
+        let mut repost_to_send = None;
+        let mut reaction_coordinates = Vec::new();
+        let can_react = app_data.my_keys.is_some();
+        let my_pubkey = app_data.my_keys.as_ref().map(|keys| keys.public_key());
+        // ラベルを選んでいないときは、末尾までスクロールしたら古い記事を読み込む
+        let can_load_older = app_data.selected_label.is_none()
+            && !app_data.timeline_exhausted
+            && !app_data.is_loading_older_posts
+            && app_data.my_keys.is_some();
+        let mut load_older = false;
+        // ラベルを選んでいるときは、そのハッシュタグの記事 (リレーの #t 検索の結果を含む) を並べる
+        let posts = if app_data.selected_label.is_some() { &app_data.label_posts } else { &app_data.timeline_posts };
+        if posts.is_empty() {
+            if app_data.selected_label.is_none() {
+                ui.label(no_timeline_message_text);
+            } else if !app_data.is_loading_label_posts {
+                ui.label("このラベルの記事は見つかりませんでした。");
+            }
         } else {
-            egui::ScrollArea::horizontal()
-                .id_salt("timeline_scroll_area_horizontal")
+            let mut scroll_area = egui::ScrollArea::horizontal().id_salt("timeline_scroll_area_horizontal");
+            if scroll_to_start {
+                scroll_area = scroll_area.horizontal_scroll_offset(0.0);
+            }
+            let scroll_output = scroll_area
                 .show(ui, |ui| {
                     ui.horizontal(|ui| {
-                        for post in &app_data.timeline_posts {
-                            // Placeholder filter logic
-                            let display_post = match &app_data.selected_label {
-                                Some(label) if label != "すべて" => post.title.contains(label) || post.summary.contains(label),
-                                _ => true, // Show all for "すべて" or None
+                        for post in posts {
+                            let card_frame = egui::Frame {
+                                inner_margin: egui::Margin::same(12),
+                                outer_margin: egui::Margin::symmetric(5, 0),
+                                corner_radius: 8.0.into(),
+                                shadow: eframe::epaint::Shadow::NONE,
+                                fill: app_data.current_theme.card_background_color(),
+                                ..Default::default()
                             };
 
-                            if display_post {
-                                let card_frame = egui::Frame {
-                                    inner_margin: egui::Margin::same(12),
-                                    outer_margin: egui::Margin::symmetric(5, 0),
-                                    corner_radius: 8.0.into(),
-                                    shadow: eframe::epaint::Shadow::NONE,
-                                    fill: app_data.current_theme.card_background_color(),
-                                    ..Default::default()
-                                };
-
-                                let card_response = card_frame.show(ui, |ui| {
-                                    ui.set_max_width(250.0);
-                                    ui.set_max_height(180.0);
-
-                                    ui.vertical(|ui| {
-                                        ui.horizontal(|ui| {
-                                            let avatar_size = egui::vec2(24.0, 24.0);
-                                            let url = &post.author_metadata.picture;
-                                            if !url.is_empty() {
-                                                let url_key = url.to_string();
-                                                let image_state = app_data.image_cache.get(&url_key).cloned();
-                                                match image_state {
-                                                    Some(ImageState::Loaded(texture_handle)) => {
-                                                        let image_widget = egui::Image::new(&texture_handle)
-                                                            .corner_radius(4.0)
-                                                            .fit_to_exact_size(avatar_size);
-                                                        ui.add(image_widget);
-                                                    }
-                                                    Some(ImageState::Loading) => {
-                                                        let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
-                                                        ui.painter().rect_filled(rect, 4.0, ui.style().visuals.widgets.inactive.bg_fill);
-                                                        ui.put(rect, egui::Spinner::new());
-                                                    }
-                                                    Some(ImageState::Failed) => {
-                                                        let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
-                                                        ui.painter().rect_filled(rect, 4.0, ui.style().visuals.error_fg_color.linear_multiply(0.2));
-                                                    }
-                                                    None => {
-                                                        if !urls_to_load.iter().any(|(u, _)| u == &url_key) {
-                                                            urls_to_load.push((url_key.clone(), ImageKind::Avatar));
-                                                        }
-                                                        let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
-                                                        ui.painter().rect_filled(rect, 4.0, ui.style().visuals.widgets.inactive.bg_fill);
-                                                        ui.put(rect, egui::Spinner::new());
+                            // カード全体をクリックできるようにしつつ、中のリアクションボタンを優先する
+                            let card_response = ui.scope_builder(egui::UiBuilder::new().sense(egui::Sense::click()), |ui| card_frame.show(ui, |ui| {
+                                ui.set_max_width(250.0);
+                                ui.set_max_height(300.0);
+
+                                ui.vertical(|ui| {
+                                    if let Some(repost) = &post.reposted_by {
+                                        repost_view::draw_reposted_by(ui, repost);
+                                        ui.add_space(4.0);
+                                    }
+                                    ui.horizontal(|ui| {
+                                        let avatar_size = egui::vec2(24.0, 24.0);
+                                        let url = &post.author_metadata.picture;
+                                        if !url.is_empty() {
+                                            let url_key = url.to_string();
+                                            let image_state = app_data.image_state(&url_key, ImageKind::Avatar).cloned();
+                                            match image_state {
+                                                Some(ImageState::Loaded(texture_handle)) => {
+                                                    let image_widget = egui::Image::new(&texture_handle)
+                                                        .corner_radius(4.0)
+                                                        .fit_to_exact_size(avatar_size);
+                                                    ui.add(image_widget);
+                                                }
+                                                Some(ImageState::Loading) => {
+                                                    let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
+                                                    ui.painter().rect_filled(rect, 4.0, ui.style().visuals.widgets.inactive.bg_fill);
+                                                    ui.put(rect, egui::Spinner::new());
+                                                }
+                                                Some(ImageState::Failed) => {
+                                                    let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
+                                                    ui.painter().rect_filled(rect, 4.0, ui.style().visuals.error_fg_color.linear_multiply(0.2));
+                                                }
+                                                None => {
+                                                    if !urls_to_load.iter().any(|(u, _)| u == &url_key) {
+                                                        urls_to_load.push((url_key.clone(), ImageKind::Avatar));
                                                     }
+                                                    let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
+                                                    ui.painter().rect_filled(rect, 4.0, ui.style().visuals.widgets.inactive.bg_fill);
+                                                    ui.put(rect, egui::Spinner::new());
                                                 }
-                                            } else {
-                                                let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
-                                                ui.painter().rect_filled(rect, 4.0, ui.style().visuals.widgets.inactive.bg_fill);
                                             }
+                                        } else {
+                                            let (rect, _) = ui.allocate_exact_size(avatar_size, egui::Sense::hover());
+                                            ui.painter().rect_filled(rect, 4.0, ui.style().visuals.widgets.inactive.bg_fill);
+                                        }
 
-                                            let display_name = if !post.author_metadata.name.is_empty() {
-                                                post.author_metadata.name.clone()
-                                            } else {
-                                                let pubkey = post.author_pubkey.to_bech32().unwrap_or_default();
-                                                format!("{}...{}", &pubkey[0..8], &pubkey[pubkey.len()-4..])
-                                            };
-                                            ui.label(egui::RichText::new(display_name).small());
-                                        });
-
-                                        ui.add_space(8.0);
-                                        ui.separator();
-                                        ui.add_space(8.0);
+                                        let display_name = if !post.author_metadata.name.is_empty() {
+                                            post.author_metadata.name.clone()
+                                        } else {
+                                            let pubkey = post.author_pubkey.to_bech32().unwrap_or_default();
+                                            format!("{}...{}", &pubkey[0..8], &pubkey[pubkey.len()-4..])
+                                        };
+                                        ui.label(egui::RichText::new(display_name).small());
+                                    });
 
-                                        if !post.title.is_empty() {
-                                            ui.label(egui::RichText::new(&post.title).strong());
+                                    ui.add_space(8.0);
+                                    ui.separator();
+                                    ui.add_space(8.0);
+
+                                    // ヘッダー画像 (NIP-23 image タグ)
+                                    if !post.image.is_empty() {
+                                        let image_size = egui::vec2(226.0, 110.0);
+                                        match app_data.image_state(&post.image, ImageKind::ArticleHeader).cloned() {
+                                            Some(ImageState::Loaded(texture_handle)) => {
+                                                let image_widget = egui::Image::new(&texture_handle)
+                                                    .corner_radius(4.0)
+                                                    .fit_to_exact_size(image_size);
+                                                ui.add(image_widget);
+                                                ui.add_space(6.0);
+                                            }
+                                            Some(ImageState::Failed) => {}
+                                            image_state => {
+                                                if image_state.is_none() && !urls_to_load.iter().any(|(u, _)| u == &post.image) {
+                                                    urls_to_load.push((post.image.clone(), ImageKind::ArticleHeader));
+                                                }
+                                                let (rect, _) = ui.allocate_exact_size(image_size, egui::Sense::hover());
+                                                ui.painter().rect_filled(rect, 4.0, ui.style().visuals.widgets.inactive.bg_fill);
+                                                ui.add_space(6.0);
+                                            }
                                         }
+                                    }
 
-                                        // The summary is already truncated, so we can just display it.
-                                        ui.label(&post.summary);
-                                    });
+                                    if !post.title.is_empty() {
+                                        ui.label(egui::RichText::new(&post.title).strong());
+                                    }
+
+                                    // The summary is already truncated, so we can just display it.
+                                    ui.label(&post.summary);
+
+                                    ui.add_space(6.0);
+                                    let coordinate_key = post.coordinate_key();
+                                    if !app_data.requested_reactions.contains(&coordinate_key) {
+                                        reaction_coordinates.push((post.id, post.coordinate()));
+                                    }
+                                    let summary = app_data.reaction_summaries.get(&coordinate_key);
+                                    if let Some(content) = reaction_view::draw_reaction_bar(ui, summary, can_react) {
+                                        reaction_to_send = Some((post.id, post.coordinate(), content));
+                                    }
+                                    // 自分の記事はリポストできない
+                                    if Some(post.author_pubkey) != my_pubkey
+                                        && repost_view::draw_repost_button(ui, app_data, &coordinate_key)
+                                    {
+                                        repost_to_send = Some((post.id, post.coordinate()));
+                                    }
                                 });
+                            }));
 
-                                if card_response.response.interact(egui::Sense::click()).clicked() {
-                                    app_data.viewing_article_id = Some(post.id);
-                                    app_data.viewing_article = None; // Clear previous article
-                                    app_data.current_tab = AppTab::ArticleView;
-                                }
+                            if card_response.response.clicked() {
+                                article_to_open = Some(post.coordinate());
                             }
                         }
+
+                        if app_data.selected_label.is_none() {
+                            ui.vertical(|ui| {
+                                ui.set_width(120.0);
+                                if app_data.is_loading_older_posts {
+                                    ui.spinner();
+                                } else if app_data.timeline_exhausted {
+                                    ui.label(egui::RichText::new("これより古い記事はありません").small().weak());
+                                } else {
+                                    if ui.add_enabled(can_load_older, egui::Button::new("さらに読み込む")).clicked() {
+                                        load_older = true;
+                                    }
+                                    if !app_data.timeline_status.is_empty() {
+                                        ui.label(egui::RichText::new(&app_data.timeline_status).small().weak());
+                                    }
+                                }
+                            });
+                        }
                     });
                 });
+            // 読み込みに失敗したときは、ボタンが押されるまで自動では読み込み直さない
+            let visible_end = scroll_output.state.offset.x + scroll_output.inner_rect.width();
+            if can_load_older
+                && app_data.timeline_status.is_empty()
+                && visible_end >= scroll_output.content_size.x - 50.0
+            {
+                load_older = true;
+            }
+        }
+        if load_older {
+            if let Some(page) = TimelinePage::older_than(&app_data.timeline_posts) {
+                fetch_timeline_page(app_data, &app_data_arc, &runtime_handle, page);
+            }
+        }
+        // 表示したカードのリアクション数はまとめて取得する
+        if !reaction_coordinates.is_empty() {
+            reaction_view::request_reactions(app_data, &app_data_arc, &runtime_handle, reaction_coordinates);
+        }
+        if let Some((event_id, coordinate, content)) = reaction_to_send {
+            reaction_view::send_reaction(app_data, &app_data_arc, &runtime_handle, event_id, coordinate, content);
+        }
+        if let Some((event_id, coordinate)) = repost_to_send {
+            repost_view::send_repost(app_data, &app_data_arc, &runtime_handle, event_id, coordinate);
+        }
+        if let Some(coordinate) = article_to_open {
+            app_data.open_article(coordinate);
         }
 
         if let Some((pubkey, follow)) = pubkey_to_modify {
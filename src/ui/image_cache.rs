@@ -1,6 +1,9 @@
-use crate::cache_db::LmdbCache;
+use eframe::egui;
 use sha2::{Digest, Sha256};
 
+use crate::cache_db::LmdbCache;
+use crate::types::ImageKind;
+
 // Hashes the URL to create a stable key for the DB.
 fn url_to_key(url: &str) -> String {
     let mut hasher = Sha256::new();
@@ -28,3 +31,17 @@ pub fn save_to_lmdb(cache_db: &LmdbCache, url: &str, data: &[u8]) {
         eprintln!("Failed to write image to LMDB cache: {}", e);
     }
 }
+
+// 画像をデコードし、種類ごとの最大サイズを超える場合だけ縮小してテクスチャにする
+pub fn decode_texture(ctx: &egui::Context, url: &str, bytes: &[u8], kind: ImageKind) -> Option<egui::TextureHandle> {
+    let mut dynamic_image = image::load_from_memory(bytes).ok()?;
+    let (width, height) = kind.thumbnail_size();
+    if dynamic_image.width() > width || dynamic_image.height() > height {
+        dynamic_image = dynamic_image.thumbnail(width, height);
+    }
+    let color_image = egui::ColorImage::from_rgba_unmultiplied(
+        [dynamic_image.width() as usize, dynamic_image.height() as usize],
+        dynamic_image.to_rgba8().as_flat_samples().as_slice(),
+    );
+    Some(ctx.load_texture(url, color_image, Default::default()))
+}
@@ -0,0 +1,262 @@
+use eframe::egui;
+use nostr::{Kind, Timestamp};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    markdown_io, nip23,
+    nostr_client::{
+        fetch_published_at, load_cached_article, merge_timeline_posts, publish_article, timeline_post_from_article,
+    },
+    types::*,
+    ui::nostr_content,
+};
+
+// 選んだファイル (またはフォルダ内の .md ファイル) を読み込んで取り込み一覧を作る
+fn load_import_items(app_data: &mut NostrStatusAppInternal, paths: Vec<PathBuf>) {
+    let Some(keys) = app_data.my_keys.clone() else {
+        return;
+    };
+
+    let mut items: Vec<ImportedArticle> = Vec::new();
+    for path in paths.iter().flat_map(|path| markdown_io::collect_markdown_files(path)) {
+        let fallback_identifier = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| {
+                markdown_io::parse_markdown_article(&text, &fallback_identifier).map_err(|e| e.to_string())
+            });
+
+        let mut item = ImportedArticle {
+            path: path.clone(),
+            metadata: nip23::ArticleMetadata::default(),
+            content: String::new(),
+            selected: false,
+            is_update: false,
+            error: None,
+            published: false,
+        };
+        match parsed {
+            Ok((mut metadata, content)) => {
+                let key = nip23::coordinate_key(Kind::from(30023), &keys.public_key(), &metadata.identifier);
+                // 公開済みの記事を更新する場合は最初の公開日時を引き継ぐ
+                if let Some(existing) = load_cached_article(&app_data.cache_db, &key) {
+                    item.is_update = true;
+                    if metadata.published_at.is_none() {
+                        metadata.published_at = Some(existing.metadata().published_at.unwrap_or(existing.created_at));
+                    }
+                }
+                if items.iter().any(|other| other.metadata.identifier == metadata.identifier) {
+                    item.error = Some(format!("識別子 \"{}\" が他のファイルと重複しています", metadata.identifier));
+                } else if content.is_empty() {
+                    item.error = Some("本文が空です".to_string());
+                } else {
+                    item.selected = true;
+                }
+                item.metadata = metadata;
+                item.content = content;
+            }
+            Err(e) => item.error = Some(e),
+        }
+        items.push(item);
+    }
+
+    app_data.import_status = format!("{}件のファイルを読み込みました", items.len());
+    app_data.import_preview_index = items.iter().position(|item| item.error.is_none());
+    app_data.import_items = items;
+}
+
+pub fn draw_import_dialog(
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let mut is_open = true;
+    egui::Window::new("Markdownを取り込む")
+        .id(egui::Id::new("markdown_import_window"))
+        .open(&mut is_open)
+        .default_size([760.0, 520.0])
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if ui.button("ファイルを選択...").clicked() {
+                    if let Some(paths) = rfd::FileDialog::new()
+                        .add_filter("Markdown", &["md", "markdown"])
+                        .pick_files()
+                    {
+                        load_import_items(app_data, paths);
+                    }
+                }
+                if ui.button("フォルダを選択...").clicked() {
+                    if let Some(path) = rfd::FileDialog::new().pick_folder() {
+                        load_import_items(app_data, vec![path]);
+                    }
+                }
+                if !app_data.import_status.is_empty() {
+                    ui.label(egui::RichText::new(&app_data.import_status).small().weak());
+                }
+            });
+            ui.label(
+                egui::RichText::new("フロントマターの title / summary / image / tags / published_at / slug を記事のタグに変換します。同じ slug の記事は上書きされます。")
+                    .small()
+                    .weak(),
+            );
+            ui.separator();
+
+            if app_data.import_items.is_empty() {
+                return;
+            }
+
+            egui::TopBottomPanel::bottom("markdown_import_buttons").show_inside(ui, |ui| {
+                ui.add_space(5.0);
+                let selected_count = app_data
+                    .import_items
+                    .iter()
+                    .filter(|item| item.selected && !item.published && item.error.is_none())
+                    .count();
+                let publish_button = egui::Button::new(format!("選択した{selected_count}件を公開"));
+                if ui.add_enabled(selected_count > 0 && !app_data.is_loading, publish_button).clicked() {
+                    publish_selected(app_data, &app_data_arc, &runtime_handle);
+                }
+            });
+
+            ui.columns(2, |columns| {
+                egui::ScrollArea::vertical().id_salt("markdown_import_list").show(&mut columns[0], |ui| {
+                    for (i, item) in app_data.import_items.iter_mut().enumerate() {
+                        ui.horizontal(|ui| {
+                            let can_publish = item.error.is_none() && !item.published;
+                            ui.add_enabled(can_publish, egui::Checkbox::without_text(&mut item.selected));
+                            let file_name = item.path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                            let label = if item.metadata.title.is_empty() { file_name } else { item.metadata.title.as_str() };
+                            let is_previewing = app_data.import_preview_index == Some(i);
+                            if ui.selectable_label(is_previewing, label).on_hover_text(file_name).clicked() {
+                                app_data.import_preview_index = Some(i);
+                            }
+                        });
+                        let status = if let Some(error) = &item.error {
+                            egui::RichText::new(error).small().color(egui::Color32::RED)
+                        } else if item.published {
+                            egui::RichText::new("公開しました").small().strong()
+                        } else if item.is_update {
+                            egui::RichText::new(format!("更新 (d: {})", item.metadata.identifier)).small().weak()
+                        } else {
+                            egui::RichText::new(format!("新規 (d: {})", item.metadata.identifier)).small().weak()
+                        };
+                        ui.label(status);
+                        ui.add_space(4.0);
+                    }
+                });
+
+                egui::ScrollArea::vertical().id_salt("markdown_import_preview").show(&mut columns[1], |ui| {
+                    let Some(item) = app_data.import_preview_index.and_then(|i| app_data.import_items.get(i)) else {
+                        return;
+                    };
+                    ui.heading(&item.metadata.title);
+                    if !item.metadata.summary.is_empty() {
+                        ui.label(egui::RichText::new(&item.metadata.summary).italics().weak());
+                    }
+                    if !item.metadata.hashtags.is_empty() {
+                        let hashtags: Vec<String> = item.metadata.hashtags.iter().map(|h| format!("#{h}")).collect();
+                        ui.label(egui::RichText::new(hashtags.join(" ")).small().color(ui.visuals().hyperlink_color));
+                    }
+                    ui.separator();
+                    // 公開後と同じ表示になるよう、記事画面と同じレンダラーで表示する
+                    // (後ろに表示している記事の目次の現在位置は上書きしない)
+                    let content = item.content.clone();
+                    let current_heading = app_data.current_heading;
+                    nostr_content::draw_nostr_content(
+                        ui,
+                        app_data,
+                        &app_data_arc,
+                        &runtime_handle,
+                        "import_preview",
+                        &content,
+                        &[],
+                        urls_to_load,
+                    );
+                    app_data.current_heading = current_heading;
+                });
+            });
+        });
+
+    if !is_open {
+        app_data.show_import_dialog = false;
+        app_data.import_items.clear();
+        app_data.import_preview_index = None;
+        app_data.import_status.clear();
+    }
+}
+
+// 選択された記事を1件ずつ順番に公開する
+fn publish_selected(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let cache_db = app_data.cache_db.clone();
+    let my_metadata = app_data.editable_profile.clone();
+    let batch: Vec<(usize, nip23::ArticleMetadata, String)> = app_data
+        .import_items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.selected && !item.published && item.error.is_none())
+        .map(|(i, item)| (i, item.metadata.clone(), item.content.clone()))
+        .collect();
+
+    app_data.is_loading = true;
+    app_data.import_status = format!("{}件を公開中...", batch.len());
+    app_data.should_repaint = true;
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let total = batch.len();
+        let mut published = 0;
+        for (i, mut metadata, content) in batch {
+            // キャッシュにない記事も、リレーに公開済みなら最初の公開日時を引き継ぐ
+            if metadata.published_at.is_none() {
+                match fetch_published_at(&client, &keys.public_key(), &metadata.identifier).await {
+                    Ok(published_at) => metadata.published_at = Some(published_at.unwrap_or_else(Timestamp::now)),
+                    Err(e) => {
+                        eprintln!("Failed to check published article {}: {}", metadata.identifier, e);
+                        let mut data = cloned_app_data_arc.lock().unwrap();
+                        if let Some(item) = data.import_items.get_mut(i) {
+                            item.error = Some(format!("公開済みの記事を確認できませんでした: {e}"));
+                        }
+                        data.should_repaint = true;
+                        continue;
+                    }
+                }
+            }
+            let result = publish_article(&cache_db, &client, &keys, &metadata, &content, my_metadata.clone()).await;
+            let mut data = cloned_app_data_arc.lock().unwrap();
+            match result {
+                Ok(article) => {
+                    published += 1;
+                    let key = article.coordinate_key();
+                    if data.timeline_posts.iter().any(|p| p.coordinate_key() == key) {
+                        merge_timeline_posts(&mut data.timeline_posts, vec![timeline_post_from_article(&article)]);
+                    }
+                    if let Some(item) = data.import_items.get_mut(i) {
+                        item.published = true;
+                        item.selected = false;
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to publish imported article {}: {}", metadata.identifier, e);
+                    if let Some(item) = data.import_items.get_mut(i) {
+                        item.error = Some(format!("公開に失敗しました: {e}"));
+                    }
+                }
+            }
+            data.import_status = format!("{published}/{total}件を公開しました");
+            data.should_repaint = true;
+        }
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        data.is_loading = false;
+        data.should_repaint = true;
+    });
+}
@@ -0,0 +1,236 @@
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    nip23,
+    nostr_client::{fetch_hashtag_articles, fetch_interests, merge_timeline_posts, update_interest},
+    types::*,
+    ui::spawn_update,
+};
+
+// ピン留めしていないタグをいくつまで並べるか
+const MAX_TRENDING_LABELS: usize = 12;
+
+// 自分の興味リスト (kind 10015) を読み込む。失敗したら「再読み込み」が押されるまで待つ
+fn ensure_interests_loaded(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    if app_data.interests_loaded || app_data.is_loading_interests || app_data.interests_load_failed {
+        return;
+    }
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    app_data.is_loading_interests = true;
+
+    spawn_update(
+        app_data_arc,
+        runtime_handle,
+        async move { fetch_interests(&client, &keys).await },
+        |data, result| {
+            match result {
+                Ok(interests) => {
+                    data.interests_list_missing = interests.is_none();
+                    data.interests = interests.unwrap_or_default();
+                    data.interests_loaded = true;
+                }
+                Err(e) => {
+                    eprintln!("Failed to fetch interests: {e}");
+                    data.interests_load_failed = true;
+                    data.label_status = format!("興味リストの読み込みに失敗しました: {e}");
+                }
+            }
+            data.is_loading_interests = false;
+        },
+    );
+}
+
+// ラベルを選ぶ。読み込み済みの記事をすぐに出し、リレーに #t で問い合わせた結果を足す
+fn select_label(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    label: Option<String>,
+) {
+    app_data.current_tab = AppTab::Home;
+    app_data.selected_label = label.clone();
+    app_data.label_status.clear();
+    let Some(label) = label else {
+        app_data.label_posts.clear();
+        app_data.is_loading_label_posts = false;
+        return;
+    };
+    app_data.label_posts = app_data
+        .timeline_posts
+        .iter()
+        .filter(|post| post.hashtags().contains(&label))
+        .cloned()
+        .collect();
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    app_data.is_loading_label_posts = true;
+    app_data.should_repaint = true;
+
+    let cache_db = app_data.cache_db.clone();
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = fetch_hashtag_articles(&cache_db, &client, &label).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        if data.selected_label.as_deref() != Some(label.as_str()) {
+            return;
+        }
+        match result {
+            Ok(posts) => {
+                merge_timeline_posts(&mut data.label_posts, posts);
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch articles for #{label}: {e}");
+                data.label_status = format!("リレーからの取得に失敗しました: {e}");
+            }
+        }
+        data.is_loading_label_posts = false;
+        data.should_repaint = true;
+    });
+}
+
+// ハッシュタグをピン留めする (またはピン留めを外す)。先に手元を変えてから公開し、
+// 成功したらリレーの最新のリストに合わせ、失敗したら元に戻す
+fn set_interest_pinned(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    label: String,
+    pinned: bool,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    if !app_data.interests_loaded {
+        return;
+    }
+    let previous = app_data.interests.clone();
+    if pinned {
+        if app_data.interests.contains(&label) {
+            return;
+        }
+        app_data.interests.push(label.clone());
+    } else {
+        app_data.interests.retain(|interest| interest != &label);
+    }
+    let list_missing = app_data.interests_list_missing;
+    app_data.is_saving_interests = true;
+    app_data.label_status.clear();
+    app_data.should_repaint = true;
+
+    spawn_update(
+        app_data_arc,
+        runtime_handle,
+        async move { update_interest(&client, &keys, &label, pinned, list_missing).await },
+        |data, result| {
+            match result {
+                Ok(interests) => {
+                    data.interests = interests;
+                    data.interests_list_missing = false;
+                }
+                Err(e) => {
+                    eprintln!("Failed to publish interests: {e}");
+                    data.interests = previous;
+                    data.label_status = format!("興味リストの保存に失敗しました: {e}");
+                }
+            }
+            data.is_saving_interests = false;
+        },
+    );
+}
+
+// サイドバーのラベル一覧
+pub fn draw_label_sidebar(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    if app_data.is_logged_in {
+        ensure_interests_loaded(app_data, app_data_arc, runtime_handle);
+    }
+
+    ui.horizontal(|ui| {
+        ui.label(egui::RichText::new("ラベル一覧").strong());
+        if app_data.is_loading_label_posts || app_data.is_saving_interests || app_data.is_loading_interests {
+            ui.spinner();
+        }
+    });
+    if app_data.interests_load_failed && ui.small_button("興味リストを再読み込み").clicked() {
+        app_data.interests_load_failed = false;
+        app_data.label_status.clear();
+    }
+    ui.add_space(10.0);
+
+    let counts = app_data.label_counts();
+    let count_of = |label: &str| counts.iter().find(|(l, _)| l == label).map_or(0, |(_, count)| *count);
+    let can_pin = app_data.is_logged_in && app_data.interests_loaded && !app_data.is_saving_interests;
+    // (ラベル, ピン留めするなら true / 外すなら false)
+    let mut pin_change = None;
+    let mut to_select = None;
+
+    if ui.selectable_label(app_data.selected_label.is_none(), "すべて").clicked() {
+        to_select = Some(None);
+    }
+    let pinned: Vec<(String, bool)> = app_data.interests.iter().map(|label| (label.clone(), true)).collect();
+    let trending: Vec<(String, bool)> = counts
+        .iter()
+        .filter(|(label, _)| !app_data.interests.contains(label))
+        .take(MAX_TRENDING_LABELS)
+        .map(|(label, _)| (label.clone(), false))
+        .collect();
+    for (label, is_pinned) in pinned.into_iter().chain(trending) {
+        let count = count_of(&label);
+        let text = match (is_pinned, count) {
+            (true, 0) => format!("📌 #{label}"),
+            (true, _) => format!("📌 #{label} ({count})"),
+            (false, _) => format!("#{label} ({count})"),
+        };
+        let is_selected = app_data.selected_label.as_deref() == Some(label.as_str());
+        let response = ui.selectable_label(is_selected, text);
+        if response.clicked() {
+            // もう一度押したら選択を外す
+            to_select = Some(if is_selected { None } else { Some(label.clone()) });
+        }
+        if can_pin {
+            response.context_menu(|ui| {
+                let menu_text = if is_pinned { "ピン留めを外す" } else { "ピン留めする" };
+                if ui.button(menu_text).clicked() {
+                    pin_change = Some((label.clone(), !is_pinned));
+                    ui.close();
+                }
+            });
+        }
+    }
+
+    if app_data.is_logged_in {
+        ui.add_space(5.0);
+        let response = ui.add_enabled(
+            can_pin,
+            egui::TextEdit::singleline(&mut app_data.interest_input).hint_text("ハッシュタグをピン留め"),
+        );
+        if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+            if let Some(label) = nip23::parse_hashtags(&app_data.interest_input).into_iter().next() {
+                pin_change = Some((label, true));
+            }
+            app_data.interest_input.clear();
+        }
+    }
+    if !app_data.label_status.is_empty() {
+        ui.label(egui::RichText::new(&app_data.label_status).small().weak());
+    }
+
+    if let Some((label, pinned)) = pin_change {
+        set_interest_pinned(app_data, app_data_arc, runtime_handle, label, pinned);
+    }
+    if let Some(label) = to_select {
+        select_label(app_data, app_data_arc, runtime_handle, label);
+    }
+}
@@ -12,7 +12,10 @@ use crate::{
     types::{Config, EditableRelay, NostrStatusAppInternal, ProfileMetadata, TimelinePost, AppTab},
     cache_db::{LmdbCache, DB_FOLLOWED, DB_RELAYS, DB_PROFILES, DB_TIMELINE},
     CONFIG_FILE,
-    nostr_client::{connect_to_relays_with_nip65, fetch_nip01_profile, fetch_timeline_events}
+    nostr_client::{
+        connect_to_relays_with_nip65, fetch_nip01_profile, fetch_timeline_events, load_timeline, merge_timeline_posts,
+        save_timeline, TimelinePage,
+    }
 };
 
 // --- Step 1: キャッシュからデータを読み込む ---
@@ -54,6 +57,7 @@ fn load_data_from_cache(
 struct FreshData {
     followed_pubkeys: HashSet<PublicKey>,
     timeline_posts: Vec<TimelinePost>,
+    timeline_gap: bool,
     log_message: String,
     fetched_nip65_relays: Vec<(String, Option<String>)>,
     profile_metadata: ProfileMetadata,
@@ -108,8 +112,20 @@ async fn fetch_fresh_data_from_network(
         cache_db.write_cache(DB_FOLLOWED, &pubkey_hex, &followed_pubkeys)?;
     }
 
-    let timeline_posts = fetch_timeline_events(keys, discover_relays, &followed_pubkeys, cache_db).await?;
-    cache_db.write_cache(DB_TIMELINE, &pubkey_hex, &timeline_posts)?;
+    // 保存済みのタイムラインに、それより新しい記事を足す
+    let mut timeline_posts = load_timeline(cache_db, &keys.public_key());
+    if received_nip02 {
+        // フォローを外したユーザーの記事 (とリポスト) は履歴から外す
+        timeline_posts.retain(|p| {
+            followed_pubkeys.contains(&p.author_pubkey)
+                && p.reposted_by.as_ref().is_none_or(|r| followed_pubkeys.contains(&r.reposter_pubkey))
+        });
+    }
+    let page = TimelinePage::newer_than(&timeline_posts);
+    let timeline = fetch_timeline_events(keys, discover_relays, &followed_pubkeys, cache_db, page).await?;
+    timeline_posts.retain(|p| !timeline.deleted.contains(&p.coordinate_key()));
+    merge_timeline_posts(&mut timeline_posts, timeline.posts);
+    save_timeline(cache_db, &keys.public_key(), &timeline_posts);
 
     let (profile_metadata, profile_json_string) =
         fetch_nip01_profile(client, keys.public_key()).await?;
@@ -118,6 +134,7 @@ async fn fetch_fresh_data_from_network(
     Ok(FreshData {
         followed_pubkeys,
         timeline_posts,
+        timeline_gap: timeline.gap,
         log_message,
         fetched_nip65_relays,
         profile_metadata,
@@ -210,7 +227,7 @@ pub fn draw_login_view(
                         };
                         if let Ok(cached_data) = load_data_from_cache(&cache_db_clone, &pubkey_hex) {
                             let mut app_data = app_data_for_login_logic.lock().unwrap();
-                            app_data.my_keys = Some(keys.clone());
+                            app_data.load_account_state(&keys);
                             app_data.nostr_client = Some(client.clone());
                             app_data.followed_pubkeys = cached_data.followed_pubkeys;
                             app_data.timeline_posts = cached_data.timeline_posts;
@@ -227,7 +244,7 @@ pub fn draw_login_view(
                             app_data.is_loading = true;
                         } else {
                             let mut app_data = app_data_for_login_logic.lock().unwrap();
-                            app_data.my_keys = Some(keys.clone());
+                            app_data.load_account_state(&keys);
                             app_data.nostr_client = Some(client.clone());
                             app_data.is_logged_in = true;
                             app_data.is_loading = true;
@@ -237,6 +254,7 @@ pub fn draw_login_view(
                             let mut app_data = app_data_for_login_logic.lock().unwrap();
                             app_data.followed_pubkeys = fresh_data.followed_pubkeys;
                             app_data.timeline_posts = fresh_data.timeline_posts;
+                            app_data.timeline_gap = fresh_data.timeline_gap;
                             if let Some(pos) = fresh_data.log_message.find("--- 現在接続中のリレー ---") {
                                 app_data.connected_relays_display = fresh_data.log_message[pos..].to_string();
                             }
@@ -333,12 +351,13 @@ pub fn draw_login_view(
                         let fresh_data_result = fetch_fresh_data_from_network(&client, &keys, &discover_relays, &default_relays, &cache_db_clone).await;
                         if let Ok(fresh_data) = fresh_data_result {
                             let mut app_data = cloned_app_data_arc.lock().unwrap();
-                            app_data.my_keys = Some(keys.clone());
+                            app_data.load_account_state(&keys);
                             app_data.nostr_client = Some(client);
                             app_data.is_logged_in = true;
                             app_data.current_tab = AppTab::Home;
                             app_data.followed_pubkeys = fresh_data.followed_pubkeys;
                             app_data.timeline_posts = fresh_data.timeline_posts;
+                            app_data.timeline_gap = fresh_data.timeline_gap;
                             if let Some(pos) = fresh_data.log_message.find("--- 現在接続中のリレー ---") {
                                 app_data.connected_relays_display = fresh_data.log_message[pos..].to_string();
                             }
@@ -0,0 +1,188 @@
+use eframe::egui;
+use nostr::{nips::nip19::{Nip19Coordinate, ToBech32}, PublicKey};
+use std::collections::HashMap;
+
+use crate::{cache_db::DB_PROFILES, types::*};
+
+// 候補として表示する最大件数
+const MAX_MENTION_CANDIDATES: usize = 8;
+
+/// Returns the position of the `@` and the text typed after it, when the cursor
+/// is right after an `@query` that starts a word.
+pub fn mention_query(text: &str, cursor: usize) -> Option<(usize, String)> {
+    let before: Vec<char> = text.chars().take(cursor).collect();
+    let at = before.iter().rposition(|c| *c == '@')?;
+    if at > 0 && !before[at - 1].is_whitespace() {
+        return None;
+    }
+    let query: String = before[at + 1..].iter().collect();
+    if query.chars().any(char::is_whitespace) || query.chars().count() > 32 {
+        return None;
+    }
+    Some((at, query))
+}
+
+pub fn byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices().nth(char_index).map_or(text.len(), |(i, _)| i)
+}
+
+/// Replaces the characters `start..end` with `replacement` and moves the cursor
+/// of the text edit `id` to the end of the inserted text.
+pub fn replace_and_move_cursor(
+    ctx: &egui::Context,
+    id: egui::Id,
+    text: &mut String,
+    start: usize,
+    end: usize,
+    replacement: &str,
+) -> usize {
+    let (start_byte, end_byte) = (byte_index(text, start), byte_index(text, end));
+    text.replace_range(start_byte..end_byte, replacement);
+    let cursor = start + replacement.chars().count();
+    if let Some(mut state) = egui::TextEdit::load_state(ctx, id) {
+        let ccursor = egui::text::CCursor::new(cursor);
+        state.cursor.set_char_range(Some(egui::text::CCursorRange::one(ccursor)));
+        state.store(ctx, id);
+    }
+    cursor
+}
+
+// フォロー中のユーザーとキャッシュ済みのプロフィールから候補を作る
+pub fn load_mention_candidates(app_data: &mut NostrStatusAppInternal) {
+    let mut profiles: HashMap<PublicKey, ProfileMetadata> = HashMap::new();
+    if let Ok(entries) = app_data.cache_db.read_all_cache::<ProfileMetadata>(DB_PROFILES) {
+        for (key, cache) in entries {
+            if let Ok(public_key) = PublicKey::from_hex(&key) {
+                profiles.insert(public_key, cache.data);
+            }
+        }
+    }
+    for post in &app_data.timeline_posts {
+        profiles.entry(post.author_pubkey).or_insert_with(|| post.author_metadata.clone());
+    }
+    for public_key in &app_data.followed_pubkeys {
+        profiles.entry(*public_key).or_default();
+    }
+
+    let mut candidates: Vec<MentionCandidate> = profiles
+        .into_iter()
+        .map(|(public_key, metadata)| MentionCandidate {
+            public_key,
+            name: display_name(&public_key, &metadata),
+            nip05: metadata.nip05.clone(),
+            is_followed: app_data.followed_pubkeys.contains(&public_key),
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.is_followed.cmp(&a.is_followed).then_with(|| a.name.cmp(&b.name)));
+    app_data.mention_candidates = Some(candidates);
+}
+
+// 名前か nip05 に入力した文字列を含む候補 (フォロー中のユーザーを優先する)
+pub fn matching_candidates(app_data: &NostrStatusAppInternal, query: &str) -> Vec<MentionCandidate> {
+    let query = query.to_lowercase();
+    app_data
+        .mention_candidates
+        .iter()
+        .flatten()
+        .filter(|candidate| {
+            query.is_empty()
+                || candidate.name.to_lowercase().contains(&query)
+                || candidate.nip05.to_lowercase().contains(&query)
+        })
+        .take(MAX_MENTION_CANDIDATES)
+        .cloned()
+        .collect()
+}
+
+/// Handles the arrow / Enter / Tab / Escape keys for an open mention popup.
+/// Must run before the text edit so it doesn't receive these keys.
+/// Returns true when the highlighted candidate should be inserted.
+pub fn handle_mention_keys(
+    ui: &egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    at: usize,
+    candidate_count: usize,
+) -> bool {
+    if candidate_count == 0 {
+        return false;
+    }
+    ui.input_mut(|i| {
+        if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown) {
+            app_data.mention_selected = (app_data.mention_selected + 1) % candidate_count;
+        }
+        if i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp) {
+            app_data.mention_selected = (app_data.mention_selected + candidate_count - 1) % candidate_count;
+        }
+        if i.consume_key(egui::Modifiers::NONE, egui::Key::Escape) {
+            app_data.mention_dismissed_at = Some(at);
+            return false;
+        }
+        i.consume_key(egui::Modifiers::NONE, egui::Key::Enter) || i.consume_key(egui::Modifiers::NONE, egui::Key::Tab)
+    })
+}
+
+/// Shows the candidate list under the cursor. Returns the clicked candidate.
+pub fn draw_mention_popup(
+    ctx: &egui::Context,
+    app_data: &NostrStatusAppInternal,
+    candidates: &[MentionCandidate],
+    position: egui::Pos2,
+) -> Option<MentionCandidate> {
+    let mut picked = None;
+    egui::Area::new(egui::Id::new("mention_popup"))
+        .order(egui::Order::Foreground)
+        .fixed_pos(position)
+        .show(ctx, |ui| {
+            egui::Frame::popup(ui.style()).show(ui, |ui| {
+                ui.set_min_width(240.0);
+                for (i, candidate) in candidates.iter().enumerate() {
+                    let label = candidate.label();
+                    let response = ui.selectable_label(i == app_data.mention_selected, label);
+                    if response.clicked() {
+                        picked = Some(candidate.clone());
+                    }
+                }
+            });
+        });
+    picked
+}
+
+/// Shows a searchable list of known articles and returns the `nostr:naddr…`
+/// URI of the one that was picked.
+pub fn draw_article_link_picker(ctx: &egui::Context, app_data: &mut NostrStatusAppInternal) -> Option<String> {
+    let mut picked = None;
+    let mut is_open = true;
+    egui::Window::new("記事へのリンクを挿入")
+        .id(egui::Id::new("article_link_picker"))
+        .open(&mut is_open)
+        .default_size([420.0, 360.0])
+        .show(ctx, |ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut app_data.article_link_query)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("タイトルで検索"),
+            );
+            ui.separator();
+            let query = app_data.article_link_query.to_lowercase();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for article in app_data
+                    .article_link_candidates
+                    .iter()
+                    .filter(|a| query.is_empty() || a.title.to_lowercase().contains(&query))
+                {
+                    let author = display_name(&article.author_pubkey, &article.author_metadata);
+                    let title = if article.title.is_empty() { "(無題)" } else { article.title.as_str() };
+                    if ui.selectable_label(false, format!("{title} — {author}")).clicked() {
+                        match Nip19Coordinate::new(article.coordinate(), Vec::new()).to_bech32() {
+                            Ok(naddr) => picked = Some(format!("nostr:{naddr}")),
+                            Err(e) => eprintln!("Failed to encode naddr: {e}"),
+                        }
+                    }
+                }
+            });
+        });
+    if !is_open || picked.is_some() {
+        app_data.show_article_link_picker = false;
+    }
+    picked
+}
@@ -0,0 +1,1027 @@
+use eframe::egui;
+use egui_extras::syntax_highlighting::{self, CodeTheme};
+use nostr::nips::nip19::{FromBech32, Nip19, ToBech32};
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use crate::{nip23, nostr_client::resolve_nostr_references, types::*, ui::highlight_view};
+
+const PARSER_OPTIONS: Options = Options::ENABLE_TABLES
+    .union(Options::ENABLE_STRIKETHROUGH)
+    .union(Options::ENABLE_TASKLISTS);
+
+// 記事本文のブロック。pulldown-cmark のイベントを一度だけ走査して組み立てるので、
+// リストや引用の入れ子、参照形式のリンクは Markdown のとおりに残る
+enum ContentBlock {
+    Paragraph(Vec<Inline>),
+    // index は本文の先頭から数えた見出しの番号 (目次からのスクロールと現在の見出しの判定に使う)
+    Heading { level: usize, index: usize, inlines: Vec<Inline> },
+    // 段落に nostr: URI だけが書かれたイベント・記事の引用
+    Quote(Nip19),
+    Code { language: String, code: String },
+    BlockQuote(Vec<ContentBlock>),
+    List { start: Option<u64>, items: Vec<ListItem> },
+    Table { header: Vec<Vec<Inline>>, rows: Vec<Vec<Vec<Inline>>> },
+    Rule,
+    Html(String),
+}
+
+struct ListItem {
+    // タスクリストの項目ならチェックの状態
+    checked: Option<bool>,
+    blocks: Vec<ContentBlock>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct InlineStyle {
+    strong: bool,
+    emphasis: bool,
+    strikethrough: bool,
+    code: bool,
+}
+
+enum Inline {
+    // link はリンク先 (nostr: URI を含む)
+    Text { text: String, style: InlineStyle, link: Option<String> },
+    Image { alt: String, url: String, link: Option<String> },
+}
+
+// 組み立て中の入れ子のブロック
+enum Container {
+    Root(Vec<ContentBlock>),
+    BlockQuote(Vec<ContentBlock>),
+    List { start: Option<u64>, items: Vec<ListItem> },
+    Item(ListItem),
+    Table { header: Vec<Vec<Inline>>, rows: Vec<Vec<Vec<Inline>>> },
+}
+
+struct ContentBuilder<'a> {
+    app_data: &'a NostrStatusAppInternal,
+    content: &'a str,
+    containers: Vec<Container>,
+    inlines: Vec<Inline>,
+    // 組み立て中の段落の元の範囲
+    source: Option<Range<usize>>,
+    style: InlineStyle,
+    link: Option<String>,
+    // 組み立て中の画像の URL と代替テキスト
+    image: Option<(String, String)>,
+    // 組み立て中のコードブロックの言語とコード
+    code: Option<(String, String)>,
+    html: Option<String>,
+    heading_level: Option<usize>,
+    heading_count: usize,
+}
+
+impl ContentBuilder<'_> {
+    fn push_block(&mut self, block: ContentBlock) {
+        match self.containers.last_mut() {
+            Some(Container::Root(blocks) | Container::BlockQuote(blocks)) => blocks.push(block),
+            Some(Container::Item(item)) => item.blocks.push(block),
+            // リストとテーブルの直下にはブロックが来ない
+            _ => {}
+        }
+    }
+
+    // 組み立て中の文章を段落にする (詰めて書かれたリストの項目には段落のタグがない)
+    fn flush_inlines(&mut self) {
+        let source = self.source.take().unwrap_or_default();
+        if self.inlines.is_empty() {
+            return;
+        }
+        let inlines = std::mem::take(&mut self.inlines);
+        let block = self.quote_block(&source).unwrap_or(ContentBlock::Paragraph(inlines));
+        self.push_block(block);
+    }
+
+    fn quote_block(&self, source: &Range<usize>) -> Option<ContentBlock> {
+        let text = self.content.get(source.clone())?.trim();
+        match nip23::nostr_uris(text).as_slice() {
+            [(range, entity)] if is_quotable(entity) && range.len() == text.len() => Some(ContentBlock::Quote(entity.clone())),
+            _ => None,
+        }
+    }
+
+    fn extend_source(&mut self, range: Range<usize>) {
+        self.source = Some(match self.source.take() {
+            Some(source) => source.start.min(range.start)..source.end.max(range.end),
+            None => range,
+        });
+    }
+
+    fn push_span(&mut self, text: &str, link: Option<String>) {
+        if !text.is_empty() {
+            self.inlines.push(Inline::Text { text: text.to_string(), style: self.style, link });
+        }
+    }
+
+    // 文章の中の nostr: URI はプロフィール名や記事のタイトルを表示するリンクにする
+    fn push_text(&mut self, text: &str, range: Range<usize>) {
+        if let Some((_, alt)) = self.image.as_mut() {
+            alt.push_str(text);
+            return;
+        }
+        if let Some((_, code)) = self.code.as_mut() {
+            code.push_str(text);
+            return;
+        }
+        self.extend_source(range);
+        if self.link.is_some() || self.style.code || !text.contains("nostr:") {
+            self.push_span(text, self.link.clone());
+            return;
+        }
+        let mut last = 0;
+        for (range, entity) in nip23::nostr_uris(text) {
+            self.push_span(&text[last..range.start], None);
+            let uri = &text[range.clone()];
+            let label = reference_label(self.app_data, &entity, uri.trim_start_matches("nostr:"));
+            self.push_span(&label, Some(uri.to_string()));
+            last = range.end;
+        }
+        self.push_span(&text[last..], None);
+    }
+
+    fn event(&mut self, event: Event, range: Range<usize>) {
+        match event {
+            Event::Start(tag) => self.start(tag, range),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) | Event::InlineHtml(text) => self.push_text(&text, range),
+            Event::Code(text) => {
+                let style = self.style;
+                self.style.code = true;
+                self.push_text(&text, range);
+                self.style = style;
+            }
+            Event::Html(html) => match self.html.as_mut() {
+                Some(block) => block.push_str(&html),
+                None => self.push_text(&html, range),
+            },
+            Event::SoftBreak => self.push_text(" ", range),
+            Event::HardBreak => self.push_text("\n", range),
+            Event::Rule => {
+                self.flush_inlines();
+                self.push_block(ContentBlock::Rule);
+            }
+            Event::TaskListMarker(checked) => {
+                if let Some(Container::Item(item)) = self.containers.last_mut() {
+                    item.checked = Some(checked);
+                }
+            }
+            // 脚注と数式は有効にしていない
+            Event::FootnoteReference(_) | Event::InlineMath(_) | Event::DisplayMath(_) => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag, range: Range<usize>) {
+        match tag {
+            Tag::Paragraph => self.flush_inlines(),
+            Tag::Heading { level, .. } => {
+                self.flush_inlines();
+                self.heading_level = Some(level as usize);
+            }
+            Tag::BlockQuote(_) => {
+                self.flush_inlines();
+                self.containers.push(Container::BlockQuote(Vec::new()));
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush_inlines();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info.split_whitespace().next().unwrap_or_default().to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((language, String::new()));
+            }
+            Tag::HtmlBlock => {
+                self.flush_inlines();
+                self.html = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.flush_inlines();
+                self.containers.push(Container::List { start, items: Vec::new() });
+            }
+            Tag::Item => self.containers.push(Container::Item(ListItem { checked: None, blocks: Vec::new() })),
+            Tag::Table(_) => {
+                self.flush_inlines();
+                self.containers.push(Container::Table { header: Vec::new(), rows: Vec::new() });
+            }
+            Tag::TableRow => {
+                if let Some(Container::Table { rows, .. }) = self.containers.last_mut() {
+                    rows.push(Vec::new());
+                }
+            }
+            Tag::Emphasis => self.style.emphasis = true,
+            Tag::Strong => self.style.strong = true,
+            Tag::Strikethrough => self.style.strikethrough = true,
+            Tag::Link { dest_url, .. } => self.link = Some(dest_url.to_string()),
+            Tag::Image { dest_url, .. } => {
+                self.extend_source(range);
+                self.image = Some((dest_url.to_string(), String::new()));
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.flush_inlines(),
+            TagEnd::Heading(_) => {
+                self.source = None;
+                let block = ContentBlock::Heading {
+                    level: self.heading_level.take().unwrap_or(1),
+                    index: self.heading_count,
+                    inlines: std::mem::take(&mut self.inlines),
+                };
+                self.heading_count += 1;
+                self.push_block(block);
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush_inlines();
+                if let Some(Container::BlockQuote(blocks)) = self.containers.pop() {
+                    self.push_block(ContentBlock::BlockQuote(blocks));
+                }
+            }
+            TagEnd::CodeBlock => {
+                if let Some((language, code)) = self.code.take() {
+                    self.push_block(ContentBlock::Code { language, code });
+                }
+            }
+            TagEnd::HtmlBlock => {
+                if let Some(html) = self.html.take() {
+                    self.push_block(ContentBlock::Html(html));
+                }
+            }
+            TagEnd::List(_) => {
+                if let Some(Container::List { start, items }) = self.containers.pop() {
+                    self.push_block(ContentBlock::List { start, items });
+                }
+            }
+            TagEnd::Item => {
+                self.flush_inlines();
+                if let Some(Container::Item(item)) = self.containers.pop() {
+                    if let Some(Container::List { items, .. }) = self.containers.last_mut() {
+                        items.push(item);
+                    }
+                }
+            }
+            TagEnd::TableCell => {
+                self.source = None;
+                let cell = std::mem::take(&mut self.inlines);
+                if let Some(Container::Table { header, rows }) = self.containers.last_mut() {
+                    // 見出し行のセルは TableRow に囲まれていない
+                    match rows.last_mut() {
+                        Some(row) => row.push(cell),
+                        None => header.push(cell),
+                    }
+                }
+            }
+            TagEnd::Table => {
+                if let Some(Container::Table { header, rows }) = self.containers.pop() {
+                    self.push_block(ContentBlock::Table { header, rows });
+                }
+            }
+            TagEnd::Emphasis => self.style.emphasis = false,
+            TagEnd::Strong => self.style.strong = false,
+            TagEnd::Strikethrough => self.style.strikethrough = false,
+            TagEnd::Link => self.link = None,
+            TagEnd::Image => {
+                if let Some((url, alt)) = self.image.take() {
+                    self.inlines.push(Inline::Image { alt, url, link: self.link.clone() });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// 本文を解析してブロックの木にする
+fn parse_content(app_data: &NostrStatusAppInternal, content: &str) -> Vec<ContentBlock> {
+    let mut builder = ContentBuilder {
+        app_data,
+        content,
+        containers: vec![Container::Root(Vec::new())],
+        inlines: Vec::new(),
+        source: None,
+        style: InlineStyle::default(),
+        link: None,
+        image: None,
+        code: None,
+        html: None,
+        heading_level: None,
+        heading_count: 0,
+    };
+    for (event, range) in Parser::new_ext(content, PARSER_OPTIONS).into_offset_iter() {
+        builder.event(event, range);
+    }
+    builder.flush_inlines();
+    match builder.containers.into_iter().next() {
+        Some(Container::Root(blocks)) => blocks,
+        _ => Vec::new(),
+    }
+}
+
+/// A heading of an article, used for the outline.
+pub struct Heading {
+    pub level: usize,
+    pub title: String,
+}
+
+fn collect_headings(blocks: &[ContentBlock], headings: &mut Vec<Heading>) {
+    for block in blocks {
+        match block {
+            ContentBlock::Heading { level, inlines, .. } => headings.push(Heading {
+                level: *level,
+                title: inline_plain_text(inlines).replace('\n', " ").trim().to_string(),
+            }),
+            ContentBlock::BlockQuote(blocks) => collect_headings(blocks, headings),
+            ContentBlock::List { items, .. } => {
+                for item in items {
+                    collect_headings(&item.blocks, headings);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Content parsed once and reused across frames: the block tree, the outline,
+/// the plain text highlights are matched against, and the `nostr:` references.
+pub struct RenderedContent {
+    content: String,
+    blocks: Vec<ContentBlock>,
+    references: Vec<Nip19>,
+    /// Headings in the order `draw_nostr_content` places its anchors. Headings
+    /// inside lists and block quotes are included; `#` lines inside code blocks are not.
+    pub headings: Vec<Heading>,
+    /// The article as the plain text the reader shows, with `nostr:` URIs replaced
+    /// by their labels. Highlights are selected from and marked against this text,
+    /// the same way other clients quote a passage in a kind 9802 event.
+    pub plain_text: String,
+}
+
+// 同時に表示する本文は多くないので、これを超えたら作り直す
+const MAX_RENDERED_CONTENTS: usize = 16;
+
+/// Returns the parsed content for `key` (the article id, or a fixed key for
+/// previews), parsing it again only when the content changed or the resolved
+/// `nostr:` references were updated.
+pub fn rendered_content(app_data: &mut NostrStatusAppInternal, key: &str, content: &str) -> Arc<RenderedContent> {
+    if let Some(rendered) = app_data.rendered_contents.get(key) {
+        if rendered.content == content {
+            return rendered.clone();
+        }
+    }
+
+    let blocks = parse_content(app_data, content);
+    let mut headings = Vec::new();
+    collect_headings(&blocks, &mut headings);
+    let mut parts = Vec::new();
+    plain_text_parts(&blocks, &mut parts);
+    let plain_text = parts
+        .iter()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let rendered = Arc::new(RenderedContent {
+        content: content.to_string(),
+        blocks,
+        references: nip23::nostr_uris(content).into_iter().map(|(_, entity)| entity).collect(),
+        headings,
+        plain_text,
+    });
+
+    if app_data.rendered_contents.len() >= MAX_RENDERED_CONTENTS {
+        app_data.rendered_contents.clear();
+    }
+    app_data.rendered_contents.insert(key.to_string(), rendered.clone());
+    rendered
+}
+
+// コードブロックの言語名を syntect が認識する拡張子に変換する
+fn syntax_extension(language: &str) -> String {
+    let language = language.to_lowercase();
+    let extension = match language.as_str() {
+        "rust" => "rs",
+        "python" => "py",
+        "javascript" | "node" => "js",
+        "typescript" => "ts",
+        "shell" | "bash" | "zsh" | "console" => "sh",
+        "ruby" => "rb",
+        "golang" => "go",
+        "c++" => "cpp",
+        "csharp" | "c#" => "cs",
+        "markdown" => "md",
+        "yml" => "yaml",
+        "haskell" => "hs",
+        other => other,
+    };
+    extension.to_string()
+}
+
+fn short_bech32(bech32: &str) -> String {
+    if bech32.chars().count() <= 16 {
+        return bech32.to_string();
+    }
+    let head: String = bech32.chars().take(10).collect();
+    let tail: String = bech32.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect();
+    format!("{head}...{tail}")
+}
+
+// nostr: URI をリンクにしたときの表示名 (プロフィールは @名前、記事はタイトル)
+fn reference_label(app_data: &NostrStatusAppInternal, entity: &Nip19, bech32: &str) -> String {
+    match entity {
+        Nip19::Pubkey(public_key) => {
+            let name = app_data.nostr_ref_profiles.get(public_key).map(|m| display_name(public_key, m));
+            format!("@{}", name.unwrap_or_else(|| short_bech32(bech32)))
+        }
+        Nip19::Profile(profile) => {
+            let name = app_data
+                .nostr_ref_profiles
+                .get(&profile.public_key)
+                .map(|m| display_name(&profile.public_key, m));
+            format!("@{}", name.unwrap_or_else(|| short_bech32(bech32)))
+        }
+        _ => nip23::reference_key(entity)
+            .and_then(|key| app_data.nostr_ref_events.get(&key))
+            .map(|post| post.title.clone())
+            .filter(|title| !title.is_empty())
+            .unwrap_or_else(|| short_bech32(bech32)),
+    }
+}
+
+fn is_quotable(entity: &Nip19) -> bool {
+    matches!(entity, Nip19::EventId(_) | Nip19::Event(_) | Nip19::Coordinate(_))
+}
+
+// 本文中の画像。読み込み中と失敗時は代わりの枠を表示する
+fn draw_article_image(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    alt: &str,
+    url: &str,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let max_width = ui.available_width();
+    match app_data.image_state(url, ImageKind::ArticleImage).cloned() {
+        Some(ImageState::Loaded(texture_handle)) => {
+            let image = egui::Image::new(&texture_handle).max_width(max_width).corner_radius(4.0);
+            let response = ui.add(image);
+            if !alt.is_empty() {
+                response.on_hover_text(alt);
+            }
+        }
+        Some(ImageState::Failed) => {
+            ui.horizontal_wrapped(|ui| {
+                let label = if alt.is_empty() { "画像を読み込めませんでした" } else { alt };
+                ui.label(egui::RichText::new(label).weak());
+                ui.hyperlink_to("画像を開く", url);
+            });
+        }
+        image_state => {
+            if image_state.is_none() && !urls_to_load.iter().any(|(u, _)| u == url) {
+                urls_to_load.push((url.to_string(), ImageKind::ArticleImage));
+            }
+            let (rect, _) = ui.allocate_exact_size(egui::vec2(max_width, 200.0), egui::Sense::hover());
+            ui.painter().rect_filled(rect, 4.0, ui.visuals().widgets.inactive.bg_fill);
+            ui.put(rect, egui::Spinner::new());
+        }
+    }
+}
+
+// ハイライトされた一節のうち、表示している文章と照らし合わせる部分
+// 段落をまたぐものは行ごとに探す (短すぎる行は誤一致を避けるため除く)
+fn highlight_passages(highlight: &Highlight) -> Vec<&str> {
+    let text = highlight.content.trim();
+    if text.is_empty() {
+        return Vec::new();
+    }
+    let mut passages = vec![text];
+    if text.contains('\n') {
+        passages.extend(text.lines().map(str::trim).filter(|line| line.chars().count() >= 8));
+    }
+    passages
+}
+
+// 文章の中でハイライトされている範囲 (バイト位置) とハイライトのインデックス
+fn highlight_marks(text: &str, highlights: &[Highlight]) -> Vec<(Range<usize>, usize)> {
+    let mut marks = Vec::new();
+    for (i, highlight) in highlights.iter().enumerate() {
+        for passage in highlight_passages(highlight) {
+            marks.extend(text.match_indices(passage).map(|(start, _)| (start..start + passage.len(), i)));
+        }
+    }
+    marks
+}
+
+fn inline_plain_text(inlines: &[Inline]) -> String {
+    inlines
+        .iter()
+        .filter_map(|inline| match inline {
+            Inline::Text { text, .. } => Some(text.as_str()),
+            Inline::Image { .. } => None,
+        })
+        .collect()
+}
+
+fn plain_text_parts(blocks: &[ContentBlock], parts: &mut Vec<String>) {
+    for block in blocks {
+        match block {
+            ContentBlock::Paragraph(inlines) | ContentBlock::Heading { inlines, .. } => {
+                parts.push(inline_plain_text(inlines));
+            }
+            ContentBlock::Code { code, .. } => parts.push(code.clone()),
+            ContentBlock::Html(html) => parts.push(html.clone()),
+            ContentBlock::BlockQuote(blocks) => plain_text_parts(blocks, parts),
+            ContentBlock::List { items, .. } => {
+                for item in items {
+                    plain_text_parts(&item.blocks, parts);
+                }
+            }
+            ContentBlock::Table { header, rows } => {
+                for row in std::iter::once(header).chain(rows) {
+                    let cells: Vec<String> = row.iter().map(|cell| inline_plain_text(cell)).collect();
+                    parts.push(cells.join("\t"));
+                }
+            }
+            ContentBlock::Quote(_) | ContentBlock::Rule => {}
+        }
+    }
+}
+
+// 言語に合わせて色分けしたコードブロック (配色はアプリのテーマに合わせる)
+fn draw_code_block(ui: &mut egui::Ui, theme: AppTheme, language: &str, code: &str) {
+    let code = code.strip_suffix('\n').unwrap_or(code);
+    let font_size = egui::TextStyle::Monospace.resolve(ui.style()).size;
+    let code_theme = match theme {
+        AppTheme::Light => CodeTheme::light(font_size),
+        AppTheme::Dark => CodeTheme::dark(font_size),
+    };
+    let layout_job = syntax_highlighting::highlight(ui.ctx(), ui.style(), &code_theme, code, &syntax_extension(language));
+
+    let frame = egui::Frame {
+        inner_margin: egui::Margin::same(8),
+        corner_radius: 6.0.into(),
+        fill: ui.visuals().extreme_bg_color,
+        stroke: ui.visuals().widgets.noninteractive.bg_stroke,
+        ..Default::default()
+    };
+    frame.show(ui, |ui| {
+        ui.set_width(ui.available_width());
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new(language).small().weak());
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                // コピーした後はボタンからマウスが離れるまで表示を変える
+                let copied_id = ui.id().with("copied");
+                let copied = ui.memory(|m| m.data.get_temp::<bool>(copied_id).unwrap_or(false));
+                let button = ui.small_button(if copied { "コピーしました" } else { "コピー" });
+                if button.clicked() {
+                    ui.ctx().copy_text(code.to_string());
+                    ui.memory_mut(|m| m.data.insert_temp(copied_id, true));
+                } else if copied && !button.hovered() {
+                    ui.memory_mut(|m| m.data.remove::<bool>(copied_id));
+                }
+            });
+        });
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.add(egui::Label::new(layout_job).selectable(true).extend());
+        });
+    });
+}
+
+// まだ取得していない参照先をまとめて取得する
+fn request_references(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    references: &[Nip19],
+) {
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    let mut keys = Vec::new();
+    let mut entities = Vec::new();
+    for entity in references {
+        let Some(key) = nip23::reference_key(entity) else {
+            continue;
+        };
+        if app_data.requested_nostr_refs.insert(key.clone()) {
+            keys.push(key);
+            entities.push(entity.clone());
+        }
+    }
+    if entities.is_empty() {
+        return;
+    }
+
+    let cache_db = app_data.cache_db.clone();
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = resolve_nostr_references(&cache_db, &client, entities).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        match result {
+            Ok(resolved) => {
+                data.nostr_ref_profiles.extend(resolved.profiles);
+                data.nostr_ref_events.extend(resolved.events);
+                // リンクの表示名と引用が変わるので、解析済みの本文を作り直す
+                data.rendered_contents.clear();
+            }
+            Err(e) => eprintln!("Failed to resolve nostr references: {e}"),
+        }
+        for key in keys {
+            let is_profile = data.nostr_ref_profiles.keys().any(|public_key| public_key.to_hex() == key);
+            if !is_profile && !data.nostr_ref_events.contains_key(&key) {
+                data.missing_nostr_refs.insert(key);
+            }
+        }
+        data.should_repaint = true;
+    });
+}
+
+/// Opens the in-app page for a `nostr:` reference: the article for addressable
+/// events, otherwise the author's profile.
+pub fn open_nostr_entity(app_data: &mut NostrStatusAppInternal, entity: &Nip19) {
+    match entity {
+        Nip19::Pubkey(public_key) => app_data.open_user_profile(*public_key),
+        Nip19::Profile(profile) => app_data.open_user_profile(profile.public_key),
+        Nip19::Coordinate(naddr) => app_data.open_article(naddr.coordinate.clone()),
+        Nip19::EventId(_) | Nip19::Event(_) => {
+            let resolved = nip23::reference_key(entity).and_then(|key| app_data.nostr_ref_events.get(&key).cloned());
+            match resolved {
+                Some(post) if post.kind.is_addressable() => app_data.open_article(post.coordinate()),
+                Some(post) => app_data.open_user_profile(post.author_pubkey),
+                None => {
+                    if let Nip19::Event(event) = entity {
+                        if let Some(author) = event.author {
+                            app_data.open_user_profile(author);
+                        }
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// 引用カード。クリックされたら true を返す
+fn draw_quote_card(ui: &mut egui::Ui, app_data: &NostrStatusAppInternal, entity: &Nip19) -> bool {
+    let key = nip23::reference_key(entity).unwrap_or_default();
+    let post = app_data.nostr_ref_events.get(&key);
+    let frame = egui::Frame {
+        inner_margin: egui::Margin::same(10),
+        corner_radius: 6.0.into(),
+        fill: app_data.current_theme.card_background_color(),
+        stroke: ui.visuals().widgets.noninteractive.bg_stroke,
+        ..Default::default()
+    };
+    let response = frame.show(ui, |ui| {
+        ui.set_width(ui.available_width());
+        match post {
+            Some(post) => {
+                let author = display_name(&post.author_pubkey, &post.author_metadata);
+                ui.label(egui::RichText::new(author).small().weak());
+                if !post.title.is_empty() {
+                    ui.label(egui::RichText::new(&post.title).strong());
+                }
+                ui.label(&post.summary);
+            }
+            None if app_data.missing_nostr_refs.contains(&key) => {
+                let bech32 = entity.to_bech32().unwrap_or_default();
+                ui.label(egui::RichText::new(format!("引用先が見つかりませんでした ({})", short_bech32(&bech32))).weak());
+            }
+            None => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(egui::RichText::new("引用を読み込んでいます...").weak());
+                });
+            }
+        }
+    });
+    response
+        .response
+        .interact(egui::Sense::click())
+        .on_hover_cursor(egui::CursorIcon::PointingHand)
+        .clicked()
+}
+
+// 描画しながら集める状態
+struct RenderState<'a> {
+    highlights: &'a [Highlight],
+    current_heading: Option<usize>,
+    clicked: Option<Nip19>,
+    block_id: usize,
+}
+
+const BLOCK_SPACING: f32 = 8.0;
+
+fn heading_scale(level: usize) -> f32 {
+    match level {
+        1 => 1.8,
+        2 => 1.5,
+        3 => 1.3,
+        4 => 1.15,
+        _ => 1.0,
+    }
+}
+
+// ポインターの下にある文字のインデックス
+fn hovered_char(galley: &egui::Galley, pos: egui::Vec2) -> Option<usize> {
+    let index = galley.cursor_from_pos(pos).index;
+    [index.checked_sub(1), Some(index)].into_iter().flatten().find(|&i| {
+        let start = galley.pos_from_cursor(egui::text::CCursor::new(i));
+        let end = galley.pos_from_cursor(egui::text::CCursor::new(i + 1));
+        start.min.y == end.min.y && egui::Rect::from_min_max(start.min, end.max).contains(pos.to_pos2())
+    })
+}
+
+// nostr: リンクはブラウザで開かずにアプリ内で表示する
+fn open_link(ui: &egui::Ui, url: &str, state: &mut RenderState) {
+    match url.strip_prefix("nostr:") {
+        Some(bech32) => match Nip19::from_bech32(bech32) {
+            Ok(entity) => state.clicked = Some(entity),
+            Err(e) => eprintln!("Failed to parse {url}: {e}"),
+        },
+        None => ui.ctx().open_url(egui::OpenUrl::new_tab(url)),
+    }
+}
+
+fn styled_text(ui: &egui::Ui, text: &str, style: InlineStyle, is_link: bool, heading_level: Option<usize>) -> egui::RichText {
+    let mut rich_text = egui::RichText::new(text);
+    if let Some(level) = heading_level {
+        let body_size = egui::TextStyle::Body.resolve(ui.style()).size;
+        rich_text = rich_text.size(body_size * heading_scale(level)).strong();
+    }
+    if style.strong {
+        rich_text = rich_text.strong();
+    }
+    if style.emphasis {
+        rich_text = rich_text.italics();
+    }
+    if style.strikethrough {
+        rich_text = rich_text.strikethrough();
+    }
+    if style.code {
+        rich_text = rich_text.code();
+    }
+    if is_link {
+        rich_text = rich_text.color(ui.visuals().hyperlink_color);
+    }
+    rich_text
+}
+
+// 画像に挟まれていない一続きの文章を1つの LayoutJob にして折り返す
+// リンクとハイライトはポインターの下の文字から探す
+fn draw_text_run(ui: &mut egui::Ui, inlines: &[Inline], heading_level: Option<usize>, state: &mut RenderState) {
+    let spans: Vec<(&str, InlineStyle, Option<&str>)> = inlines
+        .iter()
+        .filter_map(|inline| match inline {
+            Inline::Text { text, style, link } => Some((text.as_str(), *style, link.as_deref())),
+            Inline::Image { .. } => None,
+        })
+        .collect();
+    if spans.iter().all(|(text, ..)| text.trim().is_empty()) {
+        return;
+    }
+    let run_text: String = spans.iter().map(|(text, ..)| *text).collect();
+    let marks = highlight_marks(&run_text, state.highlights);
+    let char_index = |byte: usize| run_text[..byte].chars().count();
+
+    let mut job = egui::text::LayoutJob::default();
+    // リンクの文字の範囲 (文字数で数える) とリンク先
+    let mut links: Vec<(Range<usize>, &str)> = Vec::new();
+    let mut offset = 0;
+    for (text, style, link) in &spans {
+        let end = offset + text.len();
+        // ハイライトの境目で区切って背景色を付ける
+        let mut cuts: Vec<usize> = marks
+            .iter()
+            .flat_map(|(range, _)| [range.start, range.end])
+            .filter(|cut| *cut > offset && *cut < end)
+            .collect();
+        cuts.push(end);
+        cuts.sort_unstable();
+        cuts.dedup();
+        let mut start = offset;
+        for cut in cuts {
+            let mut rich_text = styled_text(ui, &run_text[start..cut], *style, link.is_some(), heading_level);
+            if marks.iter().any(|(range, _)| range.start <= start && cut <= range.end) {
+                rich_text = rich_text.background_color(egui::Color32::from_rgba_unmultiplied(255, 210, 0, 96));
+            }
+            rich_text.append_to(&mut job, ui.style(), egui::FontSelection::Default, egui::Align::Center);
+            start = cut;
+        }
+        if let Some(link) = link {
+            links.push((char_index(offset)..char_index(end), link));
+        }
+        offset = end;
+    }
+    job.wrap.max_width = ui.available_width();
+    let galley = ui.fonts(|f| f.layout_job(job));
+    let response = ui.add(egui::Label::new(galley.clone()).sense(egui::Sense::click()));
+
+    let Some(index) = response
+        .hover_pos()
+        .and_then(|pointer| hovered_char(&galley, pointer - response.rect.min))
+    else {
+        return;
+    };
+    if let Some((_, url)) = links.iter().find(|(range, _)| range.contains(&index)) {
+        ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+        if response.clicked() {
+            open_link(ui, url, state);
+        }
+        response.on_hover_text_at_pointer(*url);
+        return;
+    }
+    let byte = run_text.char_indices().nth(index).map_or(run_text.len(), |(i, _)| i);
+    let mut matched: Vec<usize> = marks
+        .iter()
+        .filter(|(range, _)| range.contains(&byte))
+        .map(|(_, i)| *i)
+        .collect();
+    matched.sort_unstable();
+    matched.dedup();
+    if !matched.is_empty() {
+        let summaries: Vec<String> = matched
+            .iter()
+            .filter_map(|i| state.highlights.get(*i))
+            .map(highlight_view::highlight_summary)
+            .collect();
+        response.on_hover_text_at_pointer(format!("ハイライト: {}", summaries.join(" / ")));
+    }
+}
+
+fn draw_inline_image(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    alt: &str,
+    url: &str,
+    link: Option<&str>,
+    state: &mut RenderState,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    // http(s) 以外の画像はキャッシュできないので代替テキストのリンクにする
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        if ui.link(if alt.is_empty() { url } else { alt }).clicked() {
+            open_link(ui, link.unwrap_or(url), state);
+        }
+        return;
+    }
+    ui.add_space(5.0);
+    let response = ui.scope(|ui| draw_article_image(ui, app_data, alt, url, urls_to_load)).response;
+    if let Some(link) = link {
+        let response = response.interact(egui::Sense::click()).on_hover_cursor(egui::CursorIcon::PointingHand);
+        if response.clicked() {
+            open_link(ui, link, state);
+        }
+    }
+    ui.add_space(5.0);
+}
+
+fn draw_inlines(
+    ui: &mut egui::Ui,
+    app_data: &NostrStatusAppInternal,
+    inlines: &[Inline],
+    heading_level: Option<usize>,
+    state: &mut RenderState,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let mut start = 0;
+    for (i, inline) in inlines.iter().enumerate() {
+        if let Inline::Image { alt, url, link } = inline {
+            draw_text_run(ui, &inlines[start..i], heading_level, state);
+            draw_inline_image(ui, app_data, alt, url, link.as_deref(), state, urls_to_load);
+            start = i + 1;
+        }
+    }
+    draw_text_run(ui, &inlines[start..], heading_level, state);
+}
+
+fn draw_blocks(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    blocks: &[ContentBlock],
+    state: &mut RenderState,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    for (i, block) in blocks.iter().enumerate() {
+        if i > 0 {
+            ui.add_space(BLOCK_SPACING);
+        }
+        state.block_id += 1;
+        ui.push_id(state.block_id, |ui| draw_block(ui, app_data, block, state, urls_to_load));
+    }
+}
+
+fn draw_block(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    block: &ContentBlock,
+    state: &mut RenderState,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    match block {
+        ContentBlock::Paragraph(inlines) => draw_inlines(ui, app_data, inlines, None, state, urls_to_load),
+        ContentBlock::Heading { level, index, inlines } => {
+            // 表示範囲の上端より上にある最後の見出しを現在の見出しとする
+            if ui.cursor().top() <= ui.clip_rect().top() + 40.0 {
+                state.current_heading = Some(*index);
+            }
+            if app_data.scroll_to_heading == Some(*index) {
+                ui.scroll_to_cursor(Some(egui::Align::TOP));
+                app_data.scroll_to_heading = None;
+            }
+            ui.add_space(BLOCK_SPACING);
+            draw_inlines(ui, app_data, inlines, Some(*level), state, urls_to_load);
+        }
+        ContentBlock::Quote(entity) => {
+            if draw_quote_card(ui, app_data, entity) {
+                state.clicked = Some(entity.clone());
+            }
+        }
+        ContentBlock::Code { language, code } => draw_code_block(ui, app_data.current_theme, language, code),
+        ContentBlock::BlockQuote(blocks) => {
+            let frame = egui::Frame::new().inner_margin(egui::Margin { left: 12, ..Default::default() });
+            let response = frame.show(ui, |ui| draw_blocks(ui, app_data, blocks, state, urls_to_load));
+            let rect = response.response.rect;
+            let stroke = egui::Stroke::new(3.0, ui.visuals().weak_text_color());
+            ui.painter().vline(rect.left() + 2.0, rect.y_range(), stroke);
+        }
+        ContentBlock::List { start, items } => {
+            for (i, item) in items.iter().enumerate() {
+                ui.horizontal_top(|ui| {
+                    ui.add_space(8.0);
+                    match (item.checked, start) {
+                        (Some(mut checked), _) => {
+                            ui.add_enabled(false, egui::Checkbox::without_text(&mut checked));
+                        }
+                        (None, Some(start)) => {
+                            ui.label(format!("{}.", start + i as u64));
+                        }
+                        (None, None) => {
+                            ui.label("•");
+                        }
+                    }
+                    ui.vertical(|ui| draw_blocks(ui, app_data, &item.blocks, state, urls_to_load));
+                });
+            }
+        }
+        ContentBlock::Table { header, rows } => {
+            egui::Frame::group(ui.style()).show(ui, |ui| {
+                egui::Grid::new("table").striped(true).show(ui, |ui| {
+                    // 見出し行は一番小さい見出しと同じく太字にする
+                    for cell in header {
+                        ui.vertical(|ui| draw_inlines(ui, app_data, cell, Some(6), state, urls_to_load));
+                    }
+                    ui.end_row();
+                    for row in rows {
+                        for cell in row {
+                            ui.vertical(|ui| draw_inlines(ui, app_data, cell, None, state, urls_to_load));
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+        ContentBlock::Rule => {
+            ui.separator();
+        }
+        ContentBlock::Html(html) => {
+            ui.label(egui::RichText::new(html.trim_end()).monospace().weak());
+        }
+    }
+}
+
+/// Renders article content in a single pulldown-cmark pass, showing `nostr:`
+/// profiles as named links and quoted events / articles as cards. Clicking either
+/// opens it in the app. Fenced code blocks are syntax highlighted with a copy
+/// button, and images are loaded through the LMDB-backed `image_cache`.
+/// Passages of the rendered text that match one of `highlights` are marked.
+/// The parsed content is cached under `key` (see `rendered_content`).
+#[allow(clippy::too_many_arguments)]
+pub fn draw_nostr_content(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    key: &str,
+    content: &str,
+    highlights: &[Highlight],
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let rendered = rendered_content(app_data, key, content);
+    request_references(app_data, app_data_arc, runtime_handle, &rendered.references);
+
+    let mut state = RenderState {
+        highlights,
+        current_heading: None,
+        clicked: None,
+        block_id: 0,
+    };
+    draw_blocks(ui, app_data, &rendered.blocks, &mut state, urls_to_load);
+
+    app_data.current_heading = state.current_heading;
+    if let Some(entity) = state.clicked {
+        open_nostr_entity(app_data, &entity);
+    }
+}
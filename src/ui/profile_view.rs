@@ -40,7 +40,7 @@ pub fn draw_profile_view(
 
                 let picture_url = &app_data.editable_profile.picture;
                 if !picture_url.is_empty() {
-                    let image_state = app_data.image_cache.get(picture_url).cloned();
+                    let image_state = app_data.image_state(picture_url, ImageKind::ProfilePicture).cloned();
                     match image_state {
                         Some(ImageState::Loaded(texture_handle)) => {
                             let image_widget = egui::Image::new(&texture_handle)
@@ -217,26 +217,10 @@ pub fn draw_profile_view(
                 ui.add_space(20.0);
 
                 if ui.button(egui::RichText::new(logout_button_text).color(egui::Color32::RED).strong()).clicked() {
-                    let client_to_shutdown = app_data.nostr_client.take();
-
-                    app_data.is_logged_in = false;
-                    app_data.my_keys = None;
-                    app_data.followed_pubkeys.clear();
-                    app_data.followed_pubkeys_display.clear();
-                    app_data.timeline_posts.clear();
-                    app_data.article_title_input.clear();
-                    app_data.article_content_input.clear();
-                    app_data.passphrase_input.clear();
-                    app_data.confirm_passphrase_input.clear();
-                    app_data.secret_key_input.clear();
-                    app_data.current_tab = AppTab::Home;
-                    app_data.nip01_profile_display.clear();
-                    app_data.editable_profile = ProfileMetadata::default();
-                    app_data.profile_fetch_status = "ログインしてください".to_string();
-                    app_data.should_repaint = true;
+                    let clients_to_shutdown = app_data.reset_account_state();
                     println!("Logged out.");
 
-                    if let Some(client) = client_to_shutdown {
+                    for client in clients_to_shutdown {
                         runtime_handle.spawn(async move {
                             client.shutdown().await;
                         });
@@ -0,0 +1,146 @@
+use eframe::egui;
+use nostr::Timestamp;
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Handle;
+
+use crate::nostr_client::{remove_scheduled_article, reschedule_article, save_scheduled_article};
+use crate::types::{NostrStatusAppInternal, ScheduledArticle};
+
+// 予約日時の入力形式 (ローカル時刻)
+pub const SCHEDULE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+pub fn format_local_time(timestamp: Timestamp) -> String {
+    chrono::DateTime::<chrono::Utc>::from_timestamp(timestamp.as_u64() as i64, 0)
+        .map(|dt| dt.with_timezone(&chrono::Local).format(SCHEDULE_TIME_FORMAT).to_string())
+        .unwrap_or_default()
+}
+
+// "2025-01-31 09:00" 形式のローカル時刻を解析する
+pub fn parse_local_time(input: &str) -> Option<Timestamp> {
+    let naive = chrono::NaiveDateTime::parse_from_str(input.trim(), SCHEDULE_TIME_FORMAT).ok()?;
+    local_timestamp(naive)
+}
+
+// タイムゾーンのないローカル日時をタイムスタンプにする
+pub fn local_timestamp(naive: chrono::NaiveDateTime) -> Option<Timestamp> {
+    let local = naive.and_local_timezone(chrono::Local).earliest()?;
+    u64::try_from(local.timestamp()).ok().map(Timestamp::from)
+}
+
+pub fn draw_publish_queue_view(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: Handle,
+) {
+    ui.heading("予約投稿");
+    ui.add_space(10.0);
+
+    if app_data.publish_queue.is_empty() {
+        ui.label("予約中の記事はありません。");
+        return;
+    }
+
+    let mut to_cancel = None;
+    let mut to_reschedule = None;
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        let card_frame = egui::Frame {
+            inner_margin: egui::Margin::same(12),
+            corner_radius: 8.0.into(),
+            fill: app_data.current_theme.card_background_color(),
+            ..Default::default()
+        };
+
+        for item in &app_data.publish_queue {
+            let key = item.coordinate_key();
+            card_frame.show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                let title = item.title();
+                ui.label(egui::RichText::new(if title.is_empty() { "(無題)" } else { title.as_str() }).strong());
+                ui.label(format!("公開予定: {}", format_local_time(item.publish_at)));
+                if let Some(error) = &item.last_error {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!(
+                            "送信失敗 ({}回): {} / 次の再試行: {}",
+                            item.attempts,
+                            error,
+                            format_local_time(item.next_attempt_at)
+                        ),
+                    );
+                }
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    let input = app_data
+                        .reschedule_inputs
+                        .entry(key.clone())
+                        .or_insert_with(|| format_local_time(item.publish_at));
+                    ui.add(egui::TextEdit::singleline(input).desired_width(140.0).hint_text("YYYY-MM-DD HH:MM"));
+                    if ui.button("日時を変更").clicked() {
+                        match parse_local_time(input) {
+                            Some(publish_at) => to_reschedule = Some((item.clone(), publish_at)),
+                            None => app_data.publish_queue_status = "日時の形式が正しくありません".to_string(),
+                        }
+                    }
+                    if ui.button("キャンセル").clicked() {
+                        to_cancel = Some(key.clone());
+                    }
+                });
+            });
+            ui.add_space(10.0);
+        }
+    });
+
+    if !app_data.publish_queue_status.is_empty() {
+        ui.label(egui::RichText::new(&app_data.publish_queue_status).small().weak());
+    }
+
+    if let Some(key) = to_cancel {
+        remove_scheduled_article(&app_data.cache_db, &key);
+        app_data.publish_queue.retain(|item| item.coordinate_key() != key);
+        app_data.reschedule_inputs.remove(&key);
+    }
+
+    if let Some((item, publish_at)) = to_reschedule {
+        let Some(keys) = app_data.my_keys.clone() else {
+            return;
+        };
+        let cache_db = app_data.cache_db.clone();
+        let cloned_app_data_arc = app_data_arc.clone();
+        let original_id = item.event.id;
+        runtime_handle.spawn(async move {
+            let result = reschedule_article(&keys, &item, publish_at).await;
+            let mut data = cloned_app_data_arc.lock().unwrap();
+            match result {
+                // 変更中にキャンセルされたり送信されたりした予約は戻さない
+                Ok(_) if !data.publish_queue.iter().any(|queued| queued.event.id == original_id) => {
+                    data.publish_queue_status = "予約が取り消されたか送信済みのため、日時を変更しませんでした".to_string();
+                }
+                Ok(rescheduled) => {
+                    save_scheduled_article(&cache_db, &rescheduled);
+                    let key = rescheduled.coordinate_key();
+                    data.publish_queue.retain(|queued| queued.coordinate_key() != key);
+                    data.publish_queue.push(rescheduled);
+                    data.publish_queue.sort_by_key(|queued| queued.publish_at);
+                    data.publish_queue_status = format!("予約日時を変更しました ({})", format_local_time(publish_at));
+                }
+                Err(e) => {
+                    eprintln!("Failed to reschedule article: {e}");
+                    data.publish_queue_status = format!("予約日時の変更に失敗しました: {e}");
+                }
+            }
+            data.should_repaint = true;
+        });
+    }
+}
+
+// キューに記事を追加する (同じ記事の予約は置き換える)
+pub fn enqueue_article(app_data: &mut NostrStatusAppInternal, item: ScheduledArticle) {
+    save_scheduled_article(&app_data.cache_db, &item);
+    let key = item.coordinate_key();
+    app_data.publish_queue.retain(|queued| queued.coordinate_key() != key);
+    app_data.reschedule_inputs.remove(&key);
+    app_data.publish_queue.push(item);
+    app_data.publish_queue.sort_by_key(|queued| queued.publish_at);
+}
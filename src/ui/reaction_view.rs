@@ -0,0 +1,142 @@
+use eframe::egui;
+use nostr::{nips::nip01::Coordinate, EventId};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    nip23,
+    nostr_client::{fetch_reaction_summaries, load_cached_reactions, publish_reaction, save_reactions},
+    types::*,
+    ui::spawn_update,
+};
+
+// メニューから選べるリアクション ("+" は いいね)
+const REACTION_CHOICES: [&str; 6] = ["+", "🔥", "😂", "👀", "🙏", "🎉"];
+
+fn coordinate_key(coordinate: &Coordinate) -> String {
+    nip23::coordinate_key(coordinate.kind, &coordinate.public_key, &coordinate.identifier)
+}
+
+// まだ集計を持っていない記事について、LMDB のキャッシュを読み、
+// キャッシュにないものだけをまとめてリレーに問い合わせる
+pub fn request_reactions(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    articles: Vec<(EventId, Coordinate)>,
+) {
+    let mut to_fetch = Vec::new();
+    for (event_id, coordinate) in articles {
+        let key = coordinate_key(&coordinate);
+        if !app_data.requested_reactions.insert(key.clone()) {
+            continue;
+        }
+        match load_cached_reactions(&app_data.cache_db, &key) {
+            Some(summary) => {
+                app_data.reaction_summaries.insert(key, summary);
+            }
+            None => to_fetch.push((event_id, coordinate)),
+        }
+    }
+    if to_fetch.is_empty() {
+        return;
+    }
+    let Some(client) = app_data.nostr_client.clone() else {
+        for (_, coordinate) in &to_fetch {
+            app_data.requested_reactions.remove(&coordinate_key(coordinate));
+        }
+        return;
+    };
+    let my_pubkey = app_data.my_keys.as_ref().map(|keys| keys.public_key());
+    let cache_db = app_data.cache_db.clone();
+    let keys: Vec<String> = to_fetch.iter().map(|(_, coordinate)| coordinate_key(coordinate)).collect();
+    spawn_update(
+        app_data_arc,
+        runtime_handle,
+        async move { fetch_reaction_summaries(&cache_db, &client, to_fetch, my_pubkey).await },
+        move |data, result| match result {
+            Ok(summaries) => data.reaction_summaries.extend(summaries),
+            Err(e) => {
+                eprintln!("Failed to fetch reactions: {e}");
+                // 次に表示したときにもう一度取得する
+                for key in &keys {
+                    data.requested_reactions.remove(key);
+                }
+            }
+        },
+    );
+}
+
+// リアクションの件数と、リアクションを選ぶメニュー。押されたリアクションの内容を返す
+pub fn draw_reaction_bar(ui: &mut egui::Ui, summary: Option<&ReactionSummary>, can_react: bool) -> Option<String> {
+    let mut chosen = None;
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 4.0;
+        if let Some(summary) = summary {
+            for (content, count) in summary.sorted_counts() {
+                let is_mine = summary.mine.iter().any(|c| c == content);
+                let text = egui::RichText::new(format!("{} {}", reaction_label(content), count)).small();
+                let response = ui.add_enabled(can_react, egui::Button::selectable(is_mine, text));
+                if response.clicked() && !is_mine {
+                    chosen = Some(content.to_string());
+                }
+            }
+        }
+        if can_react {
+            ui.menu_button(egui::RichText::new("＋").small(), |ui| {
+                ui.horizontal(|ui| {
+                    for choice in REACTION_CHOICES {
+                        if ui.button(reaction_label(choice)).clicked() {
+                            chosen = Some(choice.to_string());
+                            ui.close();
+                        }
+                    }
+                });
+            });
+        }
+    });
+    chosen
+}
+
+// 件数を先に増やしてから送信し、失敗したら元に戻す
+pub fn send_reaction(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    event_id: EventId,
+    coordinate: Coordinate,
+    content: String,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let key = coordinate_key(&coordinate);
+    let summary = app_data.reaction_summaries.entry(key.clone()).or_default();
+    if summary.mine.contains(&content) {
+        return;
+    }
+    summary.add(&content, true);
+    save_reactions(&app_data.cache_db, &key, summary);
+    app_data.should_repaint = true;
+
+    let cache_db = app_data.cache_db.clone();
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = publish_reaction(&client, &keys, event_id, &coordinate, &content).await;
+        let Err(e) = result else {
+            return;
+        };
+        eprintln!("Failed to publish reaction: {e}");
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        if let Some(summary) = data.reaction_summaries.get_mut(&key) {
+            if let Some(count) = summary.counts.get_mut(&content) {
+                *count -= 1;
+                if *count == 0 {
+                    summary.counts.remove(&content);
+                }
+            }
+            summary.mine.retain(|c| c != &content);
+            save_reactions(&cache_db, &key, summary);
+        }
+        data.should_repaint = true;
+    });
+}
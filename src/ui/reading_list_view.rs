@@ -0,0 +1,298 @@
+use eframe::egui::{self, Sense};
+use nostr::{nips::nip01::Coordinate, Keys};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    cache_db::DB_ARTICLES,
+    nostr_client::{fetch_article, save_bookmarks, sync_bookmarks, timeline_post_from_article, update_bookmark},
+    types::*,
+    ui::spawn_update,
+};
+
+// リレーから戻ってきたリストに、待っている間に変わった既読の状態を反映して保存する
+fn apply_bookmarks(data: &mut NostrStatusAppInternal, keys: &Keys, mut bookmarks: Vec<Bookmark>) {
+    for bookmark in &mut bookmarks {
+        bookmark.is_read = data.bookmark(&bookmark.coordinate_key).is_some_and(|b| b.is_read);
+    }
+    save_bookmarks(&data.cache_db, &keys.public_key(), &bookmarks);
+    data.bookmarks = bookmarks;
+}
+
+// リレーのブックマークリストを取得して手元のリストに反映する
+pub fn sync_bookmarks_from_relays(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let cache_db = app_data.cache_db.clone();
+    let local = app_data.bookmarks.clone();
+    app_data.is_syncing_bookmarks = true;
+    app_data.bookmarks_synced = true;
+    app_data.bookmark_status = "ブックマークを同期中...".to_string();
+
+    let task_keys = keys.clone();
+    spawn_update(
+        app_data_arc,
+        runtime_handle,
+        async move { sync_bookmarks(&cache_db, &client, &task_keys, local).await },
+        move |data, result| {
+            match result {
+                Ok(Some(bookmarks)) => {
+                    apply_bookmarks(data, &keys, bookmarks);
+                    data.bookmark_list_missing = false;
+                    data.bookmark_status.clear();
+                }
+                Ok(None) => {
+                    data.bookmark_list_missing = true;
+                    data.bookmark_status.clear();
+                }
+                Err(e) => {
+                    eprintln!("Failed to sync bookmarks: {e}");
+                    data.bookmark_status = format!("ブックマークの同期に失敗しました: {e}");
+                }
+            }
+            data.is_syncing_bookmarks = false;
+        },
+    );
+}
+
+// ブックマークに追加する (Some(非公開かどうか)) か、外す (None)
+pub fn set_bookmark(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    coordinate_key: String,
+    bookmark: Option<bool>,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let cache_db = app_data.cache_db.clone();
+    let local = app_data.bookmarks.clone();
+    let list_missing = app_data.bookmark_list_missing;
+    app_data.is_syncing_bookmarks = true;
+    app_data.bookmark_status = "ブックマークを更新中...".to_string();
+    app_data.should_repaint = true;
+
+    let task_keys = keys.clone();
+    spawn_update(
+        app_data_arc,
+        runtime_handle,
+        async move {
+            update_bookmark(&cache_db, &client, &task_keys, local, &coordinate_key, bookmark, list_missing).await
+        },
+        move |data, result| {
+            match result {
+                Ok(bookmarks) => {
+                    apply_bookmarks(data, &keys, bookmarks);
+                    data.bookmark_list_missing = false;
+                    data.bookmark_status.clear();
+                }
+                Err(e) => {
+                    eprintln!("Failed to update bookmarks: {e}");
+                    data.bookmark_status = format!("ブックマークの更新に失敗しました: {e}");
+                }
+            }
+            data.is_syncing_bookmarks = false;
+        },
+    );
+}
+
+// 既読・未読はローカルにだけ保存する
+pub fn mark_read(app_data: &mut NostrStatusAppInternal, coordinate_key: &str, is_read: bool) {
+    let Some(keys) = app_data.my_keys.as_ref().map(Keys::public_key) else {
+        return;
+    };
+    let Some(bookmark) = app_data.bookmarks.iter_mut().find(|b| b.coordinate_key == coordinate_key) else {
+        return;
+    };
+    if bookmark.is_read == is_read {
+        return;
+    }
+    bookmark.is_read = is_read;
+    save_bookmarks(&app_data.cache_db, &keys, &app_data.bookmarks);
+}
+
+// 記事画面のブックマークボタン
+pub fn draw_bookmark_button(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    coordinate_key: &str,
+) {
+    let enabled = !app_data.is_syncing_bookmarks;
+    if app_data.bookmark(coordinate_key).is_some() {
+        if ui.add_enabled(enabled, egui::Button::new("🔖 ブックマーク解除")).clicked() {
+            set_bookmark(app_data, app_data_arc, runtime_handle, coordinate_key.to_string(), None);
+        }
+        return;
+    }
+    ui.add_enabled_ui(enabled, |ui| {
+        ui.menu_button("🔖 ブックマーク", |ui| {
+            if ui.button("公開でブックマーク").clicked() {
+                set_bookmark(app_data, app_data_arc, runtime_handle, coordinate_key.to_string(), Some(false));
+                ui.close();
+            }
+            if ui.button("非公開でブックマーク").clicked() {
+                set_bookmark(app_data, app_data_arc, runtime_handle, coordinate_key.to_string(), Some(true));
+                ui.close();
+            }
+        });
+    });
+}
+
+// リーディングリストに表示する記事を、キャッシュ (またはリレー) から読み込む
+fn request_reading_list_posts(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    let keys: Vec<String> = app_data
+        .bookmarks
+        .iter()
+        .map(|b| b.coordinate_key.clone())
+        .filter(|key| !app_data.requested_reading_list_posts.contains(key))
+        .collect();
+    for key in keys {
+        app_data.requested_reading_list_posts.insert(key.clone());
+        let Ok(coordinate) = Coordinate::parse(&key) else {
+            continue;
+        };
+        let cache_db = app_data.cache_db.clone();
+        let client = client.clone();
+        let cloned_app_data_arc = app_data_arc.clone();
+        runtime_handle.spawn(async move {
+            let result = fetch_article(&cache_db, &client, &coordinate).await;
+            let mut data = cloned_app_data_arc.lock().unwrap();
+            match result {
+                Ok(article) => {
+                    if let Err(e) = cache_db.set_pinned(DB_ARTICLES, &key, true) {
+                        eprintln!("Failed to pin article {key}: {e}");
+                    }
+                    data.reading_list_posts.insert(key, timeline_post_from_article(&article));
+                }
+                Err(e) => eprintln!("Failed to load bookmarked article {key}: {e}"),
+            }
+            data.should_repaint = true;
+        });
+    }
+}
+
+pub fn draw_reading_list_view(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+) {
+    if !app_data.bookmarks_synced && !app_data.is_syncing_bookmarks {
+        sync_bookmarks_from_relays(app_data, &app_data_arc, &runtime_handle);
+    }
+    request_reading_list_posts(app_data, &app_data_arc, &runtime_handle);
+
+    let unread = app_data.bookmarks.iter().filter(|b| !b.is_read).count();
+    ui.horizontal(|ui| {
+        ui.heading("リーディングリスト");
+        ui.label(egui::RichText::new(format!("未読 {unread} / {}", app_data.bookmarks.len())).weak());
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.add_enabled(!app_data.is_syncing_bookmarks, egui::Button::new("リレーと同期")).clicked() {
+                sync_bookmarks_from_relays(app_data, &app_data_arc, &runtime_handle);
+            }
+            if app_data.is_syncing_bookmarks {
+                ui.spinner();
+            }
+            ui.checkbox(&mut app_data.reading_list_unread_only, "未読のみ");
+        });
+    });
+    if !app_data.bookmark_status.is_empty() {
+        ui.label(egui::RichText::new(&app_data.bookmark_status).small().weak());
+    }
+    ui.separator();
+    ui.add_space(10.0);
+
+    let bookmarks: Vec<Bookmark> = app_data
+        .bookmarks
+        .iter()
+        .filter(|b| !app_data.reading_list_unread_only || !b.is_read)
+        .cloned()
+        .collect();
+    if bookmarks.is_empty() {
+        ui.label("ブックマークした記事はありません。");
+        return;
+    }
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+    let mut to_open = None;
+    let mut read_change = None;
+    let mut to_remove = None;
+    egui::ScrollArea::vertical().id_salt("reading_list_scroll_area").show(ui, |ui| {
+        ui.set_max_width(700.0);
+        for bookmark in &bookmarks {
+            let post = app_data.reading_list_posts.get(&bookmark.coordinate_key);
+            let card_response = ui.scope_builder(egui::UiBuilder::new().sense(Sense::click()), |ui| {
+                card_frame.show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    ui.horizontal(|ui| {
+                        let marker = if bookmark.is_read { "　" } else { "●" };
+                        ui.label(egui::RichText::new(marker).color(ui.visuals().hyperlink_color));
+                        let title = match post {
+                            Some(post) if !post.title.is_empty() => post.title.clone(),
+                            Some(_) => "(無題)".to_string(),
+                            None => "読み込み中...".to_string(),
+                        };
+                        let title = egui::RichText::new(title);
+                        ui.label(if bookmark.is_read { title.weak() } else { title.strong() });
+                        if bookmark.is_private {
+                            ui.label(egui::RichText::new("🔒").small()).on_hover_text("非公開のブックマーク");
+                        }
+                    });
+                    if let Some(post) = post {
+                        let author = display_name(&post.author_pubkey, &post.author_metadata);
+                        ui.label(egui::RichText::new(author).small());
+                        ui.label(&post.summary);
+                    }
+                    ui.horizontal(|ui| {
+                        let read_text = if bookmark.is_read { "未読に戻す" } else { "既読にする" };
+                        if ui.small_button(read_text).clicked() {
+                            read_change = Some((bookmark.coordinate_key.clone(), !bookmark.is_read));
+                        }
+                        if ui
+                            .add_enabled(!app_data.is_syncing_bookmarks, egui::Button::new("削除").small())
+                            .clicked()
+                        {
+                            to_remove = Some(bookmark.coordinate_key.clone());
+                        }
+                    });
+                });
+            });
+            if card_response.response.clicked() {
+                to_open = Some(bookmark.coordinate_key.clone());
+            }
+            ui.add_space(10.0);
+        }
+    });
+
+    if let Some((key, is_read)) = read_change {
+        mark_read(app_data, &key, is_read);
+    }
+    if let Some(key) = to_remove {
+        set_bookmark(app_data, &app_data_arc, &runtime_handle, key, None);
+    }
+    if let Some(key) = to_open {
+        if let Ok(coordinate) = Coordinate::parse(&key) {
+            mark_read(app_data, &key, true);
+            app_data.open_article(coordinate);
+        }
+    }
+}
@@ -7,7 +7,7 @@ use nostr_sdk::{RelayUrl, nips::nip65::RelayMetadata, Client, ClientOptions as O
 
 use crate::{
     types::*,
-    nostr_client::{connect_to_relays_with_nip65},
+    nostr_client::{connect_to_relays_with_nip65, fetch_search_relays, publish_search_relays},
     cache_db::{DB_RELAYS},
 };
 
@@ -27,6 +27,28 @@ pub fn draw_relays_view(
     let discover_relays_label_text = "発見リレー (他ユーザーを見つけるため)";
     let default_relays_label_text = "デフォルトリレー (フォールバック用)";
     let save_nip65_button_text = "保存して発見リレーに公開";
+    let search_relays_heading_text = "検索リレー (NIP-50)";
+    let search_relays_label_text = "検索に使うリレー (kind 10007 として公開されます)";
+    let save_search_relays_button_text = "検索リレーを公開";
+
+    // 公開済みの検索リレーのリストを、編集する前に一度だけ読み込む
+    if !app_data.search_relays_loaded {
+        if let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) {
+            app_data.search_relays_loaded = true;
+            let cloned_app_data_arc = app_data_arc.clone();
+            runtime_handle.spawn(async move {
+                match fetch_search_relays(&client, &keys).await {
+                    Ok(Some(relays)) if !relays.is_empty() => {
+                        let mut data = cloned_app_data_arc.lock().unwrap();
+                        data.search_relays_editor = relays.join("\n");
+                        data.should_repaint = true;
+                    }
+                    Ok(_) => {}
+                    Err(e) => eprintln!("Failed to fetch search relays: {e}"),
+                }
+            });
+        }
+    }
 
     let card_frame = egui::Frame {
         inner_margin: egui::Margin::same(12),
@@ -212,5 +234,46 @@ pub fn draw_relays_view(
                 });
             }
         });
+
+        ui.add_space(15.0);
+
+        // --- 検索リレー ---
+        card_frame.show(ui, |ui| {
+            ui.heading(search_relays_heading_text);
+            ui.add_space(15.0);
+            ui.label(search_relays_label_text);
+            ui.add_space(5.0);
+            egui::ScrollArea::vertical().id_salt("search_relays_editor_scroll").max_height(80.0).show(ui, |ui| {
+                ui.add(egui::TextEdit::multiline(&mut app_data.search_relays_editor)
+                    .desired_width(ui.available_width()));
+            });
+
+            ui.add_space(15.0);
+            let save_search_relays_button = egui::Button::new(egui::RichText::new(save_search_relays_button_text).strong());
+            if ui.add_enabled(!app_data.is_loading, save_search_relays_button).clicked() {
+                if let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) {
+                    let search_relays = app_data.search_relays_editor.clone();
+                    app_data.is_loading = true;
+                    app_data.should_repaint = true;
+
+                    let cloned_app_data_arc = app_data_arc.clone();
+                    runtime_handle.spawn(async move {
+                        let result = publish_search_relays(&client, &keys, &search_relays).await;
+                        let mut app_data_async = cloned_app_data_arc.lock().unwrap();
+                        if let Err(e) = result {
+                            eprintln!("Failed to publish search relays: {e}");
+                            app_data_async.search_relays_status = format!("検索リレーの公開に失敗しました: {e}");
+                        } else {
+                            app_data_async.search_relays_status.clear();
+                        }
+                        app_data_async.is_loading = false;
+                        app_data_async.should_repaint = true;
+                    });
+                }
+            }
+            if !app_data.search_relays_status.is_empty() {
+                ui.label(egui::RichText::new(&app_data.search_relays_status).small().weak());
+            }
+        });
     });
 }
@@ -0,0 +1,52 @@
+use eframe::egui;
+use nostr::{nips::nip01::Coordinate, EventId};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    nip23,
+    nostr_client::{publish_repost, save_reposted_articles},
+    types::*,
+};
+
+// 「〇〇 がリポスト」の表示
+pub fn draw_reposted_by(ui: &mut egui::Ui, repost: &RepostInfo) {
+    let name = display_name(&repost.reposter_pubkey, &repost.reposter_metadata);
+    ui.label(egui::RichText::new(format!("🔁 {name} がリポスト")).small().weak());
+}
+
+// リポストボタン。押されたら true を返す (リポスト済みなら押せない)
+pub fn draw_repost_button(ui: &mut egui::Ui, app_data: &NostrStatusAppInternal, coordinate_key: &str) -> bool {
+    let is_reposted = app_data.reposted_articles.contains(coordinate_key);
+    let text = egui::RichText::new(if is_reposted { "🔁 リポスト済み" } else { "🔁 リポスト" }).small();
+    ui.add_enabled(app_data.my_keys.is_some() && !is_reposted, egui::Button::new(text))
+        .clicked()
+}
+
+pub fn send_repost(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    event_id: EventId,
+    coordinate: Coordinate,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let key = nip23::coordinate_key(coordinate.kind, &coordinate.public_key, &coordinate.identifier);
+    if !app_data.reposted_articles.insert(key.clone()) {
+        return;
+    }
+    save_reposted_articles(&app_data.cache_db, &keys.public_key(), &app_data.reposted_articles);
+    app_data.should_repaint = true;
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        if let Err(e) = publish_repost(&client, &keys, event_id, &coordinate).await {
+            eprintln!("Failed to publish repost: {e}");
+            let mut data = cloned_app_data_arc.lock().unwrap();
+            data.reposted_articles.remove(&key);
+            save_reposted_articles(&data.cache_db, &keys.public_key(), &data.reposted_articles);
+            data.should_repaint = true;
+        }
+    });
+}
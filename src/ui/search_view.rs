@@ -0,0 +1,319 @@
+use eframe::egui::{self, text::LayoutJob, Sense, TextFormat};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    nostr_client::{fetch_search_relays, search_on_relays},
+    search_index::{self, SearchHit},
+    types::*,
+};
+
+// 上部のバーの検索欄。Enter で検索する
+pub fn draw_search_box(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let response = ui.add(
+        egui::TextEdit::singleline(&mut app_data.search_query)
+            .desired_width(240.0)
+            .hint_text("🔍 記事・ユーザーを検索"),
+    );
+    if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+        start_search(app_data, app_data_arc, runtime_handle);
+    }
+}
+
+fn start_search(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    let query = app_data.search_query.trim().to_string();
+    if query.is_empty() {
+        return;
+    }
+    app_data.search_submitted_query = query.clone();
+    app_data.current_tab = AppTab::Search;
+    match app_data.search_mode {
+        SearchMode::Local => start_local_search(app_data, app_data_arc, runtime_handle, query),
+        SearchMode::Relay => start_relay_search(app_data, app_data_arc, runtime_handle, query),
+    }
+}
+
+fn start_local_search(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    query: String,
+) {
+    app_data.search_results.clear();
+    app_data.is_searching = true;
+    app_data.should_repaint = true;
+
+    let cache_db = app_data.cache_db.clone();
+    let cloned_app_data_arc = app_data_arc.clone();
+    // 索引の読み込みは LMDB への同期的なアクセスなので、ブロッキング用のスレッドで行う
+    runtime_handle.spawn_blocking(move || {
+        let result = search_index::search(&cache_db, &query);
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        if data.search_submitted_query != query {
+            return;
+        }
+        match result {
+            Ok(hits) => data.search_results = hits,
+            Err(e) => eprintln!("Failed to search articles: {e}"),
+        }
+        data.is_searching = false;
+        data.should_repaint = true;
+    });
+}
+
+// 検索リレーに問い合わせる。最初の検索のときに自分の検索リレーのリスト (kind 10007) を読み込む
+fn start_relay_search(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    query: String,
+) {
+    let (Some(client), Some(keys)) = (app_data.nostr_client.clone(), app_data.my_keys.clone()) else {
+        return;
+    };
+    let load_relay_list = !app_data.search_relays_loaded;
+    app_data.search_relays_loaded = true;
+    let mut search_relays = app_data.search_relays_editor.clone();
+    let cache_db = app_data.cache_db.clone();
+    app_data.relay_search_articles.clear();
+    app_data.relay_search_profiles.clear();
+    app_data.search_status.clear();
+    app_data.is_searching = true;
+    app_data.should_repaint = true;
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        if load_relay_list {
+            match fetch_search_relays(&client, &keys).await {
+                Ok(Some(relays)) if !relays.is_empty() => {
+                    search_relays = relays.join("\n");
+                    cloned_app_data_arc.lock().unwrap().search_relays_editor = search_relays.clone();
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to fetch search relays: {e}"),
+            }
+        }
+        let result = search_on_relays(&cache_db, &keys, &search_relays, &query).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        if data.search_submitted_query != query || data.search_mode != SearchMode::Relay {
+            return;
+        }
+        match result {
+            Ok(results) => {
+                data.relay_search_articles = results.articles;
+                data.relay_search_profiles = results.profiles;
+            }
+            Err(e) => {
+                eprintln!("Failed to search relays: {e}");
+                data.search_status = format!("リレーでの検索に失敗しました: {e}");
+            }
+        }
+        data.is_searching = false;
+        data.should_repaint = true;
+    });
+}
+
+// ヒット箇所に背景色を付けたスニペット
+fn snippet_job(ui: &egui::Ui, hit: &SearchHit) -> LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let normal = TextFormat {
+        font_id: font_id.clone(),
+        color: ui.visuals().text_color(),
+        ..Default::default()
+    };
+    let marked = TextFormat {
+        font_id,
+        color: ui.visuals().strong_text_color(),
+        background: ui.visuals().selection.bg_fill.linear_multiply(0.6),
+        ..Default::default()
+    };
+    let mut job = LayoutJob::default();
+    let mut position = 0;
+    for range in &hit.highlights {
+        job.append(&hit.snippet[position..range.start], 0.0, normal.clone());
+        job.append(&hit.snippet[range.clone()], 0.0, marked.clone());
+        position = range.end;
+    }
+    job.append(&hit.snippet[position..], 0.0, normal);
+    job
+}
+
+pub fn draw_search_view(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    ui.horizontal(|ui| {
+        ui.heading(format!("「{}」の検索結果", app_data.search_submitted_query));
+        if app_data.is_searching {
+            ui.spinner();
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let previous_mode = app_data.search_mode;
+            ui.selectable_value(&mut app_data.search_mode, SearchMode::Relay, "リレー (NIP-50)");
+            ui.selectable_value(&mut app_data.search_mode, SearchMode::Local, "キャッシュ");
+            if app_data.search_mode != previous_mode {
+                app_data.search_query = app_data.search_submitted_query.clone();
+                start_search(app_data, &app_data_arc, &runtime_handle);
+            }
+        });
+    });
+    if !app_data.search_status.is_empty() {
+        ui.label(egui::RichText::new(&app_data.search_status).small().weak());
+    }
+    ui.separator();
+    ui.add_space(10.0);
+
+    match app_data.search_mode {
+        SearchMode::Local => draw_local_results(ui, app_data),
+        SearchMode::Relay => draw_relay_results(ui, app_data, urls_to_load),
+    }
+}
+
+fn draw_local_results(ui: &mut egui::Ui, app_data: &mut NostrStatusAppInternal) {
+    if !app_data.is_searching && app_data.search_results.is_empty() {
+        ui.label("キャッシュされた記事に一致するものはありませんでした。");
+        return;
+    }
+    ui.label(egui::RichText::new(format!("{}件", app_data.search_results.len())).weak());
+    ui.add_space(5.0);
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+    let mut to_open = None;
+    egui::ScrollArea::vertical().id_salt("search_results_scroll_area").show(ui, |ui| {
+        ui.set_max_width(700.0);
+        for hit in &app_data.search_results {
+            let card_response = card_frame.show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                let title = if hit.post.title.is_empty() { "(無題)" } else { hit.post.title.as_str() };
+                ui.label(egui::RichText::new(title).strong());
+                ui.horizontal(|ui| {
+                    let author = display_name(&hit.post.author_pubkey, &hit.post.author_metadata);
+                    ui.label(egui::RichText::new(author).small());
+                    if let Some(date) =
+                        chrono::DateTime::<chrono::Utc>::from_timestamp(hit.post.created_at.as_u64() as i64, 0)
+                    {
+                        ui.label(egui::RichText::new(date.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string()).small().weak());
+                    }
+                });
+                ui.label(snippet_job(ui, hit));
+            });
+            if card_response.response.interact(Sense::click()).clicked() {
+                to_open = Some(hit.post.coordinate());
+            }
+            ui.add_space(10.0);
+        }
+    });
+    if let Some(coordinate) = to_open {
+        app_data.open_article(coordinate);
+    }
+}
+
+fn draw_relay_results(
+    ui: &mut egui::Ui,
+    app_data: &mut NostrStatusAppInternal,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    if app_data.is_searching {
+        return;
+    }
+    if app_data.relay_search_articles.is_empty() && app_data.relay_search_profiles.is_empty() {
+        ui.label("検索リレーで一致するものは見つかりませんでした。");
+        return;
+    }
+
+    let card_frame = egui::Frame {
+        inner_margin: egui::Margin::same(12),
+        corner_radius: 8.0.into(),
+        fill: app_data.current_theme.card_background_color(),
+        ..Default::default()
+    };
+    let mut article_to_open = None;
+    let mut profile_to_open = None;
+    egui::ScrollArea::vertical().id_salt("relay_search_scroll_area").show(ui, |ui| {
+        ui.set_max_width(700.0);
+        if !app_data.relay_search_profiles.is_empty() {
+            ui.label(egui::RichText::new(format!("ユーザー ({})", app_data.relay_search_profiles.len())).strong());
+            ui.add_space(5.0);
+            for (public_key, metadata) in &app_data.relay_search_profiles {
+                let row = ui.horizontal(|ui| {
+                    let avatar_size = egui::vec2(32.0, 32.0);
+                    let (avatar_rect, _) = ui.allocate_exact_size(avatar_size, Sense::hover());
+                    match app_data.image_state(&metadata.picture, ImageKind::Avatar) {
+                        Some(ImageState::Loaded(texture_handle)) => {
+                            let image_widget =
+                                egui::Image::new(texture_handle).fit_to_exact_size(avatar_size).corner_radius(4.0);
+                            ui.put(avatar_rect, image_widget);
+                        }
+                        image_state => {
+                            if image_state.is_none()
+                                && !metadata.picture.is_empty()
+                                && !urls_to_load.iter().any(|(u, _)| u == &metadata.picture)
+                            {
+                                urls_to_load.push((metadata.picture.clone(), ImageKind::Avatar));
+                            }
+                            ui.painter().rect_filled(avatar_rect, 4.0, ui.style().visuals.extreme_bg_color);
+                        }
+                    }
+                    ui.vertical(|ui| {
+                        let name = display_name(public_key, metadata);
+                        ui.horizontal(|ui| {
+                            ui.label(egui::RichText::new(name).strong());
+                            if app_data.followed_pubkeys.contains(public_key) {
+                                ui.label(egui::RichText::new("フォロー中").small().weak());
+                            }
+                        });
+                        if !metadata.nip05.is_empty() {
+                            ui.label(egui::RichText::new(&metadata.nip05).small().weak());
+                        }
+                    });
+                });
+                if row.response.interact(Sense::click()).clicked() {
+                    profile_to_open = Some(*public_key);
+                }
+                ui.add_space(6.0);
+            }
+            ui.add_space(15.0);
+        }
+
+        if !app_data.relay_search_articles.is_empty() {
+            ui.label(egui::RichText::new(format!("記事 ({})", app_data.relay_search_articles.len())).strong());
+            ui.add_space(5.0);
+            for post in &app_data.relay_search_articles {
+                let card_response = card_frame.show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    let title = if post.title.is_empty() { "(無題)" } else { post.title.as_str() };
+                    ui.label(egui::RichText::new(title).strong());
+                    let author = display_name(&post.author_pubkey, &post.author_metadata);
+                    ui.label(egui::RichText::new(author).small());
+                    ui.label(&post.summary);
+                });
+                if card_response.response.interact(Sense::click()).clicked() {
+                    article_to_open = Some(post.coordinate());
+                }
+                ui.add_space(10.0);
+            }
+        }
+    });
+    if let Some(public_key) = profile_to_open {
+        app_data.open_user_profile(public_key);
+    }
+    if let Some(coordinate) = article_to_open {
+        app_data.open_article(coordinate);
+    }
+}
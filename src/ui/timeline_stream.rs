@@ -0,0 +1,142 @@
+use nostr::{Event, Kind};
+use nostr_sdk::{Client, RelayPoolNotification, SubscriptionId};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    cache_db::LmdbCache,
+    nostr_client::{merge_timeline_posts, save_timeline, subscribe_timeline, timeline_post_from_live_event},
+    types::*,
+};
+
+// フォロー中のユーザーの新しい記事をメインのクライアントで購読する。
+// フォローが変わったら購読し直し、古い購読を待っていたタスクは終わる
+pub fn ensure_timeline_stream(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+) {
+    if !app_data.is_logged_in
+        || app_data.followed_pubkeys.is_empty()
+        || app_data.followed_pubkeys == app_data.timeline_stream_authors
+    {
+        return;
+    }
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    app_data.timeline_stream_authors = app_data.followed_pubkeys.clone();
+    let followed_pubkeys = app_data.followed_pubkeys.clone();
+    let previous = app_data.timeline_subscription.take();
+    let cache_db = app_data.cache_db.clone();
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        match subscribe_timeline(&client, &followed_pubkeys, previous).await {
+            Ok(subscription_id) => {
+                let is_current = {
+                    let mut data = cloned_app_data_arc.lock().unwrap();
+                    // 待っている間にフォローが変わったりログアウトしたりしていたら、この購読は使わない
+                    let is_current = data.timeline_stream_authors == followed_pubkeys;
+                    if is_current {
+                        data.timeline_subscription = Some(subscription_id.clone());
+                    }
+                    is_current
+                };
+                if is_current {
+                    listen_for_timeline_events(client, cache_db, subscription_id, cloned_app_data_arc).await;
+                } else {
+                    client.unsubscribe(&subscription_id).await;
+                }
+            }
+            Err(e) => eprintln!("Failed to subscribe to the timeline: {e}"),
+        }
+    });
+}
+
+// 通知を受け取り続け、購読の記事が届いたら別のタスクでカードにする
+// (プロフィールの取得を待つ間に通知を取りこぼさないように、受信のループでは待たない)
+async fn listen_for_timeline_events(
+    client: Client,
+    cache_db: LmdbCache,
+    subscription_id: SubscriptionId,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) {
+    let mut notifications = client.notifications();
+    loop {
+        let notification = match notifications.recv().await {
+            Ok(notification) => notification,
+            // 受信が追いつかずに読み飛ばした通知があっても、購読は続ける
+            Err(RecvError::Lagged(skipped)) => {
+                eprintln!("Timeline stream skipped {skipped} notifications");
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        let event = match notification {
+            RelayPoolNotification::Event { subscription_id: id, event, .. } => {
+                if id != subscription_id || event.kind != Kind::from(30023) {
+                    continue;
+                }
+                event
+            }
+            RelayPoolNotification::Shutdown => break,
+            _ => continue,
+        };
+        {
+            let data = app_data_arc.lock().unwrap();
+            if data.timeline_subscription.as_ref() != Some(&subscription_id) {
+                return;
+            }
+            if !data.followed_pubkeys.contains(&event.pubkey) {
+                continue;
+            }
+        }
+        tokio::spawn(add_live_post(client.clone(), cache_db.clone(), *event, app_data_arc.clone()));
+    }
+
+    // 通知が止まったら、次のフレームで購読し直せるように購読の状態を戻す
+    let mut data = app_data_arc.lock().unwrap();
+    if data.timeline_subscription.as_ref() == Some(&subscription_id) {
+        data.timeline_subscription = None;
+        data.timeline_stream_authors.clear();
+    }
+}
+
+async fn add_live_post(
+    client: Client,
+    cache_db: LmdbCache,
+    event: Event,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+) {
+    let post = match timeline_post_from_live_event(&cache_db, &client, &event).await {
+        Ok(post) => post,
+        Err(e) => {
+            eprintln!("Failed to load a live timeline article: {e}");
+            return;
+        }
+    };
+    let mut data = app_data_arc.lock().unwrap();
+    // 取得ボタンなどで既にタイムラインに入っている記事は知らせない
+    let key = post.coordinate_key();
+    let is_known = data
+        .timeline_posts
+        .iter()
+        .any(|p| p.coordinate_key() == key && p.created_at >= post.created_at);
+    if !is_known && merge_timeline_posts(&mut data.pending_posts, vec![post]) > 0 {
+        data.should_repaint = true;
+    }
+}
+
+// 新しい記事のお知らせが押されたら、届いていた記事をタイムラインに入れて保存する
+pub fn show_pending_posts(app_data: &mut NostrStatusAppInternal) {
+    if app_data.pending_posts.is_empty() {
+        return;
+    }
+    let pending = std::mem::take(&mut app_data.pending_posts);
+    merge_timeline_posts(&mut app_data.timeline_posts, pending);
+    if let Some(keys) = &app_data.my_keys {
+        save_timeline(&app_data.cache_db, &keys.public_key(), &app_data.timeline_posts);
+    }
+    app_data.should_repaint = true;
+}
@@ -0,0 +1,164 @@
+use eframe::egui::{self, Sense};
+use nostr::{nips::nip19::ToBech32, PublicKey};
+use std::sync::{Arc, Mutex};
+
+use crate::{nostr_client::fetch_user_articles, types::*};
+
+// 表示中のユーザーのプロフィールと記事を取得する
+fn load_user_profile(
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: &Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: &tokio::runtime::Handle,
+    public_key: PublicKey,
+) {
+    let Some(client) = app_data.nostr_client.clone() else {
+        return;
+    };
+    let cache_db = app_data.cache_db.clone();
+    app_data.is_loading_user_profile = true;
+    app_data.should_repaint = true;
+
+    let cloned_app_data_arc = app_data_arc.clone();
+    runtime_handle.spawn(async move {
+        let result = fetch_user_articles(&cache_db, &client, public_key).await;
+        let mut data = cloned_app_data_arc.lock().unwrap();
+        data.is_loading_user_profile = false;
+        data.should_repaint = true;
+        if data.viewing_profile != Some(public_key) {
+            return;
+        }
+        match result {
+            Ok((metadata, articles)) => {
+                data.nostr_ref_profiles.insert(public_key, metadata);
+                data.viewing_profile_articles = Some(articles);
+            }
+            Err(e) => {
+                eprintln!("Failed to fetch user profile: {e}");
+                data.viewing_profile_articles = Some(Vec::new());
+            }
+        }
+    });
+}
+
+pub fn draw_user_profile_view(
+    ui: &mut egui::Ui,
+    ctx: &egui::Context,
+    app_data: &mut NostrStatusAppInternal,
+    app_data_arc: Arc<Mutex<NostrStatusAppInternal>>,
+    runtime_handle: tokio::runtime::Handle,
+    urls_to_load: &mut Vec<(String, ImageKind)>,
+) {
+    let Some(public_key) = app_data.viewing_profile else {
+        app_data.current_tab = AppTab::Home;
+        return;
+    };
+    if app_data.viewing_profile_articles.is_none() && !app_data.is_loading_user_profile {
+        load_user_profile(app_data, &app_data_arc, &runtime_handle, public_key);
+    }
+
+    let npub = public_key.to_bech32().unwrap_or_default();
+    ui.horizontal(|ui| {
+        if ui.button("← Back").clicked() {
+            app_data.viewing_profile = None;
+            app_data.viewing_profile_articles = None;
+            // 記事から開いた場合は記事に戻る
+            app_data.current_tab = if app_data.viewing_article_coordinate.is_some() {
+                AppTab::ArticleView
+            } else {
+                AppTab::Home
+            };
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.button("npubをコピー").clicked() {
+                ctx.copy_text(npub.clone());
+            }
+            if app_data.followed_pubkeys.contains(&public_key) {
+                ui.label(egui::RichText::new("フォロー中").small().weak());
+            }
+        });
+    });
+    if app_data.current_tab != AppTab::UserProfile {
+        return;
+    }
+    ui.separator();
+    ui.add_space(10.0);
+
+    let metadata = app_data.nostr_ref_profiles.get(&public_key).cloned().unwrap_or_default();
+    egui::ScrollArea::vertical().id_salt("user_profile_scroll_area").show(ui, |ui| {
+        ui.set_max_width(700.0);
+        ui.horizontal(|ui| {
+            let avatar_size = egui::vec2(80.0, 80.0);
+            let (avatar_rect, _) = ui.allocate_exact_size(avatar_size, Sense::hover());
+            match app_data.image_state(&metadata.picture, ImageKind::ProfilePicture).cloned() {
+                Some(ImageState::Loaded(texture_handle)) => {
+                    let image_widget = egui::Image::new(&texture_handle)
+                        .fit_to_exact_size(avatar_size)
+                        .corner_radius(12.0);
+                    ui.put(avatar_rect, image_widget);
+                }
+                image_state => {
+                    if image_state.is_none()
+                        && !metadata.picture.is_empty()
+                        && !urls_to_load.iter().any(|(u, _)| u == &metadata.picture)
+                    {
+                        urls_to_load.push((metadata.picture.clone(), ImageKind::ProfilePicture));
+                    }
+                    ui.painter().rect_filled(avatar_rect, 12.0, ui.style().visuals.extreme_bg_color);
+                }
+            }
+            ui.add_space(15.0);
+
+            ui.vertical(|ui| {
+                ui.add_space(5.0);
+                let name = if metadata.name.is_empty() {
+                    format!("{}...{}", &npub[0..8], &npub[npub.len() - 4..])
+                } else {
+                    metadata.name.clone()
+                };
+                ui.heading(name);
+                if !metadata.nip05.is_empty() {
+                    ui.label(egui::RichText::new(&metadata.nip05).small().weak());
+                }
+                if !metadata.about.is_empty() {
+                    ui.label(&metadata.about);
+                }
+            });
+        });
+        ui.add_space(20.0);
+        ui.separator();
+        ui.add_space(10.0);
+
+        ui.heading("記事");
+        ui.add_space(10.0);
+        let Some(articles) = app_data.viewing_profile_articles.clone() else {
+            ui.spinner();
+            return;
+        };
+        if articles.is_empty() {
+            ui.label("公開されている記事はありません。");
+            return;
+        }
+
+        let card_frame = egui::Frame {
+            inner_margin: egui::Margin::same(12),
+            corner_radius: 8.0.into(),
+            fill: app_data.current_theme.card_background_color(),
+            ..Default::default()
+        };
+        for post in articles {
+            let card_response = card_frame.show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                let title = if post.title.is_empty() { "(無題)" } else { post.title.as_str() };
+                ui.label(egui::RichText::new(title).strong());
+                if let Some(date) = chrono::DateTime::<chrono::Utc>::from_timestamp(post.created_at.as_u64() as i64, 0) {
+                    ui.label(egui::RichText::new(date.with_timezone(&chrono::Local).format("%Y-%m-%d").to_string()).small().weak());
+                }
+                ui.label(&post.summary);
+            });
+            if card_response.response.interact(Sense::click()).clicked() {
+                app_data.open_article(post.coordinate());
+            }
+            ui.add_space(10.0);
+        }
+    });
+}